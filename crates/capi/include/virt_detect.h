@@ -0,0 +1,149 @@
+#ifndef VIRT_DETECT_H
+#define VIRT_DETECT_H
+
+/* 由 cbindgen 生成，请不要手动修改 */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define VD_FACTOR_BASEBOARD (1 << 0)
+
+#define VD_FACTOR_PROCESSOR (1 << 1)
+
+#define VD_FACTOR_DISK_DRIVES (1 << 2)
+
+#define VD_FACTOR_VIDEO_CONTROLLERS (1 << 3)
+
+#define VD_FACTOR_MACHINE_GUID (1 << 4)
+
+#define VD_FACTOR_CHASSIS (1 << 5)
+
+#define VD_FACTOR_BIOS (1 << 6)
+
+#define VD_FACTOR_VOLUME_SERIAL (1 << 7)
+
+#define VD_FACTOR_PRIMARY_NETWORK_ADAPTER (1 << 8)
+
+#define VD_FACTOR_MEMORY (1 << 9)
+
+#define VD_FACTOR_DISPLAY (1 << 10)
+
+#define VD_FACTOR_WINDOWS_INSTALL (1 << 11)
+
+#define VD_FACTOR_SYSTEM_UUID (1 << 12)
+
+#define VD_FACTOR_TPM (1 << 13)
+
+#define VD_FACTOR_NETWORK_ADAPTER (1 << 14)
+
+// 未提供选项时使用的因子，与 `getMachineId` 的常见用法一致
+#define VD_FACTOR_DEFAULT (((VD_FACTOR_BASEBOARD | VD_FACTOR_PROCESSOR) | VD_FACTOR_DISK_DRIVES) | VD_FACTOR_VIDEO_CONTROLLERS)
+
+typedef enum VdStatusKind {
+  VD_STATUS_KIND_ENABLED,
+  VD_STATUS_KIND_DISABLED,
+  // CPU 不支持而操作系统报告已启用 (常见于虚拟机)，或操作系统检查失败，无法确定
+  VD_STATUS_KIND_UNKNOWN,
+} VdStatusKind;
+
+typedef enum VdCpuVendor {
+  VD_CPU_VENDOR_INTEL,
+  VD_CPU_VENDOR_AMD,
+  VD_CPU_VENDOR_ARM,
+  VD_CPU_VENDOR_OTHER,
+} VdCpuVendor;
+
+typedef enum VdHashAlgorithm {
+  VD_HASH_ALGORITHM_SHA256,
+  VD_HASH_ALGORITHM_SHA512,
+  VD_HASH_ALGORITHM_BLAKE3,
+} VdHashAlgorithm;
+
+// 对应 `getVirtualization` 的主要字段
+typedef struct VdVirtualizationInfo {
+  VdStatusKind kind;
+  bool cpu_supported;
+  bool os_reported_enabled;
+  // 操作系统检查失败，此时 `os_reported_enabled` 为 false 只表示无法确认
+  bool os_check_failed;
+  VdCpuVendor cpu_vendor;
+  uint32_t core_count;
+  uint32_t logical_processor_count;
+  uint32_t cpu_family;
+  uint32_t cpu_model;
+  uint32_t cpu_stepping;
+  // 需要使用 `vd_free_string` 释放
+  char *arch;
+  // 需要使用 `vd_free_string` 释放
+  char *os;
+  // 需要使用 `vd_free_string` 释放
+  char *cpu_feature_name;
+  // 需要使用 `vd_free_string` 释放
+  char *os_check_details;
+  // 需要使用 `vd_free_string` 释放
+  char *overall_status_message;
+} VdVirtualizationInfo;
+
+typedef struct VdMachineIdOptions {
+  // `VD_FACTOR_*` 的组合
+  uint32_t factors;
+  // HMAC 密钥，为 NULL 时使用普通的 SHA-256
+  const uint8_t *hmac_key;
+  size_t hmac_key_len;
+  // 存放 pepper 的环境变量名，可以为 NULL
+  const char *pepper_env;
+  // 存放 pepper 的文件路径，可以为 NULL
+  const char *pepper_file;
+  // 按应用区分的命名空间，拼接在因子之前参与计算，可以为 NULL
+  const char *salt;
+  // 哈希算法，零初始化时为 `VD_HASH_ALGORITHM_SHA256`
+  VdHashAlgorithm hash_algorithm;
+  // 仅 Windows，单个 WMI 查询的超时时间 (毫秒)，为 0 时使用默认的 5000
+  uint32_t wmi_timeout_ms;
+} VdMachineIdOptions;
+
+typedef struct VdMachineIdResult {
+  // 成功时为 Machine ID，失败时为 NULL，需要使用 `vd_free_string` 释放
+  char *machine_id;
+  // 失败时的错误码 (如 `NO_FACTORS_FOUND`)，成功时为 NULL，需要使用 `vd_free_string` 释放
+  char *error_code;
+  // 失败时的错误信息，成功时为 NULL，需要使用 `vd_free_string` 释放
+  char *error;
+  // 是否使用 HMAC-SHA256 计算
+  bool hmac;
+} VdMachineIdResult;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// 检测虚拟化支持情况，`out` 为 NULL 时返回 false
+//
+// # Safety
+//
+// `out` 为 NULL 或指向可写的 `VdVirtualizationInfo`
+bool vd_get_virtualization(struct VdVirtualizationInfo *out);
+
+// 生成 Machine ID，成功时返回 true，失败时返回 false 并填写 `error_code` 与 `error`
+//
+// `options` 为 NULL 时使用 `VD_FACTOR_DEFAULT`，`out` 为 NULL 时直接返回 false
+//
+// # Safety
+//
+// `options` 为 NULL 或指向有效的 `VdMachineIdOptions`，其中的指针为 NULL 或有效；
+// `out` 为 NULL 或指向可写的 `VdMachineIdResult`
+bool vd_get_machine_id(const struct VdMachineIdOptions *options, struct VdMachineIdResult *out);
+
+// 释放本库返回的字符串，传入 NULL 时不做任何操作
+//
+// # Safety
+//
+// `ptr` 为 NULL 或本库返回且尚未释放的字符串
+void vd_free_string(char *ptr);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* VIRT_DETECT_H */
@@ -0,0 +1,333 @@
+//! 供 C/C++ 等非 Node 宿主调用的 `extern "C"` 接口，与 N-API 导出共用相同的实现
+//!
+//! 结构体中的字符串均由本库分配，使用完毕后需要逐个调用 `vd_free_string` 释放。
+//! 头文件在本目录下通过 `cbindgen --config cbindgen.toml --output include/virt_detect.h` 生成
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+use virt_detect_lib::{CpuVendor, get_virtualization};
+
+#[repr(C)]
+pub enum VdStatusKind {
+    Enabled,
+    Disabled,
+    /// CPU 不支持而操作系统报告已启用 (常见于虚拟机)，或操作系统检查失败，无法确定
+    Unknown,
+}
+
+#[repr(C)]
+pub enum VdCpuVendor {
+    Intel,
+    Amd,
+    Arm,
+    Other,
+}
+
+/// 对应 `getVirtualization` 的主要字段
+#[repr(C)]
+pub struct VdVirtualizationInfo {
+    pub kind: VdStatusKind,
+    pub cpu_supported: bool,
+    pub os_reported_enabled: bool,
+    /// 操作系统检查失败，此时 `os_reported_enabled` 为 false 只表示无法确认
+    pub os_check_failed: bool,
+    pub cpu_vendor: VdCpuVendor,
+    pub core_count: u32,
+    pub logical_processor_count: u32,
+    pub cpu_family: u32,
+    pub cpu_model: u32,
+    pub cpu_stepping: u32,
+    /// 需要使用 `vd_free_string` 释放
+    pub arch: *mut c_char,
+    /// 需要使用 `vd_free_string` 释放
+    pub os: *mut c_char,
+    /// 需要使用 `vd_free_string` 释放
+    pub cpu_feature_name: *mut c_char,
+    /// 需要使用 `vd_free_string` 释放
+    pub os_check_details: *mut c_char,
+    /// 需要使用 `vd_free_string` 释放
+    pub overall_status_message: *mut c_char,
+}
+
+pub const VD_FACTOR_BASEBOARD: u32 = 1 << 0;
+pub const VD_FACTOR_PROCESSOR: u32 = 1 << 1;
+pub const VD_FACTOR_DISK_DRIVES: u32 = 1 << 2;
+pub const VD_FACTOR_VIDEO_CONTROLLERS: u32 = 1 << 3;
+pub const VD_FACTOR_MACHINE_GUID: u32 = 1 << 4;
+pub const VD_FACTOR_CHASSIS: u32 = 1 << 5;
+pub const VD_FACTOR_BIOS: u32 = 1 << 6;
+pub const VD_FACTOR_VOLUME_SERIAL: u32 = 1 << 7;
+pub const VD_FACTOR_PRIMARY_NETWORK_ADAPTER: u32 = 1 << 8;
+pub const VD_FACTOR_MEMORY: u32 = 1 << 9;
+pub const VD_FACTOR_DISPLAY: u32 = 1 << 10;
+pub const VD_FACTOR_WINDOWS_INSTALL: u32 = 1 << 11;
+pub const VD_FACTOR_SYSTEM_UUID: u32 = 1 << 12;
+pub const VD_FACTOR_TPM: u32 = 1 << 13;
+pub const VD_FACTOR_NETWORK_ADAPTER: u32 = 1 << 14;
+/// 未提供选项时使用的因子，与 `getMachineId` 的常见用法一致
+pub const VD_FACTOR_DEFAULT: u32 =
+    VD_FACTOR_BASEBOARD | VD_FACTOR_PROCESSOR | VD_FACTOR_DISK_DRIVES | VD_FACTOR_VIDEO_CONTROLLERS;
+
+#[repr(C)]
+pub enum VdHashAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+#[repr(C)]
+pub struct VdMachineIdOptions {
+    /// `VD_FACTOR_*` 的组合
+    pub factors: u32,
+    /// HMAC 密钥，为 NULL 时使用普通的 SHA-256
+    pub hmac_key: *const u8,
+    pub hmac_key_len: usize,
+    /// 存放 pepper 的环境变量名，可以为 NULL
+    pub pepper_env: *const c_char,
+    /// 存放 pepper 的文件路径，可以为 NULL
+    pub pepper_file: *const c_char,
+    /// 按应用区分的命名空间，拼接在因子之前参与计算，可以为 NULL
+    pub salt: *const c_char,
+    /// 哈希算法，零初始化时为 `VD_HASH_ALGORITHM_SHA256`
+    pub hash_algorithm: VdHashAlgorithm,
+    /// 仅 Windows，单个 WMI 查询的超时时间 (毫秒)，为 0 时使用默认的 5000
+    pub wmi_timeout_ms: u32,
+}
+
+#[repr(C)]
+pub struct VdMachineIdResult {
+    /// 成功时为 Machine ID，失败时为 NULL，需要使用 `vd_free_string` 释放
+    pub machine_id: *mut c_char,
+    /// 失败时的错误码 (如 `NO_FACTORS_FOUND`)，成功时为 NULL，需要使用 `vd_free_string` 释放
+    pub error_code: *mut c_char,
+    /// 失败时的错误信息，成功时为 NULL，需要使用 `vd_free_string` 释放
+    pub error: *mut c_char,
+    /// 是否使用 HMAC-SHA256 计算
+    pub hmac: bool,
+}
+
+fn into_c_string(value: impl Into<Vec<u8>>) -> *mut c_char {
+    let mut bytes = value.into();
+    // 检测结果不应包含 NUL，出现时截断，避免 CString::new 失败
+    if let Some(index) = bytes.iter().position(|&it| it == 0) {
+        bytes.truncate(index);
+    }
+    CString::new(bytes).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+#[cfg_attr(
+    not(all(
+        feature = "machine-id",
+        any(target_os = "windows", target_os = "macos", target_os = "linux")
+    )),
+    allow(dead_code)
+)]
+/// # Safety
+///
+/// `ptr` 为 NULL 或指向以 NUL 结尾的字符串
+unsafe fn from_c_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(
+            unsafe { CStr::from_ptr(ptr) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+/// 检测虚拟化支持情况，`out` 为 NULL 时返回 false
+///
+/// # Safety
+///
+/// `out` 为 NULL 或指向可写的 `VdVirtualizationInfo`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vd_get_virtualization(out: *mut VdVirtualizationInfo) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let info = get_virtualization();
+    let kind = match info.kind {
+        "enabled" => VdStatusKind::Enabled,
+        "disabled" => VdStatusKind::Disabled,
+        _ => VdStatusKind::Unknown,
+    };
+    let cpu_vendor = match info.cpu_vendor {
+        CpuVendor::Intel => VdCpuVendor::Intel,
+        CpuVendor::Amd => VdCpuVendor::Amd,
+        CpuVendor::Arm => VdCpuVendor::Arm,
+        CpuVendor::Other => VdCpuVendor::Other,
+    };
+    let result = VdVirtualizationInfo {
+        kind,
+        cpu_supported: info.cpu_supported,
+        os_reported_enabled: info.os_reported_enabled,
+        os_check_failed: info.os_check_failed,
+        cpu_vendor,
+        core_count: info.core_count,
+        logical_processor_count: info.logical_processor_count,
+        cpu_family: info.cpu_family,
+        cpu_model: info.cpu_model,
+        cpu_stepping: info.cpu_stepping,
+        arch: into_c_string(info.arch),
+        os: into_c_string(info.os),
+        cpu_feature_name: into_c_string(info.cpu_feature_name),
+        os_check_details: into_c_string(info.os_check_details),
+        overall_status_message: into_c_string(info.overall_status_message),
+    };
+    unsafe { out.write(result) };
+    true
+}
+
+/// 生成 Machine ID，成功时返回 true，失败时返回 false 并填写 `error_code` 与 `error`
+///
+/// `options` 为 NULL 时使用 `VD_FACTOR_DEFAULT`，`out` 为 NULL 时直接返回 false
+///
+/// # Safety
+///
+/// `options` 为 NULL 或指向有效的 `VdMachineIdOptions`，其中的指针为 NULL 或有效；
+/// `out` 为 NULL 或指向可写的 `VdMachineIdResult`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vd_get_machine_id(
+    options: *const VdMachineIdOptions,
+    out: *mut VdMachineIdResult,
+) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let (result, success) = match unsafe { generate_machine_id(options.as_ref()) } {
+        Ok((machine_id, hmac)) => (
+            VdMachineIdResult {
+                machine_id: into_c_string(machine_id),
+                error_code: ptr::null_mut(),
+                error: ptr::null_mut(),
+                hmac,
+            },
+            true,
+        ),
+        Err((code, error)) => (
+            VdMachineIdResult {
+                machine_id: ptr::null_mut(),
+                error_code: into_c_string(code),
+                error: into_c_string(error),
+                hmac: false,
+            },
+            false,
+        ),
+    };
+    unsafe { out.write(result) };
+    success
+}
+
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+unsafe fn generate_machine_id(
+    options: Option<&VdMachineIdOptions>,
+) -> Result<(String, bool), (&'static str, String)> {
+    use virt_detect_lib::machine_id::{self, HashAlgorithm, MachineIdFactor};
+
+    let mask = options.map_or(VD_FACTOR_DEFAULT, |it| it.factors);
+    let factors = [
+        (VD_FACTOR_BASEBOARD, MachineIdFactor::Baseboard),
+        (VD_FACTOR_PROCESSOR, MachineIdFactor::Processor),
+        (VD_FACTOR_DISK_DRIVES, MachineIdFactor::DiskDrives),
+        (
+            VD_FACTOR_VIDEO_CONTROLLERS,
+            MachineIdFactor::VideoControllers,
+        ),
+        (VD_FACTOR_MACHINE_GUID, MachineIdFactor::MachineGuid),
+        (VD_FACTOR_CHASSIS, MachineIdFactor::Chassis),
+        (VD_FACTOR_BIOS, MachineIdFactor::Bios),
+        (VD_FACTOR_VOLUME_SERIAL, MachineIdFactor::VolumeSerial),
+        (
+            VD_FACTOR_PRIMARY_NETWORK_ADAPTER,
+            MachineIdFactor::PrimaryNetworkAdapter,
+        ),
+        (VD_FACTOR_MEMORY, MachineIdFactor::Memory),
+        (VD_FACTOR_DISPLAY, MachineIdFactor::Display),
+        (VD_FACTOR_WINDOWS_INSTALL, MachineIdFactor::WindowsInstall),
+        (VD_FACTOR_SYSTEM_UUID, MachineIdFactor::SystemUuid),
+        (VD_FACTOR_TPM, MachineIdFactor::Tpm),
+        (VD_FACTOR_NETWORK_ADAPTER, MachineIdFactor::NetworkAdapter),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| mask & bit != 0)
+    .map(|(_, factor)| factor)
+    .collect();
+
+    let options = match options {
+        Some(options) => machine_id::MachineIdOptions {
+            hmac_key: (!options.hmac_key.is_null()).then(|| {
+                unsafe { std::slice::from_raw_parts(options.hmac_key, options.hmac_key_len) }
+                    .to_vec()
+            }),
+            pepper_env: unsafe { from_c_string(options.pepper_env) },
+            pepper_file: unsafe { from_c_string(options.pepper_file) },
+            salt: unsafe { from_c_string(options.salt) },
+            hash_algorithm: match options.hash_algorithm {
+                VdHashAlgorithm::Sha256 => HashAlgorithm::Sha256,
+                VdHashAlgorithm::Sha512 => HashAlgorithm::Sha512,
+                VdHashAlgorithm::Blake3 => HashAlgorithm::Blake3,
+            },
+            wmi_timeout_ms: (options.wmi_timeout_ms != 0).then_some(options.wmi_timeout_ms),
+            ..Default::default()
+        },
+        None => machine_id::MachineIdOptions::default(),
+    };
+    machine_id::get_machine_id_with_factors(factors, &options)
+        .map(|result| (result.machine_id, result.hmac))
+        .map_err(|err| (err.code(), err.to_string()))
+}
+
+#[cfg(not(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+)))]
+unsafe fn generate_machine_id(
+    _options: Option<&VdMachineIdOptions>,
+) -> Result<(String, bool), (&'static str, String)> {
+    Err((
+        "UNSUPPORTED_PLATFORM",
+        "Machine ID is not supported on this platform".to_string(),
+    ))
+}
+
+/// 释放本库返回的字符串，传入 NULL 时不做任何操作
+///
+/// # Safety
+///
+/// `ptr` 为 NULL 或本库返回且尚未释放的字符串
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vd_free_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(unsafe { CString::from_raw(ptr) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn test_vd_get_virtualization() {
+        let mut info = MaybeUninit::<VdVirtualizationInfo>::uninit();
+        assert!(unsafe { vd_get_virtualization(info.as_mut_ptr()) });
+        let info = unsafe { info.assume_init() };
+        let os = unsafe { CStr::from_ptr(info.os) }.to_str().unwrap();
+        assert!(["windows", "linux", "macos", "unknown"].contains(&os));
+        for ptr in [
+            info.arch,
+            info.os,
+            info.cpu_feature_name,
+            info.os_check_details,
+            info.overall_status_message,
+        ] {
+            unsafe { vd_free_string(ptr) };
+        }
+        assert!(!unsafe { vd_get_virtualization(ptr::null_mut()) });
+    }
+}
@@ -0,0 +1,236 @@
+//! 诊断用命令行工具，输出与 N-API 导出相同的检测结果，便于用户直接粘贴报告
+//!
+//! ```bash
+//! cargo run --release -- [--json] [--en]
+//! ```
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use virt_detect_lib as vd;
+
+/// 按检测顺序保存的报告，`serde_json::Map` 会按键名排序
+struct Report(Vec<(&'static str, Value)>);
+
+impl Serialize for Report {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(name, value)| (name, value)))
+    }
+}
+
+const USAGE: &str = "用法: virt-detect [--json] [--en]
+
+  --json  输出 JSON 格式的报告
+  --en    使用英文输出说明文字
+  --help  显示此帮助";
+
+fn main() {
+    let mut json = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--en" => {
+                vd::set_language(vd::Language::En);
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ => {
+                eprintln!("未知的参数: {arg}\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+    let report = collect_report();
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        );
+    } else {
+        print_report(&report);
+    }
+}
+
+fn section(report: &mut Report, name: &'static str, value: impl Serialize) {
+    report
+        .0
+        .push((name, serde_json::to_value(value).unwrap_or(Value::Null)));
+}
+
+fn collect_report() -> Report {
+    let mut report = Report(Vec::new());
+    section(&mut report, "version", vd::get_version());
+    section(&mut report, "virtualization", vd::get_virtualization());
+    section(
+        &mut report,
+        "nestedVirtualization",
+        vd::check_nested_virtualization(),
+    );
+    section(
+        &mut report,
+        "hypervisorSignature",
+        vd::get_hypervisor_signature(),
+    );
+    section(
+        &mut report,
+        "hypervisorVendor",
+        vd::detect_hypervisor_vendor(),
+    );
+    section(
+        &mut report,
+        "hypervGuestFeatures",
+        vd::get_hyperv_guest_features(),
+    );
+    section(
+        &mut report,
+        "sriovCapableNics",
+        vd::list_sriov_capable_nics(),
+    );
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "macos",
+        all(feature = "features", target_os = "windows")
+    ))]
+    section(&mut report, "memory", vd::get_memory_info());
+    #[cfg(target_os = "windows")]
+    {
+        section(&mut report, "processEmulation", vd::get_process_emulation());
+        section(&mut report, "sessionContext", vd::get_session_context());
+    }
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_os = "windows"
+    ))]
+    section(
+        &mut report,
+        "nestedVirtualizationInGuest",
+        vd::check_nested_virtualization_in_guest(),
+    );
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    section(&mut report, "guest", vd::detect_vm());
+    #[cfg(target_os = "linux")]
+    {
+        section(&mut report, "container", vd::detect_container());
+        section(&mut report, "cgroupVersion", vd::get_cgroup_version());
+        section(&mut report, "wslGuest", vd::get_wsl_guest_info());
+    }
+
+    #[cfg(all(feature = "features", target_os = "windows"))]
+    {
+        section(&mut report, "windowsVersion", vd::get_windows_version());
+        section(&mut report, "windowsEdition", vd::get_windows_edition());
+        section(&mut report, "isolation", vd::detect_isolation());
+        section(&mut report, "hyperv", vd::is_hyperv_enabled());
+        section(
+            &mut report,
+            "hypervDefaultSwitch",
+            vd::has_hyperv_default_switch(),
+        );
+        section(&mut report, "wsl", vd::is_wsl_enabled());
+        section(&mut report, "virtualMachinePlatform", vd::is_vmp_enabled());
+        section(
+            &mut report,
+            "wsl2Prerequisites",
+            vd::check_wsl2_prerequisites(),
+        );
+        section(&mut report, "wslg", vd::check_wslg());
+        section(
+            &mut report,
+            "wsl2KernelVersion",
+            vd::get_wsl2_kernel_version(),
+        );
+        section(&mut report, "wslVersion", vd::get_wsl_version());
+        section(
+            &mut report,
+            "defaultWslDistro",
+            vd::get_default_wsl_distro(),
+        );
+        section(&mut report, "gpuAcceleration", vd::get_gpu_acceleration());
+        section(&mut report, "gpuPartitioning", vd::check_gpu_partitioning());
+        section(&mut report, "shieldedVm", vd::check_shielded_vm_support());
+        section(
+            &mut report,
+            "coreIsolation",
+            vd::get_core_isolation_status(),
+        );
+        section(
+            &mut report,
+            "hypervisorConflicts",
+            vd::detect_hypervisor_conflicts(),
+        );
+    }
+
+    #[cfg(all(feature = "encoding", any(target_os = "windows", target_os = "macos")))]
+    section(&mut report, "encoding", vd::get_system_encoding());
+    #[cfg(all(feature = "encoding", target_os = "windows"))]
+    section(&mut report, "consoleEncoding", vd::get_console_encoding());
+
+    #[cfg(all(
+        feature = "machine-id",
+        any(target_os = "windows", target_os = "macos", target_os = "linux")
+    ))]
+    section(&mut report, "machineId", machine_id());
+
+    report
+}
+
+/// 与 N-API 的 `getMachineId` 使用相同的默认因子，直接调用 Machine ID 模块，不经过 N-API 的类型
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+fn machine_id() -> Value {
+    use vd::machine_id::{self, MachineIdFactor, MachineIdOptions};
+
+    let factors = vec![
+        MachineIdFactor::Baseboard,
+        MachineIdFactor::Processor,
+        MachineIdFactor::DiskDrives,
+        MachineIdFactor::VideoControllers,
+    ];
+    match machine_id::get_machine_id_with_factors(factors, &MachineIdOptions::default()) {
+        Ok(result) => serde_json::json!({
+            "machineId": result.machine_id,
+            "factors": result.factors,
+            "warnings": result.warnings,
+            "missingFactors": result.missing,
+        }),
+        Err(err) => serde_json::json!({
+            "errorCode": err.code(),
+            "error": err.to_string(),
+        }),
+    }
+}
+
+fn print_report(report: &Report) {
+    for (name, value) in &report.0 {
+        match value {
+            Value::Object(fields) => {
+                println!("\n[{name}]");
+                for (key, value) in fields {
+                    print_value("  ", key, value);
+                }
+            }
+            _ => print_value("", name, value),
+        }
+    }
+}
+
+fn print_value(indent: &str, key: &str, value: &Value) {
+    match value {
+        Value::Null => println!("{indent}{key}: -"),
+        Value::String(text) => println!("{indent}{key}: {text}"),
+        Value::Array(items) if items.is_empty() => println!("{indent}{key}: (无)"),
+        Value::Array(items) => {
+            println!("{indent}{key}:");
+            for item in items {
+                match item {
+                    Value::String(text) => println!("{indent}  - {text}"),
+                    _ => println!("{indent}  - {item}"),
+                }
+            }
+        }
+        _ => println!("{indent}{key}: {value}"),
+    }
+}
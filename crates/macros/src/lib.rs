@@ -0,0 +1,39 @@
+//! 未启用 `napi` 特性时代替 `napi_derive::napi` 的属性，原样保留被标注的项，只移除字段上的 `#[napi(...)]`
+//!
+//! 命令行工具与 C 接口因此可以复用带有 `#[napi]` 标注的检测代码，而不需要依赖 napi 或改变它的特性
+use proc_macro::{Delimiter, Group, TokenStream, TokenTree};
+
+#[proc_macro_attribute]
+pub fn napi(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    strip_napi_attrs(item)
+}
+
+fn strip_napi_attrs(stream: TokenStream) -> TokenStream {
+    let mut output = Vec::new();
+    let mut tokens = stream.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '#' => {
+                if let Some(TokenTree::Group(group)) = tokens.peek()
+                    && group.delimiter() == Delimiter::Bracket
+                    && is_napi_attr(group)
+                {
+                    tokens.next();
+                    continue;
+                }
+                output.push(TokenTree::Punct(punct));
+            }
+            TokenTree::Group(group) => {
+                let mut stripped = Group::new(group.delimiter(), strip_napi_attrs(group.stream()));
+                stripped.set_span(group.span());
+                output.push(TokenTree::Group(stripped));
+            }
+            token => output.push(token),
+        }
+    }
+    output.into_iter().collect()
+}
+
+fn is_napi_attr(group: &Group) -> bool {
+    matches!(group.stream().into_iter().next(), Some(TokenTree::Ident(ident)) if ident.to_string() == "napi")
+}
@@ -0,0 +1,334 @@
+//! 基于 `AsyncTask` 的异步版本，WMI 查询和服务查询等耗时操作在 libuv 线程池中执行，不会阻塞 JS 主线程
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Task};
+use napi_derive::napi;
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+use crate::FeatureStatus;
+#[cfg(all(feature = "encoding", target_os = "windows"))]
+use crate::SystemEncoding;
+use crate::VirtualizationInfo;
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+use crate::{
+    MachineIdFactor, MachineIdOptions, MachineIdResult, MachineIdValue, ProgressCallback,
+    machine_id,
+};
+
+/// Task 只能以 Status 作为错误码，这里先创建带有自定义 code 的 JS 错误，再用它拒绝 Promise
+#[cfg(any(
+    all(feature = "features", target_os = "windows"),
+    all(
+        feature = "machine-id",
+        any(target_os = "windows", target_os = "macos", target_os = "linux")
+    )
+))]
+fn reject_with_code(env: Env, err: napi::Error<&'static str>) -> napi::Error {
+    let error = napi::JsError::from(err);
+    napi::Error::from(error.into_unknown(env))
+}
+
+pub struct GetVirtualizationTask;
+
+#[napi]
+impl Task for GetVirtualizationTask {
+    type Output = VirtualizationInfo;
+    type JsValue = VirtualizationInfo;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::get_virtualization())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// `getVirtualization` 的异步版本
+///
+/// 整个检测 (包括 Windows 上的系统功能与固件检查) 都在 libuv 线程池中执行，结果与同步版本相同
+#[napi]
+pub fn get_virtualization_async() -> AsyncTask<GetVirtualizationTask> {
+    AsyncTask::new(GetVirtualizationTask)
+}
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+pub struct IsHypervEnabledTask;
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+impl Task for IsHypervEnabledTask {
+    type Output = FeatureStatus;
+    type JsValue = FeatureStatus;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::is_hyperv_enabled())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// `isHypervEnabled` 的异步版本
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_hyperv_enabled_async() -> AsyncTask<IsHypervEnabledTask> {
+    AsyncTask::new(IsHypervEnabledTask)
+}
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+pub struct IsHypervEnabledStrictTask;
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+impl Task for IsHypervEnabledStrictTask {
+    type Output = FeatureStatus;
+    type JsValue = FeatureStatus;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::is_hyperv_enabled())
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        output
+            .into_strict()
+            .map_err(|err| reject_with_code(env, err))
+    }
+}
+
+/// `isHypervEnabledStrict` 的异步版本，无法确定状态时以带有 `code` 的错误拒绝 Promise
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_hyperv_enabled_strict_async() -> AsyncTask<IsHypervEnabledStrictTask> {
+    AsyncTask::new(IsHypervEnabledStrictTask)
+}
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+pub struct IsWslEnabledTask;
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+impl Task for IsWslEnabledTask {
+    type Output = FeatureStatus;
+    type JsValue = FeatureStatus;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::is_wsl_enabled())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// `isWslEnabled` 的异步版本
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_wsl_enabled_async() -> AsyncTask<IsWslEnabledTask> {
+    AsyncTask::new(IsWslEnabledTask)
+}
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+pub struct IsWslEnabledStrictTask;
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+impl Task for IsWslEnabledStrictTask {
+    type Output = FeatureStatus;
+    type JsValue = FeatureStatus;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::is_wsl_enabled())
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        output
+            .into_strict()
+            .map_err(|err| reject_with_code(env, err))
+    }
+}
+
+/// `isWslEnabledStrict` 的异步版本，无法确定状态时以带有 `code` 的错误拒绝 Promise
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_wsl_enabled_strict_async() -> AsyncTask<IsWslEnabledStrictTask> {
+    AsyncTask::new(IsWslEnabledStrictTask)
+}
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+pub struct EnableWslFeatureTask;
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+impl Task for EnableWslFeatureTask {
+    type Output = FeatureStatus;
+    type JsValue = FeatureStatus;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::enable_wsl_feature())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// 启用 WSL 所需的可选功能，DISM 在 libuv 线程池中执行
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn enable_wsl_feature_async() -> AsyncTask<EnableWslFeatureTask> {
+    AsyncTask::new(EnableWslFeatureTask)
+}
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+pub struct EnableHypervFeatureTask;
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+impl Task for EnableHypervFeatureTask {
+    type Output = FeatureStatus;
+    type JsValue = FeatureStatus;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::enable_hyperv_feature())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// 启用 Hyper-V 可选功能，DISM 在 libuv 线程池中执行
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn enable_hyperv_feature_async() -> AsyncTask<EnableHypervFeatureTask> {
+    AsyncTask::new(EnableHypervFeatureTask)
+}
+
+#[cfg(all(feature = "encoding", target_os = "windows"))]
+pub struct GetSystemEncodingTask;
+
+#[cfg(all(feature = "encoding", target_os = "windows"))]
+#[napi]
+impl Task for GetSystemEncodingTask {
+    type Output = SystemEncoding;
+    type JsValue = SystemEncoding;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        Ok(crate::get_system_encoding())
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// `getSystemEncoding` 的异步版本
+#[cfg(all(feature = "encoding", target_os = "windows"))]
+#[napi]
+pub fn get_system_encoding_async() -> AsyncTask<GetSystemEncodingTask> {
+    AsyncTask::new(GetSystemEncodingTask)
+}
+
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+pub struct GetMachineIdTask {
+    factors: Vec<machine_id::MachineIdFactor>,
+    options: machine_id::MachineIdOptions,
+}
+
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+#[napi]
+impl Task for GetMachineIdTask {
+    type Output = MachineIdResult;
+    type JsValue = MachineIdResult;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let factors = std::mem::take(&mut self.factors);
+        Ok(crate::machine_id_result(factors, &self.options, Vec::new()))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// `getMachineId` 的异步版本，WMI 等查询在 libuv 线程池中执行，不会阻塞 JS 主线程
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+#[napi(
+    ts_args_type = "factors: Array<MachineIdFactor>, options?: MachineIdOptions | undefined | null, onProgress?: ((progress: MachineIdProgress) => void) | undefined | null"
+)]
+pub fn get_machine_id_async(
+    factors: Vec<MachineIdFactor>,
+    options: Option<MachineIdOptions>,
+    on_progress: Option<ProgressCallback>,
+) -> AsyncTask<GetMachineIdTask> {
+    AsyncTask::new(GetMachineIdTask {
+        factors: factors.into_iter().map(|it| it.into()).collect(),
+        options: crate::machine_id_options(options, on_progress),
+    })
+}
+
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+pub struct GetMachineIdStrictTask {
+    factors: Vec<machine_id::MachineIdFactor>,
+    options: machine_id::MachineIdOptions,
+}
+
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+#[napi]
+impl Task for GetMachineIdStrictTask {
+    type Output = Result<machine_id::MachineId, machine_id::MachineIdError>;
+    type JsValue = MachineIdValue;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let factors = std::mem::take(&mut self.factors);
+        Ok(machine_id::get_machine_id_with_factors(
+            factors,
+            &self.options,
+        ))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        match output {
+            Ok(result) => Ok(MachineIdValue::new(result, &self.options, Vec::new())),
+            Err(err) => Err(reject_with_code(
+                env,
+                napi::Error::new(err.code(), err.to_string()),
+            )),
+        }
+    }
+}
+
+/// `getMachineIdStrict` 的异步版本，失败时以带有 `code` 的错误拒绝 Promise
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+#[napi]
+pub fn get_machine_id_strict_async(
+    factors: Vec<MachineIdFactor>,
+    options: Option<MachineIdOptions>,
+) -> AsyncTask<GetMachineIdStrictTask> {
+    AsyncTask::new(GetMachineIdStrictTask {
+        factors: factors.into_iter().map(|it| it.into()).collect(),
+        options: options.unwrap_or_default().into(),
+    })
+}
@@ -0,0 +1,163 @@
+//! 可选的检测结果缓存
+//!
+//! 默认关闭，每次调用都会重新检测；调用 `set_cache_ttl_ms` 开启后，相同的调用在有效期内直接返回上一次的结果
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TTL_MS: u32 = 30_000;
+
+type Entries = HashMap<String, (Instant, Box<dyn Any + Send>)>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Lifetime {
+    /// CPU、固件等信息，进程运行期间不会变化，开启缓存后一直有效
+    Static,
+    /// 服务、可选功能等状态，用户可以随时修改，超过有效期后重新检测
+    Volatile,
+}
+
+/// 有效期与缓存结果，导出的函数共用 [`cache`] 返回的全局实例
+struct Cache {
+    /// 有效期，0 表示关闭缓存
+    ttl_ms: AtomicU32,
+    entries: Mutex<Entries>,
+}
+
+impl Cache {
+    fn new() -> Self {
+        Cache {
+            ttl_ms: AtomicU32::new(0),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn set_ttl_ms(&self, ms: u32) {
+        self.ttl_ms.store(ms, Ordering::Relaxed);
+        if ms == 0 {
+            self.entries().clear();
+        }
+    }
+
+    fn get_or_insert_with<T, F>(&self, key: &str, lifetime: Lifetime, detect: F) -> T
+    where
+        T: Clone + Send + 'static,
+        F: FnOnce() -> T,
+    {
+        let ttl = self.ttl_ms.load(Ordering::Relaxed);
+        if ttl == 0 {
+            return detect();
+        }
+        if let Some((created_at, value)) = self.entries().get(key) {
+            let fresh = match lifetime {
+                Lifetime::Static => true,
+                Lifetime::Volatile => created_at.elapsed() < Duration::from_millis(ttl as u64),
+            };
+            if let Some(value) = value.downcast_ref::<T>().filter(|_| fresh) {
+                return value.clone();
+            }
+        }
+        let value = detect();
+        self.entries()
+            .insert(key.to_string(), (Instant::now(), Box::new(value.clone())));
+        value
+    }
+}
+
+fn cache() -> &'static Cache {
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+    CACHE.get_or_init(Cache::new)
+}
+
+/// 设置有效期，为 0 时关闭缓存并清空已有结果
+pub fn set_ttl_ms(ms: u32) {
+    cache().set_ttl_ms(ms);
+}
+
+pub fn clear() {
+    cache().entries().clear();
+}
+
+/// 移除 `key` 对应的缓存结果，返回是否存在
+pub fn remove(key: &str) -> bool {
+    cache().entries().remove(key).is_some()
+}
+
+/// 将导出的函数名 (如 `isWslEnabled`) 转换为缓存使用的 key (如 `is_wsl_enabled`)，snake_case 保持不变
+pub fn key_from_name(name: &str) -> String {
+    let mut key = String::with_capacity(name.len() + 4);
+    for char in name.trim().chars() {
+        if char.is_ascii_uppercase() {
+            if !key.is_empty() {
+                key.push('_');
+            }
+            key.push(char.to_ascii_lowercase());
+        } else {
+            key.push(char);
+        }
+    }
+    key
+}
+
+/// 返回 `key` 对应的缓存结果，不存在或已过期时调用 `detect` 并保存结果
+///
+/// `key` 由函数名和参数组成，检测期间不持有锁，并发调用可能会重复检测
+pub fn get_or_insert_with<T, F>(key: &str, lifetime: Lifetime, detect: F) -> T
+where
+    T: Clone + Send + 'static,
+    F: FnOnce() -> T,
+{
+    cache().get_or_insert_with(key, lifetime, detect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_or_insert_with() {
+        // 使用独立的实例，不影响其它测试使用的全局缓存
+        let cache = Cache::new();
+        let mut calls = 0;
+        let mut detect = |key: &str, lifetime| {
+            cache.get_or_insert_with(key, lifetime, || {
+                calls += 1;
+                calls
+            })
+        };
+        // 默认关闭，每次都重新检测
+        assert_eq!(detect("test_static", Lifetime::Static), 1);
+        assert_eq!(detect("test_static", Lifetime::Static), 2);
+
+        cache.set_ttl_ms(DEFAULT_TTL_MS);
+        assert_eq!(detect("test_static", Lifetime::Static), 3);
+        assert_eq!(detect("test_static", Lifetime::Static), 3);
+        assert_eq!(detect("test_volatile", Lifetime::Volatile), 4);
+        assert_eq!(detect("test_volatile", Lifetime::Volatile), 4);
+
+        cache.set_ttl_ms(1);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(detect("test_static", Lifetime::Static), 3);
+        assert_eq!(detect("test_volatile", Lifetime::Volatile), 5);
+
+        cache.set_ttl_ms(0);
+        assert_eq!(detect("test_static", Lifetime::Static), 6);
+    }
+
+    #[test]
+    fn test_key_from_name() {
+        assert_eq!(key_from_name("isWslEnabled"), "is_wsl_enabled");
+        assert_eq!(
+            key_from_name("getWsl2KernelVersion"),
+            "get_wsl2_kernel_version"
+        );
+        assert_eq!(key_from_name("is_wsl_enabled"), "is_wsl_enabled");
+        assert_eq!(key_from_name("DetectVm"), "detect_vm");
+    }
+}
@@ -1,18 +1,104 @@
-#[cfg(target_os = "windows")]
-#[deprecated = "Powershell 使用 UTF-16le 编码，此函数无用"]
-pub fn get_system_encoding() -> (u32, &'static str) {
-    use windows::Win32::Globalization::GetACP;
-    let ansi_code = unsafe { GetACP() };
+/// 将 Windows 代码页转换为 iconv / `iconv-lite` 可以识别的编码名，无法识别时返回 `unknown`
+///
+/// `code_page_name` 返回的是展示用的名称 (如 `SHIFT-JIS`)，并不都能直接传给 iconv
+pub fn codepage_to_iconv_name(code_page: u32) -> &'static str {
+    match code_page {
+        65001 => "utf-8",
+        1200 => "utf-16le",
+        1201 => "utf-16be",
+        20127 => "ascii",
+        936 => "gbk",
+        54936 => "gb18030",
+        950 => "big5",
+        932 => "shift_jis",
+        20932 => "euc-jp",
+        949 | 51949 => "euc-kr",
+        437 => "cp437",
+        850 => "cp850",
+        866 => "cp866",
+        874 => "windows-874",
+        1250 => "windows-1250",
+        1251 => "windows-1251",
+        1252 => "windows-1252",
+        1253 => "windows-1253",
+        1254 => "windows-1254",
+        1255 => "windows-1255",
+        1256 => "windows-1256",
+        1257 => "windows-1257",
+        1258 => "windows-1258",
+        28591 => "iso-8859-1",
+        10000 => "macintosh",
+        _ => "unknown",
+    }
+}
 
-    let ansi_encoding = match ansi_code {
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn code_page_name(code_page: u32) -> &'static str {
+    match code_page {
         65001 => "UTF-8",
         936 => "GBK",
         950 => "BIG5",
         1252 => "WINDOWS-1252",
         932 => "SHIFT-JIS",
+        10000 => "MACINTOSH",
         _ => "UNKNOWN",
+    }
+}
+
+/// 根据 locale (如 `zh_CN.UTF-8`) 中的字符集得到对应的 Windows 代码页，无法识别时返回 `None`
+///
+/// `C` 和 `POSIX` 没有字符集部分，视为 ASCII
+#[cfg(any(target_os = "macos", test))]
+fn locale_code_page(locale: &str) -> Option<u32> {
+    let locale = locale.trim();
+    if locale == "C" || locale == "POSIX" {
+        return Some(20127);
+    }
+    let (_, charset) = locale.split_once('.')?;
+    // 去掉 `@euro` 等修饰符
+    let charset = charset.split('@').next().unwrap_or_default();
+    let normalized = charset.to_ascii_lowercase().replace(['-', '_'], "");
+    match normalized.as_str() {
+        "utf8" => Some(65001),
+        "gb18030" => Some(54936),
+        "gbk" | "gb2312" | "euccn" => Some(936),
+        "big5" | "big5hkscs" => Some(950),
+        "sjis" | "shiftjis" => Some(932),
+        "eucjp" => Some(20932),
+        "euckr" => Some(51949),
+        "iso88591" => Some(28591),
+        "usascii" | "ascii" => Some(20127),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+/// 系统文本编码，返回 (对应的 Windows 代码页, 编码)，没有对应代码页时代码页为 0
+pub fn get_system_encoding_macos() -> (u32, &'static str) {
+    let code_page = crate::macos::system_encoding_code_page().unwrap_or(0);
+    (code_page, code_page_name(code_page))
+}
+
+#[cfg(target_os = "macos")]
+/// 终端使用的 locale 编码，按 `LC_ALL`、`LC_CTYPE`、`LANG` 的顺序取第一个非空的值，
+/// 都未设置时为 macOS 的默认值 UTF-8；无法识别字符集时代码页为 0
+pub fn get_locale_encoding_macos() -> (u32, &'static str) {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|it| !it.is_empty()));
+    let code_page = match locale {
+        Some(locale) => locale_code_page(&locale).unwrap_or(0),
+        None => 65001,
     };
-    (ansi_code, ansi_encoding)
+    (code_page, code_page_name(code_page))
+}
+
+#[cfg(target_os = "windows")]
+#[deprecated = "Powershell 使用 UTF-16le 编码，此函数无用"]
+pub fn get_system_encoding() -> (u32, &'static str) {
+    use windows::Win32::Globalization::GetACP;
+    let ansi_code = unsafe { GetACP() };
+    (ansi_code, code_page_name(ansi_code))
 }
 
 #[cfg(target_os = "windows")]
@@ -20,13 +106,43 @@ pub fn get_system_encoding() -> (u32, &'static str) {
 pub fn get_console_encoding() -> (u32, &'static str) {
     use windows::Win32::Globalization::GetOEMCP;
     let oem_code = unsafe { GetOEMCP() };
-    let oem_encoding = match oem_code {
-        65001 => "UTF-8",
-        936 => "GBK",
-        950 => "BIG5",
-        1252 => "WINDOWS-1252",
-        932 => "SHIFT-JIS",
-        _ => "UNKNOWN",
-    };
-    (oem_code, oem_encoding)
+    (oem_code, code_page_name(oem_code))
+}
+
+#[cfg(target_os = "windows")]
+/// 读取当前控制台的代码页，返回 ((输入代码页, 编码), (输出代码页, 编码))
+///
+/// 与 `GetOEMCP` 不同，这里反映的是 `chcp` 修改后的实际值；进程没有附加控制台时代码页为 0
+pub fn get_console_code_pages() -> ((u32, &'static str), (u32, &'static str)) {
+    use windows::Win32::System::Console::{GetConsoleCP, GetConsoleOutputCP};
+    let input_code = unsafe { GetConsoleCP() };
+    let output_code = unsafe { GetConsoleOutputCP() };
+    (
+        (input_code, code_page_name(input_code)),
+        (output_code, code_page_name(output_code)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codepage_to_iconv_name() {
+        assert_eq!(codepage_to_iconv_name(936), "gbk");
+        assert_eq!(codepage_to_iconv_name(932), "shift_jis");
+        assert_eq!(codepage_to_iconv_name(65001), "utf-8");
+        assert_eq!(codepage_to_iconv_name(0), "unknown");
+    }
+
+    #[test]
+    fn test_locale_code_page() {
+        assert_eq!(locale_code_page("en_US.UTF-8"), Some(65001));
+        assert_eq!(locale_code_page("zh_CN.utf8"), Some(65001));
+        assert_eq!(locale_code_page("zh_CN.GB18030"), Some(54936));
+        assert_eq!(locale_code_page("ja_JP.SJIS"), Some(932));
+        assert_eq!(locale_code_page("de_DE.ISO8859-1@euro"), Some(28591));
+        assert_eq!(locale_code_page("C"), Some(20127));
+        assert_eq!(locale_code_page("en_US"), None);
+    }
 }
@@ -0,0 +1,165 @@
+//! 读取原始固件表 (SMBIOS、ACPI)，所有固件访问都集中在这里
+//!
+//! 提供者与表 ID 与 `GetSystemFirmwareTable` 一致:
+//! - `RSMB` (0x52534D42): 表 ID 忽略，返回 `RawSMBIOSData` (8 字节头 + SMBIOS 结构表)
+//! - `ACPI` (0x41435049): 表 ID 为签名的小端序值，如 `FACP` 为 0x50434146，返回完整的表 (包含表头)
+#![cfg(any(target_os = "windows", target_os = "linux"))]
+
+#[cfg(any(target_os = "linux", test))]
+pub const PROVIDER_ACPI: u32 = u32::from_be_bytes(*b"ACPI");
+#[cfg(any(target_os = "linux", test))]
+pub const PROVIDER_RSMB: u32 = u32::from_be_bytes(*b"RSMB");
+
+#[derive(Debug)]
+pub enum FirmwareTableError {
+    /// 当前平台不支持该提供者
+    UnsupportedProvider(u32),
+    /// 表不存在
+    NotFound(String),
+    /// 读取失败，如权限不足
+    Read(String),
+}
+
+impl FirmwareTableError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            FirmwareTableError::UnsupportedProvider(_) => "UNSUPPORTED_PROVIDER",
+            FirmwareTableError::NotFound(_) => "TABLE_NOT_FOUND",
+            FirmwareTableError::Read(_) => "READ_FAILED",
+        }
+    }
+}
+
+impl std::fmt::Display for FirmwareTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FirmwareTableError::UnsupportedProvider(provider) => write!(
+                f,
+                "Unsupported firmware table provider: {} (0x{provider:08X})",
+                signature_to_string(provider.to_be_bytes())
+            ),
+            FirmwareTableError::NotFound(msg) => write!(f, "Firmware table not found: {}", msg),
+            FirmwareTableError::Read(msg) => write!(f, "Failed to read firmware table: {}", msg),
+        }
+    }
+}
+
+fn signature_to_string(bytes: [u8; 4]) -> String {
+    bytes
+        .iter()
+        .map(|&it| {
+            if it.is_ascii_graphic() {
+                it as char
+            } else {
+                '?'
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+pub fn read_table(provider: u32, table_id: u32) -> Result<Vec<u8>, FirmwareTableError> {
+    use windows::Win32::Foundation::{ERROR_INVALID_FUNCTION, ERROR_NOT_FOUND, GetLastError};
+    use windows::Win32::System::SystemInformation::{
+        FIRMWARE_TABLE_PROVIDER, GetSystemFirmwareTable,
+    };
+    let provider_signature = FIRMWARE_TABLE_PROVIDER(provider);
+    // 表的大小可能在两次调用之间变化 (如 ACPI 热插拔)，返回值大于缓冲区时按新的大小重试
+    let mut size = unsafe { GetSystemFirmwareTable(provider_signature, table_id, None) };
+    for _ in 0..3 {
+        if size == 0 {
+            let err = unsafe { GetLastError() };
+            return Err(if err == ERROR_NOT_FOUND {
+                FirmwareTableError::NotFound(format!(
+                    "{}/{}",
+                    signature_to_string(provider.to_be_bytes()),
+                    signature_to_string(table_id.to_le_bytes())
+                ))
+            } else if err == ERROR_INVALID_FUNCTION {
+                FirmwareTableError::UnsupportedProvider(provider)
+            } else {
+                FirmwareTableError::Read(format!("GetSystemFirmwareTable failed: {}", err.0))
+            });
+        }
+        let mut buffer = vec![0u8; size as usize];
+        let written =
+            unsafe { GetSystemFirmwareTable(provider_signature, table_id, Some(&mut buffer)) };
+        if written as usize <= buffer.len() && written != 0 {
+            buffer.truncate(written as usize);
+            return Ok(buffer);
+        }
+        size = written;
+    }
+    Err(FirmwareTableError::Read(
+        "table size kept changing between calls".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_table(provider: u32, table_id: u32) -> Result<Vec<u8>, FirmwareTableError> {
+    match provider {
+        PROVIDER_RSMB => {
+            let table = read_file("/sys/firmware/dmi/tables/DMI")?;
+            let entry_point = read_file("/sys/firmware/dmi/tables/smbios_entry_point")?;
+            Ok(raw_smbios_data(&entry_point, &table))
+        }
+        PROVIDER_ACPI => {
+            let signature = signature_to_string(table_id.to_le_bytes());
+            let path = format!("/sys/firmware/acpi/tables/{signature}");
+            // 存在多个同名表 (如 SSDT) 时内核命名为 SSDT1、SSDT2…，与 Windows 一样返回第一个
+            read_file(&path).or_else(|err| match err {
+                FirmwareTableError::NotFound(_) => read_file(&format!("{path}1")),
+                err => Err(err),
+            })
+        }
+        _ => Err(FirmwareTableError::UnsupportedProvider(provider)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_file(path: &str) -> Result<Vec<u8>, FirmwareTableError> {
+    std::fs::read(path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => FirmwareTableError::NotFound(path.to_string()),
+        _ => FirmwareTableError::Read(format!("{path}: {err}")),
+    })
+}
+
+#[cfg(any(target_os = "linux", test))]
+/// 按 Windows 的 `RawSMBIOSData` 布局拼接入口点中的版本号与结构表，使两个平台返回的数据可以用相同的方式解析
+///
+/// 32 位入口点 (`_SM_`) 的版本号位于偏移 6、7，64 位入口点 (`_SM3_`) 位于偏移 7、8，修订号位于偏移 9
+fn raw_smbios_data(entry_point: &[u8], table: &[u8]) -> Vec<u8> {
+    let byte = |index: usize| entry_point.get(index).copied().unwrap_or(0);
+    let (major, minor, revision) = if entry_point.starts_with(b"_SM3_") {
+        (byte(7), byte(8), byte(9))
+    } else if entry_point.starts_with(b"_SM_") {
+        (byte(6), byte(7), 0)
+    } else {
+        (0, 0, 0)
+    };
+    let mut data = Vec::with_capacity(8 + table.len());
+    data.extend_from_slice(&[0, major, minor, revision]);
+    data.extend_from_slice(&(table.len() as u32).to_le_bytes());
+    data.extend_from_slice(table);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_smbios_data() {
+        let mut entry_point = b"_SM3_".to_vec();
+        entry_point.extend_from_slice(&[0x00, 0x18, 3, 4, 0]);
+        let data = raw_smbios_data(&entry_point, &[1, 2, 3]);
+        assert_eq!(data, [0, 3, 4, 0, 3, 0, 0, 0, 1, 2, 3]);
+
+        let mut entry_point = b"_SM_".to_vec();
+        entry_point.extend_from_slice(&[0x00, 0x1F, 2, 8]);
+        assert_eq!(raw_smbios_data(&entry_point, &[])[..4], [0, 2, 8, 0]);
+
+        assert_eq!(PROVIDER_RSMB, 0x52534D42);
+        assert_eq!(PROVIDER_ACPI, 0x41435049);
+    }
+}
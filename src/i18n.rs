@@ -0,0 +1,76 @@
+//! 面向用户的说明文字 (如 `overallStatusMessage`、`osCheckDetails`、`remediation`) 使用的语言
+//!
+//! 默认为简体中文，调用 `setLanguage` 切换后对之后的检测生效；需要据此做判断的调用方应使用结构化的字段，
+//! 不要匹配说明文字
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    ZhCn,
+    En,
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::ZhCn as u8);
+
+/// 设置语言，返回是否发生了变化
+pub fn set_language(language: Language) -> bool {
+    LANGUAGE.swap(language as u8, Ordering::Relaxed) != language as u8
+}
+
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        it if it == Language::En as u8 => Language::En,
+        _ => Language::ZhCn,
+    }
+}
+
+/// 按当前语言选择文本，两种语言的文本都会被求值，只用于构造说明文字
+pub fn localize<T>(zh_cn: T, en: T) -> T {
+    match language() {
+        Language::ZhCn => zh_cn,
+        Language::En => en,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    /// 串行化修改全局语言的测试，并在结束时恢复之前的语言
+    struct LanguageGuard {
+        previous: Language,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl LanguageGuard {
+        fn new(language: Language) -> Self {
+            static LOCK: Mutex<()> = Mutex::new(());
+            let lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+            let previous = super::language();
+            set_language(language);
+            LanguageGuard {
+                previous,
+                _lock: lock,
+            }
+        }
+    }
+
+    impl Drop for LanguageGuard {
+        fn drop(&mut self) {
+            set_language(self.previous);
+        }
+    }
+
+    #[test]
+    fn test_localize() {
+        let _guard = LanguageGuard::new(Language::ZhCn);
+        assert_eq!(localize("中文", "English"), "中文");
+        assert!(set_language(Language::En));
+        assert!(!set_language(Language::En));
+        assert_eq!(localize("中文", "English"), "English");
+        assert!(set_language(Language::ZhCn));
+        assert_eq!(language(), Language::ZhCn);
+    }
+}
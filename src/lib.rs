@@ -1,295 +1,2836 @@
+use cache::Lifetime;
+#[cfg(feature = "napi")]
+use napi::bindgen_prelude::Buffer;
+#[cfg(feature = "napi")]
 use napi_derive::napi;
-use std::path::Path;
+#[cfg(not(feature = "napi"))]
+use virt_detect_macros::napi;
 
+#[cfg(feature = "napi")]
+mod async_task;
+mod cache;
+#[cfg(feature = "encoding")]
 mod encoding;
+// 只有 `getFirmwareTable` 使用，它返回 `Buffer`，仅在启用 napi 时提供
+#[cfg_attr(not(feature = "napi"), allow(dead_code))]
+mod firmware;
+mod i18n;
+/// 命令行工具与 C 接口不经过 N-API 的类型，直接调用该模块生成 Machine ID
+#[cfg(feature = "machine-id")]
+pub mod machine_id;
+mod macos;
 mod virtualization;
+#[cfg(feature = "features")]
 mod windows_feature;
-mod machine_id;
+#[cfg(any(feature = "machine-id", feature = "features"))]
+mod wmi_executor;
+
+#[cfg(feature = "napi")]
+pub use async_task::*;
+
+#[napi]
+#[cfg_attr(not(feature = "napi"), derive(Clone, Copy))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum CpuVendor {
+    Intel,
+    Amd,
+    Arm,
+    Other,
+}
+
+impl From<virtualization::CpuVendor> for CpuVendor {
+    fn from(value: virtualization::CpuVendor) -> Self {
+        match value {
+            virtualization::CpuVendor::Intel => CpuVendor::Intel,
+            virtualization::CpuVendor::Amd => CpuVendor::Amd,
+            virtualization::CpuVendor::Arm => CpuVendor::Arm,
+            virtualization::CpuVendor::Other => CpuVendor::Other,
+        }
+    }
+}
+
+/// CPU 与操作系统检测结果的组合，对应 `overallStatusMessage` 的四种情况，供调用方做判断
+#[napi]
+#[cfg_attr(not(feature = "napi"), derive(Clone, Copy))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum VirtStatus {
+    /// CPU 支持虚拟化，操作系统/固件也已启用
+    CpuAndOsEnabled,
+    /// CPU 支持虚拟化，但操作系统报告未启用或无法确认，通常需要在 BIOS/UEFI 中开启
+    CpuSupportedOsDisabled,
+    /// CPU 不支持 (或未暴露) 虚拟化，但操作系统报告已启用，常见于运行在 Hypervisor 之下
+    CpuUnsupportedOsReportsEnabled,
+    /// CPU 不支持虚拟化
+    CpuUnsupported,
+}
+
+impl VirtStatus {
+    fn new(cpu_supported: bool, os_reported_enabled: bool) -> Self {
+        match (cpu_supported, os_reported_enabled) {
+            (true, true) => VirtStatus::CpuAndOsEnabled,
+            (true, false) => VirtStatus::CpuSupportedOsDisabled,
+            (false, true) => VirtStatus::CpuUnsupportedOsReportsEnabled,
+            (false, false) => VirtStatus::CpuUnsupported,
+        }
+    }
+
+    /// [`VirtualizationInfo::kind`] 的取值，操作系统检查失败时无论 CPU 是否支持都为 `unknown`
+    fn kind(self, os_check_failed: bool) -> &'static str {
+        match self {
+            _ if os_check_failed => "unknown",
+            VirtStatus::CpuAndOsEnabled => "enabled",
+            VirtStatus::CpuSupportedOsDisabled | VirtStatus::CpuUnsupported => "disabled",
+            VirtStatus::CpuUnsupportedOsReportsEnabled => "unknown",
+        }
+    }
+}
 
 #[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
 pub struct VirtualizationInfo {
+    /// 用于 TypeScript 收窄类型，CPU 支持并且操作系统报告已启用时为 `enabled`；
+    /// CPU 不支持，或 CPU 支持但未在固件或操作系统中启用时为 `disabled`；
+    /// CPU 不支持而操作系统报告已启用 (常见于虚拟机) 或操作系统检查失败时为 `unknown`
+    #[napi(ts_type = "'enabled' | 'disabled' | 'unknown'")]
+    pub kind: &'static str,
     pub arch: &'static str,
     pub os: &'static str,
     pub cpu_supported: bool,
     pub cpu_feature_name: &'static str,
     pub os_reported_enabled: bool,
+    /// 操作系统检查本身失败 (如 macOS 上 `sysctlbyname` 返回 ENOENT 以外的错误)，
+    /// 此时 `osReportedEnabled` 为 false 只表示无法确认，不代表未启用
+    pub os_check_failed: bool,
     pub os_check_details: String,
+    /// 结构化的检测结论，判断时使用该字段而不是匹配说明文字
+    pub status: VirtStatus,
     pub overall_status_message: String,
+    /// 根据未通过的检查给出的处理步骤，如在 BIOS/UEFI 中启用 VT-x/AMD-V，无需处理时为空列表
+    pub remediation: Vec<String>,
+    /// 仅 macOS 提供，`apple_silicon` 或 `intel`，在 Rosetta 下同样反映真实硬件
+    pub mac_cpu_kind: Option<&'static str>,
+    /// 物理核心数
+    pub core_count: u32,
+    /// 逻辑处理器数
+    pub logical_processor_count: u32,
+    /// CPU family，仅 x86/x86_64 有效，其它架构为 0
+    pub cpu_family: u32,
+    /// CPU model (已合并扩展 model)，仅 x86/x86_64 有效，其它架构为 0
+    pub cpu_model: u32,
+    /// CPU stepping，仅 x86/x86_64 有效，其它架构为 0
+    pub cpu_stepping: u32,
+    pub cpu_vendor: CpuVendor,
+    /// 是否支持 invariant TSC，`null` 表示无法判断
+    pub invariant_tsc: Option<bool>,
+    /// 仅 x86/x86_64 提供，根据 CPU 型号推测的 APIC 虚拟化 (APICv / AVIC) 支持情况
+    ///
+    /// 这是启发式结果，相关能力位位于用户态无法读取的 MSR 中，BIOS 或 Hypervisor 仍可能将其关闭
+    pub apic_virtualization: Option<bool>,
+    pub apic_virtualization_details: Option<String>,
+    /// Hypervisor 位置位时区分 Hyper-V 根分区 (宿主机) 与虚拟机，物理机为 `none`
+    #[napi(ts_type = "'root' | 'guest' | 'none'")]
+    pub hyperv_partition_role: &'static str,
+}
+
+#[napi]
+pub fn get_virtualization() -> VirtualizationInfo {
+    cache::get_or_insert_with("get_virtualization", Lifetime::Volatile, || {
+        let (cpu_supported, vendor_id, cpu_feature_name) = virtualization::check_virtual_support();
+        let cpu_vendor = virtualization::CpuVendor::from_vendor_id(&vendor_id).into();
+        let os = if cfg!(target_os = "windows") {
+            "windows"
+        } else if cfg!(target_os = "linux") {
+            "linux"
+        } else if cfg!(target_os = "macos") {
+            "macos"
+        } else {
+            "unknown"
+        };
+        let arch = if cfg!(target_arch = "x86_64") {
+            "x86_64"
+        } else if cfg!(target_arch = "aarch64") {
+            "aarch64"
+        } else if cfg!(target_arch = "x86") {
+            "x86"
+        } else {
+            "unknown"
+        };
+        // `None` 表示检查失败，无法确认是否启用
+        let (os_reported_enabled, os_check_details): (Option<bool>, String) = {
+            #[cfg(target_os = "windows")]
+            {
+                let (enabled, details) = virtualization::check_virtualization_enabled_windows();
+                (Some(enabled), details)
+            }
+            #[cfg(target_os = "macos")]
+            {
+                virtualization::check_hypervisor_support_macos()
+            }
+            #[cfg(target_os = "linux")]
+            {
+                let (enabled, details) = virtualization::check_kvm_via_api_linux();
+                (Some(enabled), details)
+            }
+            #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+            {
+                (
+                    Some(false),
+                    i18n::localize(
+                        "此操作系统上的启用状态检查未实现或失败",
+                        "Checking whether virtualization is enabled is not implemented on this OS",
+                    )
+                    .to_string(),
+                )
+            }
+        };
+
+        let os_check_failed = os_reported_enabled.is_none();
+        let os_reported_enabled = os_reported_enabled.unwrap_or(false);
+        let status = VirtStatus::new(cpu_supported, os_reported_enabled);
+        let kind = status.kind(os_check_failed);
+        #[allow(unused_mut)]
+        let mut overall_status_message = match status {
+            VirtStatus::CpuAndOsEnabled => i18n::localize(
+                "CPU 支持虚拟化，并且似乎已在操作系统/固件中启用。",
+                "The CPU supports virtualization and it appears to be enabled in the OS/firmware.",
+            )
+            .to_string(),
+            VirtStatus::CpuSupportedOsDisabled => i18n::localize(
+                format!(
+                    "CPU 支持虚拟化 ({})，但操作系统报告其未启用或无法确认。详情: {}",
+                    cpu_feature_name, os_check_details
+                ),
+                format!(
+                    "The CPU supports virtualization ({}), but the OS reports it as disabled or cannot confirm it. Details: {}",
+                    cpu_feature_name, os_check_details
+                ),
+            ),
+            VirtStatus::CpuUnsupportedOsReportsEnabled => i18n::localize(
+                format!(
+                    "CPU 不支持虚拟化 ({})，但操作系统报告支持，这常见于运行在虚拟系统下或不支持检测该 CPU。详情：{}",
+                    cpu_feature_name, os_check_details
+                ),
+                format!(
+                    "The CPU does not report virtualization support ({}), but the OS does. This is common inside a virtual machine or on CPUs this check does not recognize. Details: {}",
+                    cpu_feature_name, os_check_details
+                ),
+            ),
+            VirtStatus::CpuUnsupported => i18n::localize(
+                format!("CPU 不支持虚拟化 ({}).", cpu_feature_name),
+                format!(
+                    "The CPU does not support virtualization ({}).",
+                    cpu_feature_name
+                ),
+            ),
+        };
+
+        #[cfg(target_os = "windows")]
+        {
+            let emulation = get_process_emulation();
+            if emulation.is_emulated {
+                overall_status_message.push_str(&i18n::localize(
+                    format!(
+                        " 注意：当前进程 ({}) 运行在 {} 的模拟器中，基于 CPUID 的结果反映的是模拟器而不是真实 CPU。",
+                        emulation.process_arch, emulation.machine_arch
+                    ),
+                    format!(
+                        " Note: this process ({}) runs under emulation on {}, so CPUID-based results describe the emulator rather than the real CPU.",
+                        emulation.process_arch, emulation.machine_arch
+                    ),
+                ));
+            }
+        }
+
+        #[cfg(target_os = "macos")]
+        let mac_cpu_kind = Some(virtualization::get_mac_cpu_kind());
+        #[cfg(not(target_os = "macos"))]
+        let mac_cpu_kind = None;
+
+        let hyperv_partition_role = virtualization::get_hyperv_partition_role();
+        let remediation = virtualization_remediation(
+            cpu_supported,
+            os_reported_enabled,
+            &cpu_vendor,
+            hyperv_partition_role,
+        );
+
+        let (core_count, logical_processor_count) = virtualization::get_cpu_topology();
+        let (cpu_family, cpu_model, cpu_stepping) = virtualization::get_cpu_signature();
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        let (apic_virtualization, apic_virtualization_details) = {
+            let (supported, details) = virtualization::check_apic_virtualization();
+            (Some(supported), Some(details))
+        };
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        let (apic_virtualization, apic_virtualization_details) = (None, None);
+
+        VirtualizationInfo {
+            kind,
+            os,
+            arch,
+            cpu_supported,
+            cpu_feature_name,
+            os_reported_enabled,
+            os_check_failed,
+            os_check_details,
+            status,
+            overall_status_message,
+            remediation,
+            mac_cpu_kind,
+            core_count,
+            logical_processor_count,
+            cpu_family,
+            cpu_model,
+            cpu_stepping,
+            cpu_vendor,
+            invariant_tsc: virtualization::has_invariant_tsc(),
+            apic_virtualization,
+            apic_virtualization_details,
+            hyperv_partition_role,
+        }
+    })
 }
 
-#[napi]
-pub fn get_virtualization() -> VirtualizationInfo {
-    let (cpu_supported, _, cpu_feature_name) = virtualization::check_virtual_support();
-    let os = if cfg!(target_os = "windows") {
-        "windows"
-    } else if cfg!(target_os = "linux") {
-        "linux"
-    } else if cfg!(target_os = "macos") {
-        "macos"
-    } else {
-        "unknown"
-    };
-    let arch = if cfg!(target_arch = "x86_64") {
-        "x86_64"
-    } else if cfg!(target_arch = "aarch64") {
-        "aarch64"
-    } else {
-        "unknown"
-    };
-    let (os_reported_enabled, os_check_details) = {
-        #[cfg(target_os = "windows")]
-        {
-            virtualization::check_virtualization_enabled_windows()
+/// 根据具体未通过的检查生成处理步骤
+fn virtualization_remediation(
+    cpu_supported: bool,
+    os_reported_enabled: bool,
+    cpu_vendor: &CpuVendor,
+    hyperv_partition_role: &str,
+) -> Vec<String> {
+    let mut steps = vec![];
+    if !cpu_supported {
+        // 虚拟机中 CPU 不支持通常是宿主没有暴露 VT-x/AMD-V
+        if hyperv_partition_role == "guest" {
+            steps.push(
+                i18n::localize(
+                    "当前运行在虚拟机中，需要在宿主机上为此虚拟机开启嵌套虚拟化 (Hyper-V: `Set-VMProcessor -VMName <名称> -ExposeVirtualizationExtensions $true`)。",
+                    "This is a virtual machine; enable nested virtualization for it on the host (Hyper-V: `Set-VMProcessor -VMName <name> -ExposeVirtualizationExtensions $true`).",
+                )
+                .to_string(),
+            );
+        }
+        return steps;
+    }
+    #[cfg_attr(not(any(target_os = "windows", target_os = "linux")), allow(unused))]
+    let firmware_step = match cpu_vendor {
+        CpuVendor::Intel => i18n::localize(
+            "在 BIOS/UEFI 中启用 Intel VT-x (Intel Virtualization Technology)，保存后重启。",
+            "Enable Intel VT-x (Intel Virtualization Technology) in the BIOS/UEFI, save and reboot.",
+        ),
+        CpuVendor::Amd => i18n::localize(
+            "在 BIOS/UEFI 中启用 AMD-V (SVM Mode)，保存后重启。",
+            "Enable AMD-V (SVM Mode) in the BIOS/UEFI, save and reboot.",
+        ),
+        _ => i18n::localize(
+            "在 BIOS/UEFI 中启用 CPU 虚拟化 (VT-x/AMD-V)，保存后重启。",
+            "Enable CPU virtualization (VT-x/AMD-V) in the BIOS/UEFI, save and reboot.",
+        ),
+    };
+    if !os_reported_enabled {
+        #[cfg(target_os = "windows")]
+        steps.push(firmware_step.to_string());
+        #[cfg(target_os = "linux")]
+        if std::path::Path::new("/dev/kvm").exists() {
+            steps.push(
+                i18n::localize(
+                    "将当前用户加入 kvm 组以访问 /dev/kvm: `sudo usermod -aG kvm $USER`，重新登录后生效。",
+                    "Add the current user to the kvm group to access /dev/kvm: `sudo usermod -aG kvm $USER`, then log in again.",
+                )
+                .to_string(),
+            );
+        } else {
+            steps.push(firmware_step.to_string());
+            let module = match cpu_vendor {
+                CpuVendor::Amd => "kvm_amd",
+                _ => "kvm_intel",
+            };
+            steps.push(i18n::localize(
+                format!("加载 KVM 内核模块: `sudo modprobe {module}`。"),
+                format!("Load the KVM kernel module: `sudo modprobe {module}`."),
+            ));
+        }
+        #[cfg(target_os = "macos")]
+        if crate::macos::sysctl_int("kern.hv_support") == Err(libc::ENOENT) {
+            steps.push(
+                i18n::localize(
+                    "升级到 macOS 10.10 或更高版本以使用 Hypervisor Framework。",
+                    "Upgrade to macOS 10.10 or later to use the Hypervisor Framework.",
+                )
+                .to_string(),
+            );
+        } else if hyperv_partition_role == "guest" {
+            steps.push(
+                i18n::localize(
+                    "当前运行在虚拟机中，需要在宿主机上为此虚拟机开启嵌套虚拟化。",
+                    "This is a virtual machine; enable nested virtualization for it on the host.",
+                )
+                .to_string(),
+            );
+        }
+    }
+    // 固件已启用但 Hypervisor 没有运行：可选功能已开启时通常是启动项中禁用了 Hypervisor
+    #[cfg(all(feature = "features", target_os = "windows"))]
+    if os_reported_enabled && hyperv_partition_role == "none" {
+        let hyperv = windows_feature::hypervisor::check_hyperv_via_wmi().unwrap_or(false);
+        let vmp = windows_feature::wsl::check_vmp_via_wmi().unwrap_or(None);
+        if hyperv || vmp == Some(true) {
+            steps.push(
+                i18n::localize(
+                    "Hyper-V 相关功能已启用但 Hypervisor 未运行，请以管理员身份执行 `bcdedit /enum {current}` 检查 hypervisorlaunchtype，必要时执行 `bcdedit /set hypervisorlaunchtype auto` 并重启。",
+                    "Hyper-V features are enabled but the hypervisor is not running. Run `bcdedit /enum {current}` as administrator to check hypervisorlaunchtype; if needed run `bcdedit /set hypervisorlaunchtype auto` and reboot.",
+                )
+                .to_string(),
+            );
+        }
+        if vmp == Some(false) && windows_feature::wsl::check_wsl_via_reg() {
+            steps.push(
+                i18n::localize(
+                    "WSL2 需要启用 VirtualMachinePlatform 可选功能: `dism /online /enable-feature /featurename:VirtualMachinePlatform /all /norestart`，完成后重启。",
+                    "WSL2 requires the VirtualMachinePlatform optional feature: `dism /online /enable-feature /featurename:VirtualMachinePlatform /all /norestart`, then reboot.",
+                )
+                .to_string(),
+            );
+        }
+    }
+    steps
+}
+
+/// 判断当前运行在 Hyper-V 根分区 (宿主机)、虚拟机中还是物理机上，返回 `root`、`guest` 或 `none`
+///
+/// 开启 Hyper-V 的宿主机同样带有 Hypervisor 位，需要通过 0x40000003 的分区权限区分
+#[napi(ts_return_type = "'root' | 'guest' | 'none'")]
+pub fn get_hyperv_partition_role() -> &'static str {
+    virtualization::get_hyperv_partition_role()
+}
+
+/// Hyper-V 客户机可用的 enlightenment，来自 CPUID 叶 0x40000003 和 0x40000004
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct HyperVGuestFeatures {
+    /// 可以读取 VP 运行时间 (HV_X64_MSR_VP_RUNTIME)
+    pub access_vp_runtime: bool,
+    /// 可以读取分区引用计数器 (HV_X64_MSR_TIME_REF_COUNT)
+    pub access_partition_reference_counter: bool,
+    /// 可以访问合成中断控制器 (SynIC)
+    pub access_synic: bool,
+    /// 可以访问合成定时器
+    pub access_synthetic_timers: bool,
+    /// 可以通过 MSR 访问 APIC (EOI/ICR/TPR)
+    pub access_apic_msrs: bool,
+    /// 可以访问 Hypercall MSR
+    pub access_hypercall_msrs: bool,
+    /// 可以读取虚拟处理器索引
+    pub access_vp_index: bool,
+    /// 可以访问分区引用 TSC 页
+    pub access_partition_reference_tsc: bool,
+    /// 可以读取 TSC/APIC 频率 MSR
+    pub access_frequency_msrs: bool,
+    /// 宿主是根分区 (可以创建分区)
+    pub create_partitions: bool,
+    /// 建议使用 Hypercall 切换地址空间
+    pub hypercall_address_space_switch_recommended: bool,
+    /// 建议使用 Hypercall 刷新本地 TLB
+    pub hypercall_local_flush_recommended: bool,
+    /// 建议使用 Hypercall 刷新远程 TLB
+    pub hypercall_remote_flush_recommended: bool,
+    /// 建议使用 MSR 访问 APIC
+    pub apic_msrs_recommended: bool,
+    /// 建议使用 MSR 重置系统
+    pub msr_reset_recommended: bool,
+    /// 建议放宽超时检测，客户机可能因宿主调度出现长时间停顿
+    pub relaxed_timing_recommended: bool,
+    /// 自旋锁在通知 Hypervisor 前的建议重试次数，0xFFFFFFFF 表示从不通知
+    pub spinlock_retries: u32,
+}
+
+/// 获取 Hyper-V 客户机的 enlightenment 信息，用于调优客户机性能
+///
+/// 物理机、非 Hyper-V 的 Hypervisor 或非 x86/x86_64 架构返回 `null`
+#[napi]
+pub fn get_hyperv_guest_features() -> Option<HyperVGuestFeatures> {
+    cache::get_or_insert_with("get_hyperv_guest_features", Lifetime::Static, || {
+        let (features, privileges, recommendations, spinlock_retries) =
+            virtualization::read_hyperv_guest_leaves()?;
+        let bit = |value: u32, index: u32| value & (1 << index) != 0;
+        Some(HyperVGuestFeatures {
+            access_vp_runtime: bit(features, 0),
+            access_partition_reference_counter: bit(features, 1),
+            access_synic: bit(features, 2),
+            access_synthetic_timers: bit(features, 3),
+            access_apic_msrs: bit(features, 4),
+            access_hypercall_msrs: bit(features, 5),
+            access_vp_index: bit(features, 6),
+            access_partition_reference_tsc: bit(features, 9),
+            access_frequency_msrs: bit(features, 11),
+            create_partitions: bit(privileges, 0),
+            hypercall_address_space_switch_recommended: bit(recommendations, 0),
+            hypercall_local_flush_recommended: bit(recommendations, 1),
+            hypercall_remote_flush_recommended: bit(recommendations, 2),
+            apic_msrs_recommended: bit(recommendations, 3),
+            msr_reset_recommended: bit(recommendations, 4),
+            relaxed_timing_recommended: bit(recommendations, 5),
+            spinlock_retries,
+        })
+    })
+}
+
+#[napi(string_enum)]
+#[cfg_attr(not(feature = "napi"), derive(Clone, Copy))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum HypervisorVendor {
+    HyperV,
+    VMware,
+    VirtualBox,
+    Kvm,
+    Xen,
+    Parallels,
+    QEMU,
+    Unknown,
+    None,
+}
+
+impl From<virtualization::HypervisorVendor> for HypervisorVendor {
+    fn from(value: virtualization::HypervisorVendor) -> Self {
+        match value {
+            virtualization::HypervisorVendor::HyperV => HypervisorVendor::HyperV,
+            virtualization::HypervisorVendor::VMware => HypervisorVendor::VMware,
+            virtualization::HypervisorVendor::VirtualBox => HypervisorVendor::VirtualBox,
+            virtualization::HypervisorVendor::Kvm => HypervisorVendor::Kvm,
+            virtualization::HypervisorVendor::Xen => HypervisorVendor::Xen,
+            virtualization::HypervisorVendor::Parallels => HypervisorVendor::Parallels,
+            virtualization::HypervisorVendor::Qemu => HypervisorVendor::QEMU,
+            virtualization::HypervisorVendor::Unknown => HypervisorVendor::Unknown,
+            virtualization::HypervisorVendor::None => HypervisorVendor::None,
+        }
+    }
+}
+
+/// 根据 CPUID 0x40000000 的厂商签名判断当前运行在哪种 Hypervisor 下，物理机 (Hypervisor 位未置位) 返回 `None`
+///
+/// 签名可以被 Hypervisor 修改或隐藏，不应作为唯一的安全依据；`QEMU` 仅指纯软件模拟 (TCG)，
+/// 使用 KVM 加速的 QEMU 返回 `Kvm`
+#[napi]
+pub fn detect_hypervisor_vendor() -> HypervisorVendor {
+    cache::get_or_insert_with("detect_hypervisor_vendor", Lifetime::Static, || {
+        virtualization::HypervisorVendor::from_signature(
+            virtualization::read_hypervisor_signature().as_deref(),
+        )
+        .into()
+    })
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct HypervisorSignature {
+    /// CPUID 叶 1 ECX 第 31 位 (Hypervisor 位) 是否置位
+    pub present: bool,
+    /// 0x40000000 返回的 12 字节厂商签名，如 `Microsoft Hv`、`VMwareVMware`、`KVMKVMKVM`、`VBoxVBoxVBox`，
+    /// 未知厂商同样原样返回，`present` 为 false 时为空字符串
+    pub signature: String,
+    /// 运行在虚拟机中，Hypervisor 位置位但当前为 Hyper-V 根分区 (宿主机) 时为 false
+    pub is_guest: bool,
+}
+
+/// 获取 Hypervisor 的 CPUID 厂商签名，用于区分当前运行在哪种 Hypervisor 下
+///
+/// 非 x86/x86_64 架构始终返回 `present: false`
+#[napi]
+pub fn get_hypervisor_signature() -> HypervisorSignature {
+    cache::get_or_insert_with("get_hypervisor_signature", Lifetime::Static, || {
+        let signature = virtualization::read_hypervisor_signature();
+        HypervisorSignature {
+            present: signature.is_some(),
+            is_guest: virtualization::get_hyperv_partition_role() == "guest",
+            signature: signature.unwrap_or_default(),
+        }
+    })
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct NestedVirtualizationSupport {
+    /// Intel EPT (Extended Page Tables)
+    pub ept_supported: bool,
+    /// Intel 无限制客户机 (Unrestricted Guest)，AMD 处理器与 `nestedPaging` 相同；无法读取时为空
+    pub unrestricted_guest: Option<bool>,
+    /// 二级地址转换: Intel EPT 或 AMD RVI (NPT)
+    pub nested_paging: bool,
+    /// 满足运行嵌套 Hypervisor 的要求: Intel 需要 EPT 与无限制客户机，AMD 需要 NPT；
+    /// 无限制客户机未知时只根据嵌套分页判断
+    pub supported: bool,
+    /// cpuid 叶 0 的厂商字符串，如 `GenuineIntel`
+    pub vendor: String,
+    pub details: String,
+}
+
+/// 检查 CPU 是否具备在客户机中运行 Hypervisor 所需的特性，可以在创建嵌套虚拟机之前判断
+///
+/// Windows 无法读取 VMX 能力 MSR，Intel 处理器只能通过 SLAT 推断 EPT，`unrestrictedGuest` 为空；
+/// aarch64 上所有字段均为 false
+#[napi]
+pub fn check_nested_virtualization() -> NestedVirtualizationSupport {
+    cache::get_or_insert_with("check_nested_virtualization", Lifetime::Static, || {
+        let (ept_supported, unrestricted_guest, nested_paging, details) =
+            virtualization::check_nested_virtualization_features();
+        let (_, vendor, _) = virtualization::check_virtual_support();
+        NestedVirtualizationSupport {
+            ept_supported,
+            unrestricted_guest,
+            nested_paging,
+            supported: nested_paging && unrestricted_guest != Some(false),
+            vendor,
+            details,
+        }
+    })
+}
+
+/// 在 Windows 虚拟机中检测宿主是否开启了嵌套虚拟化，开启后才能在虚拟机内使用 WSL2 或 Hyper-V
+///
+/// 物理机和开启了 Hyper-V 的宿主机返回 `disabled`
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_os = "windows"
+))]
+#[napi]
+pub fn check_nested_virtualization_in_guest() -> FeatureStatus {
+    cache::get_or_insert_with(
+        "check_nested_virtualization_in_guest",
+        Lifetime::Static,
+        || {
+            let (available, details) = virtualization::check_nested_enabled_in_guest();
+            // 与 check_nested_enabled_in_guest 的判断顺序一致，每个执行过的探测对应一条结果
+            let identity = virtualization::cpu_identity();
+            let mut checks = vec![CheckResult::new(
+                "cpuid_hypervisor",
+                if identity.hypervisor_present {
+                    "present"
+                } else {
+                    "absent"
+                },
+                Some(identity.hypervisor_signature.clone()),
+            )];
+            if identity.hypervisor_present {
+                let role = virtualization::get_hyperv_partition_role();
+                checks.push(CheckResult::new("partition_role", role, None));
+                if role != "root" {
+                    let (exposed, _, feature_name) = virtualization::check_virtual_support();
+                    checks.push(CheckResult::new(
+                        "cpuid_feature",
+                        if exposed { "exposed" } else { "hidden" },
+                        Some(feature_name.to_string()),
+                    ));
+                }
+            }
+            FeatureStatus {
+                kind: if available { "enabled" } else { "disabled" },
+                enabled: available,
+                details: vec![details],
+                checks,
+            }
+        },
+    )
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct SessionContext {
+    /// 是否运行在远程桌面 (RDP) 会话中，此时枚举到的显卡为远程显示适配器
+    pub is_rdp: bool,
+    /// 当前进程所在的会话 ID
+    pub session_id: u32,
+    /// 是否运行在会话 0 中，Vista 之后只有服务 (及以服务身份启动的进程) 位于会话 0，无法访问桌面与显卡
+    pub is_service: bool,
+}
+
+/// 获取当前进程的会话信息，GPU、显示相关的检测在 RDP 会话或会话 0 中会得到不同的结果
+#[cfg(target_os = "windows")]
+#[napi]
+pub fn get_session_context() -> SessionContext {
+    let (is_rdp, session_id) = virtualization::get_session_context_windows();
+    SessionContext {
+        is_rdp,
+        session_id,
+        is_service: session_id == 0,
+    }
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct ProcessEmulation {
+    /// 是否运行在 WOW64 下 (如 x64 系统上的 x86 进程)
+    pub is_wow64: bool,
+    /// 当前进程映像的架构
+    pub process_arch: &'static str,
+    /// 本机真实的架构
+    pub machine_arch: &'static str,
+    /// 进程架构与本机架构不同，此时 CPUID 结果来自模拟器而不是真实 CPU
+    pub is_emulated: bool,
+}
+
+#[cfg(target_os = "windows")]
+#[napi]
+pub fn get_process_emulation() -> ProcessEmulation {
+    cache::get_or_insert_with("get_process_emulation", Lifetime::Static, || {
+        let (is_wow64, process_arch, machine_arch) =
+            virtualization::get_process_emulation_windows();
+        ProcessEmulation {
+            is_wow64,
+            process_arch,
+            machine_arch,
+            is_emulated: process_arch != machine_arch && machine_arch != "unknown",
+        }
+    })
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct GuestInfo {
+    pub is_guest: bool,
+    pub vendor: Option<&'static str>,
+    pub details: String,
+}
+
+/// 检测当前是否运行在虚拟机中
+///
+/// macOS 检查 IORegistry 的平台信息；Linux 检查设备树的 `/hypervisor` 节点 (ARM 客户机)，再退回到 CPUID
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+#[napi]
+pub fn detect_vm() -> GuestInfo {
+    cache::get_or_insert_with("detect_vm", Lifetime::Static, || {
+        #[cfg(target_os = "macos")]
+        let (is_guest, vendor, details) = virtualization::detect_vm_macos();
+        #[cfg(target_os = "linux")]
+        let (is_guest, vendor, details) = virtualization::detect_vm_linux();
+        GuestInfo {
+            is_guest,
+            vendor,
+            details,
+        }
+    })
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct IsolationInfo {
+    /// 当前进程是否运行在应用隔离环境 (沙盒) 中
+    pub is_isolated: bool,
+    #[napi(ts_type = "'sandboxie' | null")]
+    pub vendor: Option<&'static str>,
+    pub details: Vec<String>,
+}
+
+/// 检测当前进程是否运行在 Sandboxie 等应用级沙盒中，这类隔离与虚拟机、容器无关，CPUID 等检测无法发现
+///
+/// 以进程中是否注入了 `SbieDll.dll` 为准，`SbieDrv`/`SbieSvc` 仅说明本机安装了 Sandboxie
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn detect_isolation() -> IsolationInfo {
+    cache::get_or_insert_with("detect_isolation", Lifetime::Static, || {
+        let mut details = vec![];
+        for name in ["SbieDrv", "SbieSvc"] {
+            match windows_feature::isolation::check_sandboxie_via_service(name) {
+                Ok(running) => details.push(format!(
+                    "服务 '{name}': 状态为 '{}'。",
+                    if running { "正在运行" } else { "已停止" }
+                )),
+                Err(_) => details.push(format!("服务 '{name}': 未安装。")),
+            }
+        }
+        let injected = windows_feature::isolation::check_sandboxie_dll();
+        details.push(format!(
+            "模块检查: 当前进程{}加载 SbieDll.dll。",
+            if injected { "已" } else { "未" }
+        ));
+        IsolationInfo {
+            is_isolated: injected,
+            vendor: injected.then_some("sandboxie"),
+            details,
+        }
+    })
+}
+
+/// 检测当前是否运行在容器中，返回容器类型，不在容器中时返回 `null`
+///
+/// `lxd` 和 `systemd-nspawn` 是运行完整 init 的系统容器，`docker` 和 `podman` 是应用容器，
+/// 无法识别的容器类型返回 `other`
+#[cfg(target_os = "linux")]
+#[napi(ts_return_type = "'lxd' | 'lxc' | 'systemd-nspawn' | 'docker' | 'podman' | 'other' | null")]
+pub fn detect_container() -> Option<&'static str> {
+    cache::get_or_insert_with(
+        "detect_container",
+        Lifetime::Static,
+        virtualization::detect_container_linux,
+    )
+}
+
+/// 获取 cgroup 层级版本，返回 2 (unified)、1 (legacy) 或 0 (未挂载或非 Linux)
+///
+/// 两种层级读取资源限制的方式不同，如内存限制分别位于 `memory.max` 和 `memory/memory.limit_in_bytes`
+#[napi]
+pub fn get_cgroup_version() -> u32 {
+    cache::get_or_insert_with("get_cgroup_version", Lifetime::Static, || {
+        #[cfg(target_os = "linux")]
+        {
+            virtualization::get_cgroup_version_linux()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            0
+        }
+    })
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct WslGuestInfo {
+    /// 当前进程运行在 WSL 发行版中
+    pub inside_wsl: bool,
+    /// WSL 版本，1 或 2，不在 WSL 中时为 `null`
+    pub version: Option<u32>,
+}
+
+/// 检测当前进程是否运行在 WSL 发行版中，是 `isWslEnabled` 在 Linux 一侧的对应功能
+///
+/// 非 Linux 平台始终返回 `insideWsl: false`
+#[napi]
+pub fn get_wsl_guest_info() -> WslGuestInfo {
+    cache::get_or_insert_with("get_wsl_guest_info", Lifetime::Static, || {
+        #[cfg(target_os = "linux")]
+        let version = virtualization::detect_wsl_guest_linux();
+        #[cfg(not(target_os = "linux"))]
+        let version = None;
+        WslGuestInfo {
+            inside_wsl: version.is_some(),
+            version,
+        }
+    })
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct MemoryInfo {
+    /// 物理内存总量 (MB)
+    pub total_mb: u32,
+    /// 可用内存 (MB)，包括可以立即回收的缓存
+    pub available_mb: u32,
+    /// 安装的内存是否达到运行虚拟机的最低要求 (4 GB)
+    ///
+    /// Windows 以 SMBIOS 报告的安装容量为准；Linux 只能得到内核可见的内存，会扣除固件和内核保留的部分后再比较
+    pub sufficient_for_vm: bool,
+}
+
+/// 获取物理内存总量和可用内存，查询失败时返回 `null`
+///
+/// Windows 查询 `Win32_OperatingSystem`，Linux 读取 `/proc/meminfo`，macOS 读取 `hw.memsize` 和 VM 统计信息
+#[cfg(any(
+    target_os = "linux",
+    target_os = "macos",
+    all(feature = "features", target_os = "windows")
+))]
+#[napi]
+pub fn get_memory_info() -> Option<MemoryInfo> {
+    // 创建虚拟机或启用 WSL2、Windows 沙盒时建议的最低物理内存
+    const MIN_VM_MEMORY_MB: u64 = 4096;
+    // 固件、内核和集成显卡保留的内存，安装 4 GB 时系统通常只能看到 3.5 ~ 3.9 GB
+    const RESERVED_MEMORY_MB: u64 = 512;
+    cache::get_or_insert_with("get_memory_info", Lifetime::Volatile, || {
+        #[cfg(target_os = "windows")]
+        let memory = windows_feature::memory::get_memory_via_wmi().ok();
+        #[cfg(target_os = "linux")]
+        let memory = virtualization::get_memory_info_linux();
+        #[cfg(target_os = "macos")]
+        let memory = virtualization::get_memory_info_macos();
+        memory.map(|(total, available)| {
+            #[cfg(target_os = "windows")]
+            let installed = windows_feature::memory::get_installed_memory_mb();
+            #[cfg(target_os = "linux")]
+            let installed = None::<u64>;
+            // hw.memsize 即安装的内存容量
+            #[cfg(target_os = "macos")]
+            let installed = Some(total);
+            MemoryInfo {
+                total_mb: total.min(u32::MAX as u64) as u32,
+                available_mb: available.min(u32::MAX as u64) as u32,
+                sufficient_for_vm: match installed {
+                    Some(installed) => installed >= MIN_VM_MEMORY_MB,
+                    None => total + RESERVED_MEMORY_MB >= MIN_VM_MEMORY_MB,
+                },
+            }
+        })
+    })
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct SystemEncoding {
+    /// Windows 为 ANSI 代码页 (`GetACP`)；macOS 为系统文本编码 (`CFStringGetSystemEncoding`) 对应的代码页，没有对应时为 0
+    pub ansi_code: u32,
+    /// Windows 为 OEM 代码页 (`GetOEMCP`)；macOS 为终端 locale (`LC_ALL`/`LC_CTYPE`/`LANG`) 的字符集对应的代码页，无法识别时为 0
+    pub oem_code: u32,
+    pub ansi_encoding: &'static str,
+    pub oem_encoding: &'static str,
+}
+
+/// 获取系统编码，macOS 上终端编码以 `oemCode`/`oemEncoding` 为准
+#[allow(deprecated)]
+#[cfg(all(feature = "encoding", any(target_os = "windows", target_os = "macos")))]
+#[napi]
+pub fn get_system_encoding() -> SystemEncoding {
+    cache::get_or_insert_with("get_system_encoding", Lifetime::Volatile, || {
+        #[cfg(target_os = "windows")]
+        let ((ansi_code, ansi_encoding), (oem_code, oem_encoding)) = (
+            encoding::get_system_encoding(),
+            encoding::get_console_encoding(),
+        );
+        #[cfg(target_os = "macos")]
+        let ((ansi_code, ansi_encoding), (oem_code, oem_encoding)) = (
+            encoding::get_system_encoding_macos(),
+            encoding::get_locale_encoding_macos(),
+        );
+        SystemEncoding {
+            ansi_code,
+            ansi_encoding,
+            oem_code,
+            oem_encoding,
+        }
+    })
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct ConsoleEncoding {
+    /// 控制台输入代码页 (`GetConsoleCP`)，进程没有附加控制台时为 0
+    pub input_code: u32,
+    /// 控制台输出代码页 (`GetConsoleOutputCP`)，进程没有附加控制台时为 0
+    pub output_code: u32,
+    pub input_encoding: &'static str,
+    pub output_encoding: &'static str,
+    /// 输入与输出代码页不同，此时通过管道读写的数据需要分别解码
+    pub mismatch: bool,
+}
+
+/// 获取当前控制台的输入和输出代码页，二者可以通过 `SetConsoleCP`/`SetConsoleOutputCP` 分别修改
+///
+/// 代码页随 `chcp` 随时变化且因控制台而异，每次调用都会重新读取，不经过缓存
+#[cfg(all(feature = "encoding", target_os = "windows"))]
+#[napi]
+pub fn get_console_encoding() -> ConsoleEncoding {
+    let ((input_code, input_encoding), (output_code, output_encoding)) =
+        encoding::get_console_code_pages();
+    ConsoleEncoding {
+        input_code,
+        output_code,
+        input_encoding,
+        output_encoding,
+        mismatch: input_code != output_code,
+    }
+}
+
+/// 控制台的输入和输出代码页是否都为 UTF-8 (65001)，此时可以跳过旧代码页的解码
+///
+/// Windows Terminal 或系统设置中开启了 "使用 Unicode UTF-8 提供全球语言支持" 时较为常见；
+/// 每次调用都会重新读取，`chcp 65001` 之后立即生效
+#[cfg(all(feature = "encoding", target_os = "windows"))]
+#[napi]
+pub fn is_console_utf8() -> bool {
+    let ((input_code, _), (output_code, _)) = encoding::get_console_code_pages();
+    input_code == 65001 && output_code == 65001
+}
+
+/// 将 Windows 代码页 (如 `getConsoleEncoding` 返回的 `outputCode`) 转换为 iconv / `iconv-lite` 可以识别的编码名，
+/// 可以直接传给 `iconv.decode(buffer, name)`，无法识别时返回 `unknown`
+#[cfg(feature = "encoding")]
+#[napi]
+pub fn codepage_to_iconv_name(code_page: u32) -> &'static str {
+    encoding::codepage_to_iconv_name(code_page)
+}
+
+#[napi]
+pub fn get_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// 说明文字使用的语言
+#[napi]
+pub enum Language {
+    /// 简体中文，默认值
+    ZhCn,
+    En,
+}
+
+impl From<Language> for i18n::Language {
+    fn from(value: Language) -> Self {
+        match value {
+            Language::ZhCn => i18n::Language::ZhCn,
+            Language::En => i18n::Language::En,
+        }
+    }
+}
+
+/// 设置 `overallStatusMessage`、`osCheckDetails`、`remediation` 等说明文字的语言，默认为简体中文
+///
+/// 对之后的检测生效，切换时会丢弃所有缓存的结果，避免返回另一种语言的说明文字；说明文字只用于展示，不要据此做判断
+#[napi]
+pub fn set_language(language: Language) {
+    if i18n::set_language(language.into()) {
+        cache::clear();
+    }
+}
+
+/// 开启检测结果缓存并设置有效期 (毫秒)，省略时为 30 秒，传入 0 关闭缓存并清空已有结果
+///
+/// 缓存默认关闭。开启后 CPU、固件等硬件信息一直有效，Hyper-V、WSL 等用户可以随时修改的状态超过有效期后重新检测
+#[napi]
+pub fn set_cache_ttl_ms(ms: Option<u32>) {
+    cache::set_ttl_ms(ms.unwrap_or(cache::DEFAULT_TTL_MS));
+}
+
+/// 清空缓存的检测结果 (包括 Machine ID 缓存的因子)，不影响缓存开关
+#[napi]
+pub fn clear_cache() {
+    cache::clear();
+    #[cfg(all(
+        feature = "machine-id",
+        any(target_os = "windows", target_os = "macos", target_os = "linux")
+    ))]
+    machine_id::clear_cache();
+}
+
+/// 丢弃所有缓存的检测结果 (包括 CPU、固件等一直有效的结果)，之后的调用都会重新检测，不影响缓存开关
+///
+/// 运行期间可能变化、刷新后能反映新状态的结果：Hyper-V、WSL、VirtualMachinePlatform 等可选功能与服务的状态、
+/// 内存、显卡驱动、WSL 内核版本等。CPU 型号与功能位、固件中的虚拟化开关只会在重启后变化，修改 BIOS/UEFI
+/// 设置后需要重启，刷新没有意义；CPUID 的结果在进程内只读取一次，不会因刷新而重新读取
+#[napi]
+pub fn refresh_all() {
+    clear_cache();
+}
+
+/// 丢弃单个函数缓存的结果，下一次调用该函数时重新检测，`name` 为导出的函数名，如 `isWslEnabled`
+///
+/// 返回是否存在被丢弃的结果，缓存关闭或尚未调用过该函数时为 false
+#[napi]
+pub fn refresh(name: String) -> bool {
+    cache::remove(&cache::key_from_name(&name))
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct FeatureStatus {
+    /// 用于 TypeScript 收窄类型，`unknown` 表示所有检测方法均失败，无法确定状态
+    #[napi(ts_type = "'enabled' | 'disabled' | 'unknown'")]
+    pub kind: &'static str,
+    pub enabled: bool,
+    /// 供用户阅读的检测过程
+    pub details: Vec<String>,
+    /// 与 `details` 对应的结构化结果，便于程序判断，尚未提供结构化结果的检查为空列表
+    pub checks: Vec<CheckResult>,
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct CheckResult {
+    /// 检测方法，如 `file`、`service`、`registry`、`wmi`
+    pub method: String,
+    /// 检测结果，如 `running`、`stopped`、`enabled`、`disabled`、`error`
+    pub result: String,
+    /// 原始值，如版本号、可选功能名称或查询失败时的错误信息
+    pub raw: Option<String>,
+    /// 查询失败时的错误码，如 `WMI_QUERY_FAILED`、`SERVICE_QUERY_FAILED`，其它结果为 `null`
+    pub code: Option<&'static str>,
+}
+
+#[cfg(any(
+    all(feature = "features", target_os = "windows"),
+    all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_os = "windows"
+    )
+))]
+impl CheckResult {
+    fn new(method: &str, result: &str, raw: Option<String>) -> Self {
+        CheckResult {
+            method: method.to_string(),
+            result: result.to_string(),
+            raw,
+            code: None,
+        }
+    }
+
+    #[cfg(feature = "features")]
+    fn error(method: &str, err: &windows_feature::WindowsFeatureError) -> Self {
+        CheckResult {
+            code: Some(err.code()),
+            ..CheckResult::new(method, "error", Some(err.to_string()))
+        }
+    }
+}
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+impl FeatureStatus {
+    fn enabled(details: Vec<String>) -> Self {
+        FeatureStatus {
+            kind: "enabled",
+            enabled: true,
+            details,
+            checks: vec![],
+        }
+    }
+
+    fn disabled(details: Vec<String>) -> Self {
+        FeatureStatus {
+            kind: "disabled",
+            enabled: false,
+            details,
+            checks: vec![],
+        }
+    }
+
+    fn unknown(details: Vec<String>) -> Self {
+        FeatureStatus {
+            kind: "unknown",
+            enabled: false,
+            details,
+            checks: vec![],
+        }
+    }
+
+    fn with_checks(mut self, checks: Vec<CheckResult>) -> Self {
+        self.checks = checks;
+        self
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "features", target_os = "windows"))]
+impl FeatureStatus {
+    /// 供 `*Strict` 系列函数使用，`unknown` 转换为错误，`code` 取自第一个失败的检测方法
+    pub(crate) fn into_strict(self) -> napi::Result<FeatureStatus, &'static str> {
+        if self.kind != "unknown" {
+            return Ok(self);
+        }
+        let code = self
+            .checks
+            .iter()
+            .find_map(|it| it.code)
+            .unwrap_or("UNKNOWN");
+        Err(napi::Error::new(code, self.details.join(" ")))
+    }
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct WindowsVersion {
+    pub major: u32,
+    pub minor: u32,
+    /// 内部版本号，如 `22631`
+    pub build: u32,
+    /// 修订号 (Update Build Revision)，随每月累积更新递增
+    pub ubr: u32,
+    /// 功能更新版本，如 `23H2`，旧系统为 `ReleaseId` (如 `1909`)，都不存在时为 `null`
+    pub display_version: Option<String>,
+}
+
+/// 获取 Windows 版本，读取失败的字段为 0
+///
+/// 部分功能依赖系统版本，例如 WSL2 需要 Windows 10 1903 (build 18362) 及以上
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn get_windows_version() -> WindowsVersion {
+    cache::get_or_insert_with("get_windows_version", Lifetime::Static, || {
+        let (major, minor, build, ubr, display_version) =
+            windows_feature::version::read_windows_version_via_reg().unwrap_or((0, 0, 0, 0, None));
+        WindowsVersion {
+            major,
+            minor,
+            build,
+            ubr,
+            display_version,
+        }
+    })
+}
+
+/// 获取 Windows 版本类型 (注册表中的 `EditionID`)，如 `Professional`、`Core` (家庭版)，读取失败时为 `null`
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn get_windows_edition() -> Option<String> {
+    cache::get_or_insert_with(
+        "get_windows_edition",
+        Lifetime::Static,
+        windows_feature::version::read_edition_id_via_reg,
+    )
+}
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_hyperv_enabled() -> FeatureStatus {
+    cache::get_or_insert_with("is_hyperv_enabled", Lifetime::Volatile, || {
+        let mut details = vec![];
+        let mut checks = vec![];
+        // 至少有一种检测方法成功执行，否则结果为 unknown
+        let mut conclusive = false;
+        // Hyper-V 已启用时补充 PowerShell 模块的状态，说明 `Get-VM` 等命令为何可能不可用
+        let enabled_status = |mut details: Vec<String>, mut checks: Vec<CheckResult>| {
+            let installed = has_hyperv_powershell_module();
+            details.push(if installed {
+                "PowerShell 模块: Hyper-V 模块已安装。".to_string()
+            } else {
+                "PowerShell 模块: 未安装 Hyper-V 模块 (Microsoft-Hyper-V-Management-PowerShell)，Get-VM 等命令不可用。".to_string()
+            });
+            checks.push(CheckResult::new(
+                "powershell_module",
+                if installed { "installed" } else { "missing" },
+                None,
+            ));
+            FeatureStatus::enabled(details).with_checks(checks)
+        };
+
+        match windows_feature::hypervisor::check_hyperv_via_service() {
+            Ok(running) => {
+                conclusive = true;
+                details.push(format!(
+                    "服务 'vmms': 状态为 '{}'。",
+                    if running { "正在运行" } else { "已停止" }
+                ));
+                checks.push(CheckResult::new(
+                    "service",
+                    if running { "running" } else { "stopped" },
+                    Some("vmms".to_string()),
+                ));
+                if running {
+                    return enabled_status(details, checks);
+                }
+            }
+            Err(err) => {
+                details.push(format!("服务 'vmms' 查询失败: {err}。"));
+                checks.push(CheckResult::error("service", &err));
+            }
+        }
+        match windows_feature::hypervisor::check_hyperv_via_wmi() {
+            Ok(enabled) => {
+                conclusive = true;
+                details.push(format!(
+                    "WMI 检查: Hyper-V 可选功能状态为 {}。",
+                    if enabled { "已启用" } else { "未启用" }
+                ));
+                checks.push(CheckResult::new(
+                    "wmi",
+                    if enabled { "enabled" } else { "disabled" },
+                    Some("Microsoft-Hyper-V-All".to_string()),
+                ));
+                if enabled {
+                    return enabled_status(details, checks);
+                }
+            }
+            Err(err) => {
+                checks.push(CheckResult::error("wmi", &err));
+                details.push(err.to_string());
+            }
+        }
+        details.push("所有检测方法均未能确认 Hyper-V 已完全启用。".to_string());
+        if conclusive {
+            FeatureStatus::disabled(details).with_checks(checks)
+        } else {
+            FeatureStatus::unknown(details).with_checks(checks)
+        }
+    })
+}
+
+/// 与 `isHypervEnabled` 相同，但所有检测方法均失败时抛出带有 `code` 的错误 (如 `WMI_QUERY_FAILED`)，而不是返回 `unknown`
+#[cfg(all(feature = "napi", feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_hyperv_enabled_strict() -> napi::Result<FeatureStatus, &'static str> {
+    is_hyperv_enabled().into_strict()
+}
+
+/// 检查 Hyper-V PowerShell 模块是否可用，缺少该模块时即使 Hyper-V 已启用也无法使用 `Get-VM` 等命令
+///
+/// 检查 `Microsoft-Hyper-V-Management-PowerShell` 可选功能以及系统 PowerShell 模块目录，任一满足即可
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn has_hyperv_powershell_module() -> bool {
+    cache::get_or_insert_with("has_hyperv_powershell_module", Lifetime::Volatile, || {
+        windows_feature::hypervisor::check_powershell_module_via_dir()
+            || windows_feature::hypervisor::check_powershell_module_via_wmi().unwrap_or(false)
+    })
+}
+
+/// 检查 Hyper-V 的 Default Switch 是否存在，WSL2 和使用默认网络的虚拟机依赖它进行 NAT
+///
+/// 未安装 Hyper-V (`root\virtualization\v2` 不存在) 或查询失败时返回 `false`
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn has_hyperv_default_switch() -> bool {
+    cache::get_or_insert_with("has_hyperv_default_switch", Lifetime::Volatile, || {
+        matches!(
+            windows_feature::hypervisor::check_default_switch_via_wmi(),
+            Ok(Some(true))
+        )
+    })
+}
+
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_wsl_enabled() -> FeatureStatus {
+    cache::get_or_insert_with("is_wsl_enabled", Lifetime::Volatile, || {
+        let mut details = vec![];
+        let mut checks = vec![];
+
+        if !std::path::Path::new("C:\\Windows\\System32\\wsl.exe").exists() {
+            details.push("文件检查: 未找到 wsl.exe，WSL 未安装。".to_string());
+            checks.push(CheckResult::new(
+                "file",
+                "not_found",
+                Some("wsl.exe".to_string()),
+            ));
+            return FeatureStatus::disabled(details).with_checks(checks);
+        }
+
+        details.push("文件检查: 找到 wsl.exe。".to_string());
+        checks.push(CheckResult::new(
+            "file",
+            "found",
+            Some("wsl.exe".to_string()),
+        ));
+        let version = get_windows_version();
+        if version.build != 0 && version.build < 18362 {
+            details.push(format!(
+                "系统版本: build {}，WSL2 需要 Windows 10 1903 (build 18362) 或更高版本，仅支持 WSL1。",
+                version.build
+            ));
+            checks.push(CheckResult::new(
+                "version",
+                "wsl1_only",
+                Some(version.build.to_string()),
+            ));
+        }
+        // 至少有一种检测方法成功执行，否则结果为 unknown
+        let mut conclusive = false;
+
+        match windows_feature::wsl::check_wsl_via_service() {
+            Ok(running) => {
+                conclusive = true;
+                details.push(format!(
+                    "服务 'LxssManager': 状态为 '{}'。",
+                    if running { "正在运行" } else { "已停止" }
+                ));
+                checks.push(CheckResult::new(
+                    "service",
+                    if running { "running" } else { "stopped" },
+                    Some("LxssManager".to_string()),
+                ));
+                if running {
+                    return FeatureStatus::enabled(details).with_checks(checks);
+                }
+            }
+            Err(err) => {
+                details.push(format!("服务 'LxssManager' 查询失败: {err}。"));
+                checks.push(CheckResult::error("service", &err));
+            }
+        }
+        match windows_feature::wsl::check_wsl_via_reg() {
+            true => {
+                details.push("注册表检查: WSL 已启用。".to_string());
+                checks.push(CheckResult::new("registry", "enabled", None));
+
+                return FeatureStatus::enabled(details).with_checks(checks);
+            }
+            false => {
+                details.push("注册表检查: WSL 未启用。".to_string());
+                checks.push(CheckResult::new("registry", "disabled", None));
+            }
+        }
+        match windows_feature::wsl::check_wsl_via_wmi() {
+            Ok((wsl_enabled, vmp_enabled)) => {
+                conclusive = true;
+                details.push(format!(
+                    "WMI: 'Microsoft-Windows-Subsystem-Linux' 状态为 {}.",
+                    if wsl_enabled {
+                        "已启用"
+                    } else {
+                        "未启用"
+                    }
+                ));
+                details.push(format!(
+                    "WMI: 'VirtualMachinePlatform' 状态为 {}.",
+                    if vmp_enabled {
+                        "已启用"
+                    } else {
+                        "未启用"
+                    }
+                ));
+                for (name, enabled) in [
+                    ("Microsoft-Windows-Subsystem-Linux", wsl_enabled),
+                    ("VirtualMachinePlatform", vmp_enabled),
+                ] {
+                    checks.push(CheckResult::new(
+                        "wmi",
+                        if enabled { "enabled" } else { "disabled" },
+                        Some(name.to_string()),
+                    ));
+                }
+
+                let fully_enabled = wsl_enabled && vmp_enabled;
+                if fully_enabled {
+                    return FeatureStatus::enabled(details).with_checks(checks);
+                }
+            }
+            Err(e) => {
+                details.push(format!("WMI 查询可选功能失败: {e}。"));
+                checks.push(CheckResult::error("wmi", &e));
+            }
+        }
+        details.push("所有检测方法均未能确认 WSL 已完全启用。".to_string());
+        if conclusive {
+            FeatureStatus::disabled(details).with_checks(checks)
+        } else {
+            FeatureStatus::unknown(details).with_checks(checks)
+        }
+    })
+}
+
+/// 与 `isWslEnabled` 相同，但所有检测方法均失败时抛出带有 `code` 的错误 (如 `WMI_QUERY_FAILED`)，而不是返回 `unknown`
+#[cfg(all(feature = "napi", feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_wsl_enabled_strict() -> napi::Result<FeatureStatus, &'static str> {
+    is_wsl_enabled().into_strict()
+}
+
+/// 单独检查 `VirtualMachinePlatform` 可选功能，WSL2 和 Windows 沙盒都依赖它，但它可以独立于 WSL 启用
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_vmp_enabled() -> FeatureStatus {
+    cache::get_or_insert_with("is_vmp_enabled", Lifetime::Volatile, || {
+        let name = Some("VirtualMachinePlatform".to_string());
+        match windows_feature::wsl::check_vmp_via_wmi() {
+            Ok(Some(true)) => FeatureStatus::enabled(vec![
+                "WMI: 'VirtualMachinePlatform' 状态为 已启用。".to_string(),
+            ])
+            .with_checks(vec![CheckResult::new("wmi", "enabled", name)]),
+            Ok(Some(false)) => FeatureStatus::disabled(vec![
+                "WMI: 'VirtualMachinePlatform' 状态为 未启用。".to_string(),
+            ])
+            .with_checks(vec![CheckResult::new("wmi", "disabled", name)]),
+            Ok(None) => FeatureStatus::disabled(vec![
+                "WMI: 未找到 'VirtualMachinePlatform' 可选功能，需要 Windows 10 1903 (build 18362) 或更高版本。"
+                    .to_string(),
+            ])
+            .with_checks(vec![CheckResult::new("wmi", "absent", name)]),
+            Err(err) => FeatureStatus::unknown(vec![format!("WMI 查询可选功能失败: {err}。")])
+                .with_checks(vec![CheckResult::error("wmi", &err)]),
+        }
+    })
+}
+
+/// 与 `isVmpEnabled` 相同，但所有检测方法均失败时抛出带有 `code` 的错误 (如 `WMI_QUERY_FAILED`)，而不是返回 `unknown`
+#[cfg(all(feature = "napi", feature = "features", target_os = "windows"))]
+#[napi]
+pub fn is_vmp_enabled_strict() -> napi::Result<FeatureStatus, &'static str> {
+    is_vmp_enabled().into_strict()
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Wsl2Readiness {
+    /// 所有条件均满足，可以直接安装或切换到 WSL2
+    pub ready: bool,
+    /// 64 位系统 (x64 或 ARM64)
+    pub is_64bit: bool,
+    /// CPU 支持硬件虚拟化 (VT-x / AMD-V / ARM64 EL2)
+    pub cpu_supported: bool,
+    /// CPU 支持 SLAT (Intel EPT / AMD RVI)
+    pub slat_supported: bool,
+    /// 虚拟化已在固件中开启
+    pub firmware_enabled: bool,
+    /// `VirtualMachinePlatform` 可选功能已启用
+    pub vmp_enabled: bool,
+    /// 系统版本满足要求: x64 需要 build 18362 及以上，ARM64 需要 build 19041 及以上
+    pub build_supported: bool,
+    /// 未满足的条件，供用户阅读
+    pub missing: Vec<String>,
+}
+
+/// 汇总 WSL2 的前提条件: 64 位系统、CPU 虚拟化与 SLAT、固件开关、`VirtualMachinePlatform` 以及系统版本
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn check_wsl2_prerequisites() -> Wsl2Readiness {
+    cache::get_or_insert_with("check_wsl2_prerequisites", Lifetime::Volatile, || {
+        let machine_arch = get_process_emulation().machine_arch;
+        let is_64bit = matches!(machine_arch, "x86_64" | "aarch64");
+        let (cpu_supported, _, _) = virtualization::check_virtual_support();
+        let (slat_supported, slat_details) = virtualization::check_slat_support_windows();
+        let (firmware_enabled, firmware_details) =
+            virtualization::check_virtualization_enabled_windows();
+        let vmp_enabled = is_vmp_enabled().enabled;
+        let version = get_windows_version();
+        let minimum_build = if machine_arch == "aarch64" {
+            19041
+        } else {
+            18362
+        };
+        let build_supported = version.build >= minimum_build;
+
+        let mut missing = vec![];
+        if !is_64bit {
+            missing.push(format!("WSL2 需要 64 位系统，当前为 {machine_arch}。"));
+        }
+        if !cpu_supported {
+            missing.push("CPU 不支持硬件虚拟化。".to_string());
+        }
+        if !slat_supported {
+            missing.push(format!("{slat_details}。"));
+        }
+        if cpu_supported && !firmware_enabled {
+            missing.push(format!("虚拟化未在固件中开启: {firmware_details}"));
+        }
+        if !vmp_enabled {
+            missing.push("'VirtualMachinePlatform' 可选功能未启用。".to_string());
+        }
+        if !build_supported {
+            missing.push(format!(
+                "系统版本: build {}，WSL2 需要 build {minimum_build} 或更高版本。",
+                version.build
+            ));
+        }
+        Wsl2Readiness {
+            ready: missing.is_empty(),
+            is_64bit,
+            cpu_supported,
+            slat_supported,
+            firmware_enabled,
+            vmp_enabled,
+            build_supported,
+            missing,
+        }
+    })
+}
+
+/// 检查 WSLg (在 Windows 上运行 Linux 图形应用) 的前提条件
+///
+/// 依次确认 WSL 已启用、默认版本为 WSL2、WSLg 组件已安装，以及显卡驱动支持 WDDM 2.9 (GPU 加速)，
+/// 缺少的条件会记录在 `details` 中
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn check_wslg() -> FeatureStatus {
+    cache::get_or_insert_with("check_wslg", Lifetime::Volatile, || {
+        let wsl = is_wsl_enabled();
+        let mut details = wsl.details;
+        let mut checks = wsl.checks;
+        if !wsl.enabled {
+            details.push("缺少前提条件: WSL 未启用。".to_string());
+            return FeatureStatus {
+                kind: wsl.kind,
+                enabled: false,
+                details,
+                checks,
+            };
+        }
+
+        let mut missing = vec![];
+        let store_package = windows_feature::wsl::is_store_package_installed();
+        let default_version = windows_feature::wsl::read_default_version_via_reg();
+        match default_version {
+            Some(2) => details.push("默认版本: WSL2。".to_string()),
+            Some(version) => {
+                details.push(format!("默认版本: WSL{version}。"));
+                missing.push("默认版本不是 WSL2，请执行 `wsl --set-default-version 2`");
+            }
+            None if store_package => {
+                details.push("默认版本: 未设置，应用商店版本的 WSL 默认使用 WSL2。".to_string())
+            }
+            None => {
+                details.push("默认版本: 未设置，内置版本的 WSL 可能默认使用 WSL1。".to_string());
+                missing.push("未将默认版本设置为 WSL2");
+            }
+        }
+        checks.push(CheckResult::new(
+            "default_version",
+            match default_version {
+                Some(2) => "wsl2",
+                Some(_) => "wsl1",
+                None => "not_set",
+            },
+            default_version.map(|it| it.to_string()),
+        ));
+
+        let components = windows_feature::wsl::find_wslg_components();
+        for component in &components {
+            details.push(format!("WSLg 组件: 找到 '{component}'。"));
         }
-        #[cfg(target_os = "macos")]
-        {
-            virtualization::check_hypervisor_support_macos()
+        if !components.iter().any(|it| it.ends_with("system.vhd")) {
+            missing.push("未找到 WSLg 组件，请执行 `wsl --update` 安装最新版本的 WSL");
+            checks.push(CheckResult::new("wslg_component", "not_found", None));
+        } else {
+            checks.push(CheckResult::new(
+                "wslg_component",
+                "found",
+                Some(components.join(";")),
+            ));
         }
-        #[cfg(target_os = "linux")]
-        {
-            virtualization::check_kvm_via_api_linux()
+
+        let mut conclusive = true;
+        match windows_feature::gpu::list_video_controllers() {
+            Ok(controllers) => {
+                let mut supported = false;
+                for (name, driver_version) in controllers {
+                    let result = match windows_feature::gpu::parse_wddm_version(&driver_version) {
+                        Some((major, minor)) => {
+                            let wddm_2_9 = (major, minor) >= (2, 9);
+                            supported |= wddm_2_9;
+                            details.push(format!(
+                                "显卡 '{name}': 驱动版本 {driver_version}，WDDM {major}.{minor}。"
+                            ));
+                            if wddm_2_9 { "supported" } else { "unsupported" }
+                        }
+                        None => {
+                            details.push(format!(
+                                "显卡 '{name}': 驱动版本 {driver_version}，无法识别 WDDM 版本。"
+                            ));
+                            "unknown"
+                        }
+                    };
+                    checks.push(CheckResult::new("gpu_driver", result, Some(driver_version)));
+                }
+                if !supported {
+                    missing.push(
+                        "没有支持 WDDM 2.9 的显卡驱动，WSLg 只能使用软件渲染，请更新显卡驱动",
+                    );
+                }
+            }
+            Err(e) => {
+                details.push(format!("WMI 查询显卡失败: {e}。"));
+                checks.push(CheckResult::error("gpu_driver", &e));
+                conclusive = false;
+            }
         }
-        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-        {
-            (
-                false,
-                String::from("此操作系统上的启用状态检查未实现或失败"),
-            )
+
+        for prerequisite in &missing {
+            details.push(format!("缺少前提条件: {prerequisite}。"));
         }
-    };
+        if !missing.is_empty() {
+            FeatureStatus::disabled(details).with_checks(checks)
+        } else if conclusive {
+            FeatureStatus::enabled(details).with_checks(checks)
+        } else {
+            FeatureStatus::unknown(details).with_checks(checks)
+        }
+    })
+}
 
-    let overall_status_message = if cpu_supported && os_reported_enabled {
-        "CPU 支持虚拟化，并且似乎已在操作系统/固件中启用。".to_string()
-    } else if cpu_supported && !os_reported_enabled {
-        format!(
-            "CPU 支持虚拟化 ({})，但操作系统报告其未启用或无法确认。详情: {}",
-            cpu_feature_name, os_check_details
-        )
-    } else if !cpu_supported && os_reported_enabled {
-        format!(
-            "CPU 不支持虚拟化 ({})，但操作系统报告支持，这常见于运行在虚拟系统下或不支持检测该 CPU。详情：{}",
-            cpu_feature_name, os_check_details
-        )
-    } else {
-        format!("CPU 不支持虚拟化 ({}).", cpu_feature_name)
+/// 与 `checkWslg` 相同，但所有检测方法均失败时抛出带有 `code` 的错误 (如 `WMI_QUERY_FAILED`)，而不是返回 `unknown`
+#[cfg(all(feature = "napi", feature = "features", target_os = "windows"))]
+#[napi]
+pub fn check_wslg_strict() -> napi::Result<FeatureStatus, &'static str> {
+    check_wslg().into_strict()
+}
+
+/// 获取 WSL2 内核版本 (如 `5.15.90.1`)，未安装 WSL2 时返回 `None`
+///
+/// 直接读取 `.wslconfig` 配置的或 WSL 自带的内核镜像，不调用 `wsl.exe uname -r`，
+/// 避免启动实用工具虚拟机以及控制台编码问题
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn get_wsl2_kernel_version() -> Option<String> {
+    cache::get_or_insert_with(
+        "get_wsl2_kernel_version",
+        Lifetime::Volatile,
+        windows_feature::wsl::read_kernel_version,
+    )
+}
+
+/// 通过 DISM 启用可选功能，完成后清空检测缓存，使后续的 `is*Enabled` 反映新的状态
+#[cfg(all(feature = "features", target_os = "windows"))]
+fn enable_optional_features(product: &str, names: &[&str]) -> FeatureStatus {
+    if !windows_feature::dism::is_elevated() {
+        return FeatureStatus::disabled(vec![format!(
+            "权限检查: 当前进程未以管理员身份运行，无法启用 {product}，请以管理员身份重新运行后重试。"
+        )])
+        .with_checks(vec![CheckResult::new("elevation", "denied", None)]);
+    }
+    let results = match windows_feature::dism::enable_features(names) {
+        Ok(results) => results,
+        Err(err) => {
+            return FeatureStatus::disabled(vec![format!("{err}。")])
+                .with_checks(vec![CheckResult::error("dism", &err)]);
+        }
     };
+    cache::clear();
 
-    VirtualizationInfo {
-        os,
-        arch,
-        cpu_supported,
-        cpu_feature_name,
-        os_reported_enabled,
-        os_check_details,
-        overall_status_message,
+    let mut details = vec![];
+    let mut checks = vec![];
+    let mut failed = false;
+    let mut reboot_required = false;
+    for (name, result) in results {
+        match result {
+            Ok(true) => {
+                reboot_required = true;
+                details.push(format!("DISM: 已启用 '{name}'，需要重启计算机后生效。"));
+                checks.push(CheckResult::new(
+                    "dism",
+                    "reboot_required",
+                    Some(name.to_string()),
+                ));
+            }
+            Ok(false) => {
+                details.push(format!("DISM: 已启用 '{name}'。"));
+                checks.push(CheckResult::new("dism", "enabled", Some(name.to_string())));
+            }
+            Err(err) => {
+                failed = true;
+                details.push(format!("DISM: 启用 '{name}' 失败: {err}。"));
+                checks.push(CheckResult::new(
+                    "dism",
+                    "error",
+                    Some(format!("{name}: {err}")),
+                ));
+            }
+        }
+    }
+    if failed {
+        FeatureStatus::disabled(details).with_checks(checks)
+    } else {
+        if reboot_required {
+            details.push(format!("{product} 将在重启后可用。"));
+        }
+        FeatureStatus::enabled(details).with_checks(checks)
     }
 }
 
-#[napi(object)]
-pub struct SystemEncoding {
-    pub ansi_code: u32,
-    pub oem_code: u32,
-    pub ansi_encoding: &'static str,
-    pub oem_encoding: &'static str,
+/// 启用 WSL 所需的 `Microsoft-Windows-Subsystem-Linux` 与 `VirtualMachinePlatform` 可选功能
+///
+/// 需要以管理员身份运行，未提权时直接返回 `disabled` 而不调用 DISM；两者均已启用时不做任何操作。
+/// 需要重启时 `checks` 中对应项的结果为 `reboot_required`
+///
+/// DISM 可能需要数分钟，JS 中只提供异步版本 `enableWslFeatureAsync`，避免阻塞主线程
+#[cfg(all(feature = "features", target_os = "windows"))]
+pub fn enable_wsl_feature() -> FeatureStatus {
+    if is_wsl_enabled().enabled && is_vmp_enabled().enabled {
+        return FeatureStatus::enabled(vec![
+            "WSL 与 VirtualMachinePlatform 均已启用，无需操作。".to_string(),
+        ]);
+    }
+    enable_optional_features(
+        "WSL",
+        &[
+            "Microsoft-Windows-Subsystem-Linux",
+            "VirtualMachinePlatform",
+        ],
+    )
 }
 
-#[allow(deprecated)]
-#[cfg(target_os = "windows")]
-#[napi]
-pub fn get_system_encoding() -> SystemEncoding {
-    let (ansi_code, ansi_encoding) = encoding::get_system_encoding();
-    let (oem_code, oem_encoding) = encoding::get_console_encoding();
-    SystemEncoding {
-        ansi_code,
-        ansi_encoding,
-        oem_code,
-        oem_encoding,
+/// 启用 `Microsoft-Hyper-V-All` 可选功能 (Hyper-V 平台与管理工具)
+///
+/// 调用 DISM 前依次确认：系统版本提供 Hyper-V (家庭版不提供)、CPU 支持硬件虚拟化 (或 Hypervisor 已在运行)、当前进程已提权，
+/// 不满足时返回 `disabled` 并在 `details` 中说明原因。需要重启时 `checks` 中的结果为 `reboot_required`
+///
+/// DISM 可能需要数分钟，JS 中只提供异步版本 `enableHypervFeatureAsync`，避免阻塞主线程
+#[cfg(all(feature = "features", target_os = "windows"))]
+pub fn enable_hyperv_feature() -> FeatureStatus {
+    if is_hyperv_enabled().enabled {
+        return FeatureStatus::enabled(vec!["Hyper-V 已启用，无需操作。".to_string()]);
+    }
+    if let Some(edition) = get_windows_edition()
+        .filter(|edition| !windows_feature::version::edition_supports_hyperv(edition))
+    {
+        return FeatureStatus::disabled(vec![format!(
+            "版本检查: 当前系统版本 ({edition}) 不提供 Hyper-V，需要升级到专业版、企业版或教育版。"
+        )])
+        .with_checks(vec![CheckResult::new(
+            "edition",
+            "unsupported",
+            Some(edition),
+        )]);
     }
+    let info = get_virtualization();
+    // Hypervisor 运行时 (WSL2、VBS 等) 根分区的 CPUID 不再暴露 VT-x/AMD-V，此时操作系统仍报告已启用，
+    // 只有两者都不满足时才是真正不支持
+    if matches!(info.status, VirtStatus::CpuUnsupported) {
+        return FeatureStatus::disabled(vec![format!(
+            "CPU 检查: 处理器不支持 {}，无法启用 Hyper-V。",
+            info.cpu_feature_name
+        )])
+        .with_checks(vec![CheckResult::new(
+            "cpu",
+            "unsupported",
+            Some(info.cpu_feature_name.to_string()),
+        )]);
+    }
+    enable_optional_features("Hyper-V", &["Microsoft-Hyper-V-All"])
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct WslDistro {
+    pub name: String,
+    /// WSL 版本，1 或 2
+    pub version: u32,
+    /// 注册表中的安装状态，无法识别时为 `unknown`
+    #[napi(ts_type = "'installed' | 'installing' | 'uninstalling' | 'converting' | 'unknown'")]
+    pub state: &'static str,
+    /// WSL2 的轻量虚拟机 (`vmmem`) 是否正在运行，所有 WSL2 发行版共用同一个虚拟机；
+    /// WSL1 发行版或查询失败时为 `null`
+    pub is_running: Option<bool>,
 }
 
+/// 获取当前用户的默认 WSL 发行版，没有安装任何发行版时返回 `null`
+///
+/// 与 `isWslEnabled` 不同，WSL 已启用但尚未安装发行版时同样返回 `null`
+#[cfg(all(feature = "features", target_os = "windows"))]
 #[napi]
-pub fn get_version() -> &'static str {
-    env!("CARGO_PKG_VERSION")
+pub fn get_default_wsl_distro() -> Option<WslDistro> {
+    cache::get_or_insert_with("get_default_wsl_distro", Lifetime::Volatile, || {
+        let (name, version, state) = windows_feature::wsl::read_default_distro_via_reg()?;
+        let state = match state {
+            1 => "installed",
+            2 => "installing",
+            3 => "uninstalling",
+            4 => "converting",
+            _ => "unknown",
+        };
+        let is_running = if version == 2 {
+            windows_feature::wsl::is_utility_vm_running().ok()
+        } else {
+            None
+        };
+        Some(WslDistro {
+            name,
+            version,
+            state,
+            is_running,
+        })
+    })
 }
 
 #[napi(object)]
-pub struct FeatureStatus {
-    pub enabled: bool,
-    pub details: Vec<String>,
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct WslVersion {
+    /// WSL 已启用，与 `isWslEnabled().enabled` 相同
+    pub installed: bool,
+    /// 当前用户的默认版本 (`wsl --set-default-version`)，1 或 2，未设置时为 `null`
+    pub default_version: Option<u32>,
+    /// `VirtualMachinePlatform` 已启用，可以运行 WSL2 发行版
+    pub wsl2_capable: bool,
+    /// 已注册的发行版数量，WSL 已启用但尚未安装发行版时为 0
+    pub distribution_count: u32,
 }
 
-#[cfg(target_os = "windows")]
+/// 获取 WSL 的默认版本以及是否可以使用 WSL2
+///
+/// `isWslEnabled` 只能确认 WSL 已启用，WSL2 还需要启用 `VirtualMachinePlatform`
+#[cfg(all(feature = "features", target_os = "windows"))]
 #[napi]
-pub fn is_hyperv_enabled() -> FeatureStatus {
-    let mut details = vec![];
+pub fn get_wsl_version() -> WslVersion {
+    cache::get_or_insert_with("get_wsl_version", Lifetime::Volatile, || {
+        let installed = is_wsl_enabled().enabled;
+        let wsl2_capable = windows_feature::wsl::check_wsl_via_wmi()
+            .map(|(_, vmp_enabled)| vmp_enabled)
+            .unwrap_or(false);
+        WslVersion {
+            installed,
+            default_version: windows_feature::wsl::read_default_version_via_reg(),
+            wsl2_capable,
+            distribution_count: windows_feature::wsl::count_distributions_via_reg(),
+        }
+    })
+}
 
-    match windows_feature::hypervisor::check_hyperv_via_service() {
-        Ok(running) => {
-            details.push(format!(
-                "服务 'vmms': 状态为 '{}'。",
-                if running { "正在运行" } else { "已停止" }
-            ));
-            if running {
-                return FeatureStatus {
-                    enabled: true,
-                    details,
+/// 检测与 Hyper-V 冲突的虚拟化产品，返回可读的冲突警告，没有冲突时返回空列表
+///
+/// Hyper-V、WSL2、VBS 都会启动 Hyper-V 虚拟机监控程序，此时 VirtualBox、VMware、HAXM 的旧版本无法直接使用 VT-x/AMD-V
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn detect_hypervisor_conflicts() -> Vec<String> {
+    cache::get_or_insert_with("detect_hypervisor_conflicts", Lifetime::Volatile, || {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        let hypervisor_running = virtualization::check_hyperv_environment_cpuid().0;
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        let hypervisor_running = false;
+        let whpx_enabled = windows_feature::conflicts::check_whpx_via_wmi().unwrap_or(false);
+        if !hypervisor_running && !whpx_enabled {
+            return vec![];
+        }
+
+        let cause = if hypervisor_running {
+            "Hyper-V 虚拟机监控程序正在运行 (可能由 Hyper-V、WSL2 或基于虚拟化的安全启用)"
+        } else {
+            "Windows 虚拟机监控程序平台 (WHPX) 已启用"
+        };
+        windows_feature::conflicts::list_competing_services()
+            .into_iter()
+            .map(|(service_name, product, running)| {
+                let advice = match product {
+                    "VirtualBox" => "VirtualBox 6.0 之前的版本无法启动虚拟机，较新版本会退回到性能较差的 Hyper-V 后端",
+                    "Intel HAXM" => "HAXM 无法在此时工作，请改用 Android Emulator 的 WHPX 后端，或执行 `bcdedit /set hypervisorlaunchtype off` 后重启",
+                    _ => "VMware Workstation/Player 15.5.5 之前的版本无法启动虚拟机，请升级到支持 Hyper-V 的版本",
                 };
+                format!(
+                    "{cause}，检测到 {product} (服务 '{service_name}'，{})：{advice}。",
+                    if running { "正在运行" } else { "未运行" }
+                )
+            })
+            .collect()
+    })
+}
+
+/// 检查主机是否支持 GPU 半虚拟化 (GPU-PV)，WSLg 和 Hyper-V 虚拟机可以借此使用宿主显卡
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn check_gpu_partitioning() -> FeatureStatus {
+    cache::get_or_insert_with("check_gpu_partitioning", Lifetime::Volatile, || {
+        let mut details = vec![];
+        let mut checks = vec![];
+
+        let hcs_available = match windows_feature::gpu::check_vmcompute_via_service() {
+            Ok(running) => {
+                details.push(format!(
+                    "服务 'vmcompute': 状态为 '{}'。",
+                    if running { "正在运行" } else { "已停止" }
+                ));
+                checks.push(CheckResult::new(
+                    "service",
+                    if running { "running" } else { "stopped" },
+                    Some("vmcompute".to_string()),
+                ));
+                true
+            }
+            Err(err) => {
+                details.push(format!("服务 'vmcompute' 查询失败: {err}。"));
+                checks.push(CheckResult::error("service", &err));
+                false
+            }
+        };
+
+        match windows_feature::gpu::list_wddm_versions() {
+            Ok(adapters) => {
+                for (name, (major, minor)) in adapters {
+                    details.push(format!("显卡 '{name}': 驱动模型 WDDM {major}.{minor}。"));
+                    checks.push(CheckResult::new(
+                        "gpu_driver",
+                        "found",
+                        Some(format!("WDDM {major}.{minor}")),
+                    ));
+                }
+            }
+            Err(e) => {
+                details.push(format!("查询显卡驱动模型失败: {e}。"));
+                checks.push(CheckResult::error("gpu_driver", &e));
             }
         }
-        Err(err) => {
-            details.push(format!("服务 'vmms' 查询失败: {:?}。", err));
-        }
-    }
-    match windows_feature::hypervisor::check_hyperv_via_wmi() {
-        Ok(enabled) => {
-            details.push(format!(
-                "WMI 检查: Hyper-V 可选功能状态为 {}。",
-                if enabled { "已启用" } else { "未启用" }
-            ));
-            if enabled {
-                return FeatureStatus {
-                    enabled: true,
-                    details,
-                };
+
+        let mut conclusive = true;
+        let partitionable = match windows_feature::gpu::list_partitionable_gpus() {
+            Ok(Some(gpus)) if gpus.is_empty() => {
+                details.push("没有支持分区的显卡。".to_string());
+                checks.push(CheckResult::new("partitionable_gpu", "not_found", None));
+                false
+            }
+            Ok(Some(gpus)) => {
+                for gpu in &gpus {
+                    details.push(format!("支持分区的显卡: '{gpu}'。"));
+                }
+                checks.push(CheckResult::new(
+                    "partitionable_gpu",
+                    "found",
+                    Some(gpus.join(";")),
+                ));
+                true
+            }
+            Ok(None) => {
+                details.push(
+                    "命名空间 'root\\virtualization\\v2' 不存在，Hyper-V 可能未启用。".to_string(),
+                );
+                checks.push(CheckResult::new(
+                    "partitionable_gpu",
+                    "absent",
+                    Some("root\\virtualization\\v2".to_string()),
+                ));
+                false
             }
+            Err(e) => {
+                details.push(format!("WMI 查询可分区显卡失败: {e}。"));
+                checks.push(CheckResult::error("partitionable_gpu", &e));
+                conclusive = false;
+                false
+            }
+        };
+
+        if hcs_available && partitionable {
+            FeatureStatus::enabled(details).with_checks(checks)
+        } else if conclusive {
+            FeatureStatus::disabled(details).with_checks(checks)
+        } else {
+            FeatureStatus::unknown(details).with_checks(checks)
         }
-        Err(err) => details.push(err),
-    }
-    details.push("所有检测方法均未能确认 Hyper-V 已完全启用。".to_string());
-    FeatureStatus {
-        enabled: false,
-        details,
-    }
+    })
 }
 
-#[cfg(target_os = "windows")]
+/// 与 `checkGpuPartitioning` 相同，但所有检测方法均失败时抛出带有 `code` 的错误 (如 `WMI_QUERY_FAILED`)，而不是返回 `unknown`
+#[cfg(all(feature = "napi", feature = "features", target_os = "windows"))]
 #[napi]
-pub fn is_wsl_enabled() -> FeatureStatus {
-    let mut details = vec![];
+pub fn check_gpu_partitioning_strict() -> napi::Result<FeatureStatus, &'static str> {
+    check_gpu_partitioning().into_strict()
+}
 
-    if !Path::new("C:\\Windows\\System32\\wsl.exe").exists() {
-        details.push("文件检查: 未找到 wsl.exe，WSL 未安装。".to_string());
-        return FeatureStatus {
-            enabled: false,
-            details,
-        };
-    }
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct VideoAdapter {
+    pub name: String,
+    pub driver_version: String,
+    /// 远程桌面、Hyper-V 合成显卡或 VMware/VirtualBox/QEMU 模拟的显卡
+    pub is_virtual: bool,
+}
 
-    details.push("文件检查: 找到 wsl.exe。".to_string());
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct GpuAcceleration {
+    pub adapters: Vec<VideoAdapter>,
+    /// 所有显卡都是虚拟显卡 (常见于云主机和远程桌面会话)，此时无法使用硬件加速的图形功能；
+    /// 查询失败或没有显卡时为 `false`
+    pub only_virtual_gpu: bool,
+    pub details: Vec<String>,
+}
 
-    match windows_feature::wsl::check_wsl_via_service() {
-        Ok(running) => {
-            details.push(format!(
-                "服务 'LxssManager': 状态为 '{}'。",
-                if running { "正在运行" } else { "已停止" }
-            ));
-            if running {
-                return FeatureStatus {
-                    enabled: true,
-                    details,
-                };
+/// 列出显卡并判断是否只有虚拟显卡，用于决定是否启用硬件加速
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn get_gpu_acceleration() -> GpuAcceleration {
+    cache::get_or_insert_with("get_gpu_acceleration", Lifetime::Volatile, || {
+        match windows_feature::gpu::list_video_adapters() {
+            Ok(adapters) => {
+                let adapters = adapters
+                    .into_iter()
+                    .map(|(name, driver_version, pnp_device_id)| VideoAdapter {
+                        is_virtual: virtualization::is_virtual_gpu(&name, &pnp_device_id),
+                        name,
+                        driver_version,
+                    })
+                    .collect::<Vec<_>>();
+                GpuAcceleration {
+                    only_virtual_gpu: !adapters.is_empty()
+                        && adapters.iter().all(|it| it.is_virtual),
+                    adapters,
+                    details: vec![],
+                }
             }
+            Err(e) => GpuAcceleration {
+                adapters: vec![],
+                only_virtual_gpu: false,
+                details: vec![format!("WMI 查询显卡失败: {e}。")],
+            },
         }
-        Err(err) => {
-            details.push(format!("服务 'LxssManager' 查询失败: {:?}。", err));
-        }
-    }
-    match windows_feature::wsl::check_wsl_via_reg() {
-        true => {
-            details.push("注册表检查: WSL 已启用。".to_string());
+    })
+}
 
-            return FeatureStatus {
-                enabled: true,
-                details,
-            };
+/// 检查主机是否可以运行受防护的虚拟机 (Shielded VM)
+///
+/// 需要启用 Host Guardian Hyper-V Support 可选功能，并通过 HGS 客户端配置为受保护主机 (Guarded Host)，
+/// 仅启用功能而未通过证明时返回 `disabled`
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn check_shielded_vm_support() -> FeatureStatus {
+    cache::get_or_insert_with("check_shielded_vm_support", Lifetime::Volatile, || {
+        let mut details = vec![];
+        let mut checks = vec![];
+        let name = Some("HostGuardian".to_string());
+
+        match windows_feature::hgs::check_host_guardian_via_wmi() {
+            Ok(true) => {
+                details.push("WMI 检查: 'HostGuardian' 可选功能已启用。".to_string());
+                checks.push(CheckResult::new("wmi", "enabled", name));
+            }
+            Ok(false) => {
+                details.push("WMI 检查: 'HostGuardian' 可选功能未启用。".to_string());
+                checks.push(CheckResult::new("wmi", "disabled", name));
+                return FeatureStatus::disabled(details).with_checks(checks);
+            }
+            Err(err) => {
+                details.push(err.to_string());
+                checks.push(CheckResult::error("wmi", &err));
+                return FeatureStatus::unknown(details).with_checks(checks);
+            }
         }
-        false => {
-            details.push("注册表检查: WSL 未启用。".to_string());
+
+        match windows_feature::hgs::check_hgs_client_via_wmi() {
+            Ok(Some((true, url))) => {
+                details.push(format!(
+                    "HGS 客户端: 已配置为受保护主机，证明服务地址 '{}'。",
+                    url.as_deref().unwrap_or_default()
+                ));
+                checks.push(CheckResult::new("hgs_client", "guarded", url));
+                FeatureStatus::enabled(details).with_checks(checks)
+            }
+            Ok(Some((false, url))) => {
+                details.push(match &url {
+                    Some(url) => format!("HGS 客户端: 尚未通过证明服务 '{url}' 的证明。"),
+                    None => "HGS 客户端: 尚未配置证明服务。".to_string(),
+                });
+                checks.push(CheckResult::new("hgs_client", "not_guarded", url));
+                FeatureStatus::disabled(details).with_checks(checks)
+            }
+            Ok(None) => {
+                details.push(
+                    "命名空间 'root\\Microsoft\\Windows\\Hgs' 不存在，HGS 客户端未安装。"
+                        .to_string(),
+                );
+                checks.push(CheckResult::new("hgs_client", "absent", None));
+                FeatureStatus::disabled(details).with_checks(checks)
+            }
+            Err(err) => {
+                details.push(format!("WMI 查询 HGS 客户端配置失败: {err}。"));
+                checks.push(CheckResult::error("hgs_client", &err));
+                FeatureStatus::unknown(details).with_checks(checks)
+            }
         }
-    }
-    match windows_feature::wsl::check_wsl_via_wmi() {
-        Ok((wsl_enabled, vmp_enabled)) => {
-            details.push(format!(
-                "WMI: 'Microsoft-Windows-Subsystem-Linux' 状态为 {}.",
-                if wsl_enabled {
-                    "已启用"
-                } else {
-                    "未启用"
+    })
+}
+
+/// 与 `checkShieldedVmSupport` 相同，但所有检测方法均失败时抛出带有 `code` 的错误 (如 `WMI_QUERY_FAILED`)，而不是返回 `unknown`
+#[cfg(all(feature = "napi", feature = "features", target_os = "windows"))]
+#[napi]
+pub fn check_shielded_vm_support_strict() -> napi::Result<FeatureStatus, &'static str> {
+    check_shielded_vm_support().into_strict()
+}
+
+#[napi(object)]
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct CoreIsolationStatus {
+    /// 注册表中是否开启了内存完整性 (HVCI)
+    pub hvci_configured: bool,
+    /// 注册表中的 `WasEnabledBy`，记录 HVCI 是由谁开启的，未记录时为 `null`
+    pub hvci_was_enabled_by: Option<u32>,
+    /// HVCI 是否正在运行，`null` 表示无法查询 `Win32_DeviceGuard`
+    pub hvci_running: Option<bool>,
+    /// 注册表中是否开启了 Credential Guard
+    pub credential_guard_configured: bool,
+    /// Credential Guard 是否正在运行，`null` 表示无法查询 `Win32_DeviceGuard`
+    pub credential_guard_running: Option<bool>,
+    /// 已在注册表中开启但尚未运行，通常需要重启
+    pub reboot_required: bool,
+    pub details: Vec<String>,
+    /// 与 `details` 对应的结构化结果，`raw` 为注册表中的场景名或 WMI 命名空间
+    pub checks: Vec<CheckResult>,
+}
+
+/// 获取内核隔离 (Core Isolation) 的配置与运行状态
+///
+/// 配置来自 `HKLM\SYSTEM\CurrentControlSet\Control\DeviceGuard\Scenarios`，运行状态来自 WMI `Win32_DeviceGuard`，
+/// 用于解释“设置中已开启但实际未生效，需要重启”的情况
+#[cfg(all(feature = "features", target_os = "windows"))]
+#[napi]
+pub fn get_core_isolation_status() -> CoreIsolationStatus {
+    cache::get_or_insert_with("get_core_isolation_status", Lifetime::Volatile, || {
+        let mut details = vec![];
+        let mut checks = vec![];
+        let describe = |enabled: bool| if enabled { "已开启" } else { "未开启" };
+        let result = |enabled: bool| if enabled { "enabled" } else { "disabled" };
+
+        let (hvci_configured, hvci_was_enabled_by) =
+            match windows_feature::core_isolation::read_scenario_via_reg(
+                "HypervisorEnforcedCodeIntegrity",
+            ) {
+                Some((enabled, was_enabled_by)) => {
+                    details.push(format!(
+                        "注册表检查: 内存完整性 (HVCI) {}。",
+                        describe(enabled)
+                    ));
+                    checks.push(CheckResult::new(
+                        "registry",
+                        result(enabled),
+                        Some("HypervisorEnforcedCodeIntegrity".to_string()),
+                    ));
+                    (enabled, was_enabled_by)
                 }
-            ));
-            details.push(format!(
-                "WMI: 'VirtualMachinePlatform' 状态为 {}.",
-                if vmp_enabled {
-                    "已启用"
-                } else {
-                    "未启用"
+                None => {
+                    details.push("注册表检查: 未找到内存完整性 (HVCI) 的配置。".to_string());
+                    checks.push(CheckResult::new(
+                        "registry",
+                        "not_found",
+                        Some("HypervisorEnforcedCodeIntegrity".to_string()),
+                    ));
+                    (false, None)
                 }
-            ));
+            };
+        let credential_guard_configured =
+            match windows_feature::core_isolation::read_scenario_via_reg("CredentialGuard") {
+                Some((enabled, _)) => {
+                    details.push(format!(
+                        "注册表检查: Credential Guard {}。",
+                        describe(enabled)
+                    ));
+                    checks.push(CheckResult::new(
+                        "registry",
+                        result(enabled),
+                        Some("CredentialGuard".to_string()),
+                    ));
+                    enabled
+                }
+                None => {
+                    details.push("注册表检查: 未找到 Credential Guard 的配置。".to_string());
+                    checks.push(CheckResult::new(
+                        "registry",
+                        "not_found",
+                        Some("CredentialGuard".to_string()),
+                    ));
+                    false
+                }
+            };
 
-            let fully_enabled = wsl_enabled && vmp_enabled;
-            if fully_enabled {
-                return FeatureStatus {
-                    enabled: true,
-                    details,
-                };
-            }
+        let (credential_guard_running, hvci_running) =
+            match windows_feature::core_isolation::check_running_via_wmi() {
+                Ok(Some((credential_guard, hvci))) => {
+                    details.push(format!(
+                        "WMI: 内存完整性 (HVCI) {}，Credential Guard {}。",
+                        if hvci { "正在运行" } else { "未运行" },
+                        if credential_guard {
+                            "正在运行"
+                        } else {
+                            "未运行"
+                        }
+                    ));
+                    let running = |running: bool| if running { "running" } else { "stopped" };
+                    checks.push(CheckResult::new(
+                        "wmi",
+                        running(hvci),
+                        Some("HypervisorEnforcedCodeIntegrity".to_string()),
+                    ));
+                    checks.push(CheckResult::new(
+                        "wmi",
+                        running(credential_guard),
+                        Some("CredentialGuard".to_string()),
+                    ));
+                    (Some(credential_guard), Some(hvci))
+                }
+                Ok(None) => {
+                    details.push(
+                        "命名空间 'root\\Microsoft\\Windows\\DeviceGuard' 不存在，系统不支持基于虚拟化的安全 (VBS)。"
+                            .to_string(),
+                    );
+                    checks.push(CheckResult::new(
+                        "wmi",
+                        "absent",
+                        Some("root\\Microsoft\\Windows\\DeviceGuard".to_string()),
+                    ));
+                    (Some(false), Some(false))
+                }
+                Err(e) => {
+                    details.push(format!("WMI 查询 Win32_DeviceGuard 失败: {e}。"));
+                    checks.push(CheckResult::error("wmi", &e));
+                    (None, None)
+                }
+            };
+
+        let reboot_required = (hvci_configured && hvci_running == Some(false))
+            || (credential_guard_configured && credential_guard_running == Some(false));
+        if reboot_required {
+            details.push("已在注册表中开启但尚未运行，可能需要重启。".to_string());
         }
-        Err(e) => {
-            details.push(format!("WMI 查询可选功能失败: {:?}。", e));
+
+        CoreIsolationStatus {
+            hvci_configured,
+            hvci_was_enabled_by,
+            hvci_running,
+            credential_guard_configured,
+            credential_guard_running,
+            reboot_required,
+            details,
+            checks,
         }
-    }
-    details.push("所有检测方法均未能确认 WSL 已完全启用。".to_string());
-    FeatureStatus {
-        enabled: false,
-        details,
-    }
+    })
+}
+
+/// 列出支持 SR-IOV 的网卡名称，用于高性能的虚拟机网络
+///
+/// Windows 查询 `root\StandardCimv2` 下的 `MSFT_NetAdapterSriovSettingData`，Linux 读取 sysfs，
+/// 没有支持的网卡或查询失败时返回空列表
+#[napi]
+pub fn list_sriov_capable_nics() -> Vec<String> {
+    cache::get_or_insert_with("list_sriov_capable_nics", Lifetime::Volatile, || {
+        #[cfg(all(feature = "features", target_os = "windows"))]
+        {
+            windows_feature::sriov::list_sriov_nics_via_wmi().unwrap_or_default()
+        }
+        #[cfg(target_os = "linux")]
+        {
+            virtualization::list_sriov_nics_linux()
+        }
+        #[cfg(not(any(all(feature = "features", target_os = "windows"), target_os = "linux")))]
+        {
+            vec![]
+        }
+    })
 }
 
 #[napi(object)]
-pub struct MachineIdResult{
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct MachineIdResult {
     pub machine_id: Option<String>,
     pub error: Option<String>,
+    /// 失败时的数字错误码，不随 `error` 的措辞变化，对照表见 `MachineIdError::numeric_code`
+    pub error_code: Option<u32>,
+    /// 参与计算的因子，与 `hashAlgorithm`、`salt` 无关，切换算法时可以据此确认硬件没有变化
+    pub factors: Vec<String>,
+    /// 清理前的原始因子值，仅在 `includeRaw` 为 true 时返回
+    pub raw_factors: Option<Vec<RawFactor>>,
+    /// 是否使用 `hmacKey` 通过 HMAC (默认为 HMAC-SHA256) 计算
+    pub hmac: bool,
+    /// 非致命的问题，如无法识别的因子名、失败的单个查询，存在时 ID 由剩余的因子计算
+    pub warnings: Vec<String>,
+    /// 请求了但没有收集到的因子 key，如虚拟机上的 `disk_serial`；整个查询失败或没有数据时为因子名，如 `memory`
+    ///
+    /// 不为空时 ID 只由部分因子计算，硬件或权限变化后可能改变
+    pub missing_factors: Vec<String>,
+    /// 是否按 `hardwareOnly` 预设计算，此时 `factors` 只包含硬件因子
+    pub hardware_only: bool,
+}
+
+/// 成功时的 Machine ID，用于出错时抛出异常的 `getMachineIdStrict` 系列函数
+#[napi(object)]
+pub struct MachineIdValue {
+    pub machine_id: String,
     pub factors: Vec<String>,
+    pub raw_factors: Option<Vec<RawFactor>>,
+    pub hmac: bool,
+    pub warnings: Vec<String>,
+    pub missing_factors: Vec<String>,
+    pub hardware_only: bool,
+}
+
+#[cfg(all(
+    feature = "napi",
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+impl MachineIdValue {
+    fn new(
+        result: machine_id::MachineId,
+        options: &machine_id::MachineIdOptions,
+        mut warnings: Vec<String>,
+    ) -> Self {
+        warnings.extend(result.warnings);
+        MachineIdValue {
+            machine_id: result.machine_id,
+            factors: result.factors.into_iter().collect(),
+            raw_factors: options.include_raw.then(|| {
+                result
+                    .raw_factors
+                    .into_iter()
+                    .map(|(key, value)| RawFactor { key, value })
+                    .collect()
+            }),
+            hmac: result.hmac,
+            warnings,
+            missing_factors: result.missing,
+            hardware_only: options.hardware_only,
+        }
+    }
+}
+
+#[napi(object)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct RawFactor {
+    pub key: String,
+    pub value: String,
+}
+
+#[cfg(feature = "napi")]
+#[napi(object)]
+#[derive(Default)]
+pub struct MachineIdOptions {
+    /// 返回清理前的原始因子值供审计使用，默认关闭以免意外泄露硬件序列号
+    pub include_raw: Option<bool>,
+    /// 应用内置的密钥，提供时使用 HMAC-SHA256 代替 SHA-256
+    pub hmac_key: Option<Buffer>,
+    /// 从该环境变量读取 pepper 并参与计算，变量不存在时返回错误
+    pub pepper_env: Option<String>,
+    /// 从该文件读取 pepper 并参与计算，仅在未设置 `pepperEnv` 时使用，文件不存在时返回错误
+    pub pepper_file: Option<String>,
+    /// 按应用区分的命名空间，拼接在因子之前参与计算，同一台机器在不同应用中得到不同的 ID，
+    /// 避免原始的硬件哈希被多个应用关联；返回的 `factors` 仍为未加 salt 的因子
+    pub salt: Option<String>,
+    /// 哈希算法，默认为 `Sha256`；因子与算法无关，切换算法只会改变 `machineId`
+    pub hash_algorithm: Option<HashAlgorithm>,
+    /// 仅 Windows，代替默认 `root\cimv2` 的 WMI 命名空间，用于修改过的系统镜像
+    pub wmi_namespace: Option<String>,
+    /// 仅 Windows，单个 WMI 查询的超时时间 (毫秒)，默认为 5000；WMI 存储库损坏时查询可能一直阻塞，
+    /// 超时的查询视为失败，不影响其它因子
+    pub wmi_timeout_ms: Option<u32>,
+    /// 收集到的因子数量达到该值后跳过剩余的查询，适合只需要足够熵、不想等待慢查询的场景
+    pub min_factors: Option<u32>,
+    /// 只使用硬件因子的预设：忽略传入的因子，改用 `SystemUuid`、`Baseboard` 和 `DiskDrivers`，
+    /// 得到的 ID 只与物理机器有关，重装系统后不变
+    pub hardware_only: Option<bool>,
+}
+
+#[napi(object)]
+pub struct FactorDiff {
+    /// 新出现的因子
+    pub added: Vec<String>,
+    /// 不再存在的因子
+    pub removed: Vec<String>,
+    /// 两组因子是否完全一致
+    pub stable: bool,
+}
+
+/// 比较之前保存的因子与当前的因子，找出发生变化的硬件
+///
+/// 可用于实现“允许 N 个因子变化后才使许可证失效”之类的策略
+#[cfg(feature = "machine-id")]
+#[napi]
+pub fn compare_factors(old: Vec<String>, new: Vec<String>) -> FactorDiff {
+    let (added, removed) = machine_id::diff_factors(&old, &new);
+    let stable = added.is_empty() && removed.is_empty();
+    FactorDiff {
+        added,
+        removed,
+        stable,
+    }
+}
+
+#[napi]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+impl From<HashAlgorithm> for machine_id::HashAlgorithm {
+    fn from(value: HashAlgorithm) -> Self {
+        match value {
+            HashAlgorithm::Sha256 => machine_id::HashAlgorithm::Sha256,
+            HashAlgorithm::Sha512 => machine_id::HashAlgorithm::Sha512,
+            HashAlgorithm::Blake3 => machine_id::HashAlgorithm::Blake3,
+        }
+    }
 }
 
 #[napi]
 pub enum MachineIdFactor {
     Baseboard,
     Processor,
+    /// 系统盘型号和序列号；从同一模板克隆的虚拟机可能共享磁盘序列号，需要区分时另外指定 `VolumeSerial`
     DiskDrivers,
-    VideoControllers
+    VideoControllers,
+    /// 仅 Windows 与 Linux (`/etc/machine-id`)，系统安装 ID，重装系统后会改变，需要显式指定
+    MachineGuid,
+    /// 仅 Windows 与 Linux，机箱类型和机箱序列号，Linux 上读取序列号需要 root 权限
+    Chassis,
+    /// 仅 Windows 与 Linux，BIOS 版本、厂商和发布日期，升级 BIOS 后会改变，需要显式指定
+    Bios,
+    /// 仅 Windows 与 Linux，系统盘卷序列号 (Linux 为文件系统 UUID)，重新格式化后会改变
+    VolumeSerial,
+    /// 仅 Windows 与 Linux，主网卡 (默认路由所在的 PCI 网卡) 的 MAC 地址，不受扩展坞等 USB 网卡插拔的影响
+    PrimaryNetworkAdapter,
+    /// 仅 Windows，内存条序列号、部件号和容量，更换或增减内存后会改变
+    Memory,
+    /// 仅 Windows 与 Linux，显示器 EDID 中的厂商、产品代码和序列号，拔掉显示器后会改变，需要显式指定，
+    /// 适合显示器固定不变的自助终端
+    Display,
+    /// 仅 Windows，系统的 ProductId 和安装时间，将 ID 绑定到当前的系统安装，重装系统后会改变，需要显式指定
+    WindowsInstall,
+    /// 主板固件提供的 SMBIOS 系统 UUID (macOS 为 `IOPlatformUUID`)，重装系统后不变，
+    /// Linux 上读取 `/sys/class/dmi/id/product_uuid` 需要 root 权限
+    SystemUuid,
+    /// 仅 Windows，TPM 的厂商和规范版本，更换主板后会改变，不受更换磁盘的影响；查询需要管理员权限，
+    /// 没有 TPM 时不提供任何值
+    Tpm,
+    /// 仅 Windows 与 Linux，所有内置 (PCI) 物理网卡的 MAC 地址，排除虚拟机软件创建的虚拟网卡，
+    /// 增减网卡后会改变，需要显式指定
+    NetworkAdapter,
 }
 
-#[cfg(target_os = "windows")]
-impl Into<machine_id::windows::MachineIdFactor> for MachineIdFactor {
-    fn into(self) -> machine_id::windows::MachineIdFactor {
-        match self {
-            MachineIdFactor::Baseboard => machine_id::windows::MachineIdFactor::Baseboard,
-            MachineIdFactor::Processor => machine_id::windows::MachineIdFactor::Processor,
-            MachineIdFactor::DiskDrivers => machine_id::windows::MachineIdFactor::DiskDrives,
-            MachineIdFactor::VideoControllers => machine_id::windows::MachineIdFactor::VideoControllers
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+impl From<MachineIdFactor> for machine_id::MachineIdFactor {
+    fn from(value: MachineIdFactor) -> Self {
+        match value {
+            MachineIdFactor::Baseboard => machine_id::MachineIdFactor::Baseboard,
+            MachineIdFactor::Processor => machine_id::MachineIdFactor::Processor,
+            MachineIdFactor::DiskDrivers => machine_id::MachineIdFactor::DiskDrives,
+            MachineIdFactor::VideoControllers => machine_id::MachineIdFactor::VideoControllers,
+            MachineIdFactor::MachineGuid => machine_id::MachineIdFactor::MachineGuid,
+            MachineIdFactor::Chassis => machine_id::MachineIdFactor::Chassis,
+            MachineIdFactor::Bios => machine_id::MachineIdFactor::Bios,
+            MachineIdFactor::VolumeSerial => machine_id::MachineIdFactor::VolumeSerial,
+            MachineIdFactor::PrimaryNetworkAdapter => {
+                machine_id::MachineIdFactor::PrimaryNetworkAdapter
+            }
+            MachineIdFactor::Memory => machine_id::MachineIdFactor::Memory,
+            MachineIdFactor::Display => machine_id::MachineIdFactor::Display,
+            MachineIdFactor::WindowsInstall => machine_id::MachineIdFactor::WindowsInstall,
+            MachineIdFactor::SystemUuid => machine_id::MachineIdFactor::SystemUuid,
+            MachineIdFactor::Tpm => machine_id::MachineIdFactor::Tpm,
+            MachineIdFactor::NetworkAdapter => machine_id::MachineIdFactor::NetworkAdapter,
         }
     }
 }
 
-#[cfg(target_os = "windows")]
+#[cfg(all(
+    feature = "napi",
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+impl From<MachineIdOptions> for machine_id::MachineIdOptions {
+    fn from(value: MachineIdOptions) -> Self {
+        machine_id::MachineIdOptions {
+            include_raw: value.include_raw.unwrap_or(false),
+            hmac_key: value.hmac_key.map(|it| it.to_vec()),
+            pepper_env: value.pepper_env,
+            pepper_file: value.pepper_file,
+            salt: value.salt,
+            hash_algorithm: value.hash_algorithm.map(Into::into).unwrap_or_default(),
+            on_progress: None,
+            min_factors: value.min_factors,
+            wmi_namespace: value.wmi_namespace,
+            wmi_timeout_ms: value.wmi_timeout_ms,
+            hardware_only: value.hardware_only.unwrap_or(false),
+        }
+    }
+}
+
+#[napi(object)]
+pub struct MachineIdProgress {
+    /// 刚完成的步骤，如 `querying Win32_Processor`
+    pub step: String,
+    /// 已完成的步骤数，从 1 开始
+    pub index: u32,
+    pub total: u32,
+    /// 该步骤新增的因子，界面可以据此逐步展示，所有步骤的因子合起来即为最终结果
+    pub factors: Vec<String>,
+}
+
+/// 可选的进度回调，每完成一个因子的查询时调用，仅异步的导出接受
+#[cfg(all(
+    feature = "napi",
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+pub(crate) type ProgressCallback = napi::threadsafe_function::ThreadsafeFunction<
+    MachineIdProgress,
+    napi::threadsafe_function::ErrorStrategy::Fatal,
+>;
+
+/// 收集因子并生成 Machine ID，查询期间阻塞 JS 主线程
+///
+/// 不提供进度回调：回调通过线程安全函数投递到主线程，主线程被阻塞时只能在返回结果之后才收到，
+/// 需要进度时使用 `getMachineIdAsync`
+#[cfg(all(
+    feature = "napi",
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
 #[napi]
-pub fn get_machine_id(factors: Vec<MachineIdFactor>) -> MachineIdResult {
-    let factors = factors.into_iter().map(|it|it.into()).collect();
-    match machine_id::windows::get_machine_id_with_factors(factors) { 
-        Ok((machine_id, factors)) => {
+pub fn get_machine_id(
+    factors: Vec<MachineIdFactor>,
+    options: Option<MachineIdOptions>,
+) -> MachineIdResult {
+    let factors = factors.into_iter().map(|it| it.into()).collect();
+    machine_id_result(factors, &machine_id_options(options, None), Vec::new())
+}
+
+/// 通过字符串指定因子，如 `"baseboard"`、`"processor"`，旧版本的绑定也可以请求新增的因子
+///
+/// 无法识别的因子名会被忽略，并记录在 `warnings` 中；与 `getMachineId` 一样不提供进度回调
+#[cfg(all(
+    feature = "napi",
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+#[napi]
+pub fn get_machine_id_by_names(
+    factors: Vec<String>,
+    options: Option<MachineIdOptions>,
+) -> MachineIdResult {
+    let mut warnings = Vec::new();
+    let factors = factors
+        .iter()
+        .filter_map(|name| match name.parse() {
+            Ok(factor) => Some(factor),
+            Err(err) => {
+                warnings.push(err);
+                None
+            }
+        })
+        .collect();
+    machine_id_result(factors, &machine_id_options(options, None), warnings)
+}
+
+/// 与 `getMachineId` 相同，但失败时抛出带有 `code` 的错误 (如 `NO_FACTORS_FOUND`) 而不是返回 `error` 字段
+#[cfg(all(
+    feature = "napi",
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+#[napi]
+pub fn get_machine_id_strict(
+    factors: Vec<MachineIdFactor>,
+    options: Option<MachineIdOptions>,
+) -> napi::Result<MachineIdValue, &'static str> {
+    let factors = factors.into_iter().map(|it| it.into()).collect();
+    let options: machine_id::MachineIdOptions = options.unwrap_or_default().into();
+    machine_id::get_machine_id_with_factors(factors, &options)
+        .map(|result| MachineIdValue::new(result, &options, Vec::new()))
+        .map_err(|err| napi::Error::new(err.code(), err.to_string()))
+}
+
+/// 设置 Machine ID 缓存收集到的因子的时间 (毫秒)，省略时为 60 秒，传入 0 关闭缓存并清空已有结果
+///
+/// 缓存默认开启，与 `setCacheTtlMs` 相互独立；有效期内因子、`hardwareOnly`、`minFactors` 等参数相同的调用
+/// 直接使用缓存的因子，只重新计算哈希，因此不同的 salt 或哈希算法可以共享同一次查询；使用缓存时不会调用进度回调
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+#[napi]
+pub fn set_machine_id_cache_ttl_ms(ms: Option<u32>) {
+    machine_id::set_cache_ttl_ms(ms.unwrap_or(machine_id::DEFAULT_CACHE_TTL_MS));
+}
+
+/// 清空 Machine ID 缓存的因子，更换硬件后调用，下一次调用重新查询
+#[cfg(all(
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+#[napi]
+pub fn clear_machine_id_cache() {
+    machine_id::clear_cache();
+}
+
+/// 读取原始固件表，供调用方自行解析 SMBIOS/ACPI
+///
+/// `provider` 与 `tableId` 与 Windows 的 `GetSystemFirmwareTable` 一致:
+/// - `0x52534D42` (`RSMB`): 忽略 `tableId`，返回 `RawSMBIOSData` (8 字节头 + SMBIOS 结构表)，
+///   Linux 读取 `/sys/firmware/dmi/tables` 并拼接相同的头
+/// - `0x41435049` (`ACPI`): `tableId` 为签名的小端序值，如 `FACP` 为 `0x50434146`，
+///   Linux 读取 `/sys/firmware/acpi/tables`
+///
+/// Linux 上读取这些文件通常需要 root 权限，失败时抛出带有 `code` 的错误
+/// (`UNSUPPORTED_PROVIDER`、`TABLE_NOT_FOUND`、`READ_FAILED`)
+#[cfg(all(feature = "napi", any(target_os = "windows", target_os = "linux")))]
+#[napi]
+pub fn get_firmware_table(provider: u32, table_id: u32) -> napi::Result<Buffer, &'static str> {
+    firmware::read_table(provider, table_id)
+        .map(Buffer::from)
+        .map_err(|err| napi::Error::new(err.code(), err.to_string()))
+}
+
+/// 导出 WMI 类的原始数据为 JSON 字符串，用于排查 Machine ID 因子缺失的问题
+///
+/// 仅允许 Machine ID 会查询的类 (如 `Win32_BaseBoard`、`Win32_Processor`、`Win32_DiskDrive`)，
+/// 失败时返回 `{ "error": "..." }`
+#[cfg(all(feature = "machine-id", target_os = "windows"))]
+#[napi]
+pub fn dump_wmi_class(class: String) -> String {
+    machine_id::windows::dump_wmi_class(&class).unwrap_or_else(|err| {
+        serde_json::json!({ "code": err.code(), "error": err.to_string() }).to_string()
+    })
+}
+
+#[cfg(all(
+    feature = "napi",
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+/// 转换选项并将进度回调包装为线程安全的调用，`Buffer` 等 JS 值在这里转换，之后的计算可以在任意线程中进行
+pub(crate) fn machine_id_options(
+    options: Option<MachineIdOptions>,
+    on_progress: Option<ProgressCallback>,
+) -> machine_id::MachineIdOptions {
+    use napi::threadsafe_function::ThreadsafeFunctionCallMode;
+
+    let mut options: machine_id::MachineIdOptions = options.unwrap_or_default().into();
+    options.on_progress = on_progress.map(|callback| -> machine_id::ProgressCallback {
+        Box::new(move |step, index, total, factors| {
+            callback.call(
+                MachineIdProgress {
+                    step: step.to_string(),
+                    index,
+                    total,
+                    factors: factors.to_vec(),
+                },
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        })
+    });
+    options
+}
+
+#[cfg(all(
+    feature = "napi",
+    feature = "machine-id",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+pub(crate) fn machine_id_result(
+    factors: Vec<machine_id::MachineIdFactor>,
+    options: &machine_id::MachineIdOptions,
+    warnings: Vec<String>,
+) -> MachineIdResult {
+    match machine_id::get_machine_id_with_factors(factors, options) {
+        Ok(result) => {
+            let value = MachineIdValue::new(result, options, warnings);
             MachineIdResult {
-                machine_id: Some(machine_id),
+                machine_id: Some(value.machine_id),
                 error: None,
-                factors: factors.into_iter().collect()
+                error_code: None,
+                factors: value.factors,
+                raw_factors: value.raw_factors,
+                hmac: value.hmac,
+                warnings: value.warnings,
+                missing_factors: value.missing_factors,
+                hardware_only: value.hardware_only,
             }
-        },
+        }
         Err(err) => {
+            let mut warnings = warnings;
+            // 所有查询都失败时，各个查询失败的原因同样放入 warnings
+            if let machine_id::MachineIdError::NoFactorsFound(failures) = &err {
+                warnings.extend(failures.iter().cloned());
+            }
             MachineIdResult {
                 machine_id: None,
                 error: Some(err.to_string()),
+                error_code: Some(err.numeric_code()),
                 factors: vec![],
+                raw_factors: None,
+                hmac: options.hmac_key.is_some(),
+                warnings,
+                missing_factors: vec![],
+                hardware_only: options.hardware_only,
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virt_status_kind() {
+        assert_eq!(VirtStatus::new(true, true).kind(false), "enabled");
+        assert_eq!(VirtStatus::new(true, false).kind(false), "disabled");
+        assert_eq!(VirtStatus::new(false, false).kind(false), "disabled");
+        assert_eq!(VirtStatus::new(false, true).kind(false), "unknown");
+        // 检查失败时 `os_reported_enabled` 为 false 只表示无法确认
+        assert_eq!(VirtStatus::new(true, false).kind(true), "unknown");
+        assert_eq!(VirtStatus::new(false, false).kind(true), "unknown");
+    }
+}
@@ -1,10 +1,744 @@
+// 在尚未实现 Machine ID 的平台上，这些公共实现暂时没有调用方
+#![cfg_attr(
+    not(any(target_os = "windows", target_os = "macos", target_os = "linux")),
+    allow(dead_code)
+)]
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MachineIdFactor {
+    Baseboard = 1,
+    Processor,
+    VideoControllers,
+    /// 系统盘的型号和序列号
+    ///
+    /// 从同一模板克隆的虚拟机通常共享虚拟磁盘序列号，需要区分克隆时另外指定 `VolumeSerial`
+    DiskDrives,
+    /// 注册表 `HKLM\SOFTWARE\Microsoft\Cryptography\MachineGuid`，重装系统后会改变
+    MachineGuid,
+    /// 机箱类型和机箱序列号，OEM 笔记本上机箱序列号通常是最稳定的字段
+    Chassis,
+    /// BIOS 版本、厂商和发布日期，升级 BIOS 后会改变，属于半稳定因子
+    Bios,
+    /// 系统盘的卷序列号，重新格式化或重装镜像后会改变，作为磁盘硬件序列号的补充
+    VolumeSerial,
+    /// 默认路由所在的内置 (PCI) 网卡的 MAC 地址，只取一块网卡，扩展坞等 USB 网卡插拔时不受影响
+    PrimaryNetworkAdapter,
+    /// 内存条的序列号、部件号和容量，按序列号排序后合并为一个因子，更换插槽不会改变结果
+    Memory,
+    /// 显示器 EDID 中的厂商、产品代码和序列号，拔掉或更换显示器后会改变，属于半稳定因子，
+    /// 适合显示器固定不变的自助终端等设备
+    Display,
+    /// 注册表 `HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion` 中的 `ProductId` 和 `InstallDate`，
+    /// 将 ID 绑定到当前的系统安装，重装系统 (包括原地升级到新的功能版本) 后会改变
+    WindowsInstall,
+    /// SMBIOS 系统 UUID (Windows 为 `Win32_ComputerSystemProduct.UUID`，macOS 为 `IOPlatformUUID`)，
+    /// 由主板固件提供，重装系统后不变；全 0 或全 F 的占位值会被忽略
+    SystemUuid,
+    /// TPM 的厂商和规范版本 (`Win32_Tpm`)，随主板或 CPU (固件 TPM) 更换而改变，与磁盘无关；
+    /// 没有 TPM 时不提供任何值
+    Tpm,
+    /// 所有内置 (PCI) 物理网卡的 MAC 地址，排除 Hyper-V、VMware 等创建的虚拟网卡，
+    /// 增减网卡后会改变，需要只绑定一块网卡时使用 `PrimaryNetworkAdapter`
+    NetworkAdapter,
+}
+
+/// `hardware_only` 使用的因子，只包含固件和硬件序列号，重装系统后不变
+pub const HARDWARE_ONLY_FACTORS: [MachineIdFactor; 3] = [
+    MachineIdFactor::SystemUuid,
+    MachineIdFactor::Baseboard,
+    MachineIdFactor::DiskDrives,
+];
+
+impl std::str::FromStr for MachineIdFactor {
+    type Err = String;
+
+    /// 解析 snake_case 形式的因子名，忽略大小写
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "baseboard" => Ok(MachineIdFactor::Baseboard),
+            "processor" => Ok(MachineIdFactor::Processor),
+            "video_controllers" => Ok(MachineIdFactor::VideoControllers),
+            "disk_drives" => Ok(MachineIdFactor::DiskDrives),
+            "machine_guid" => Ok(MachineIdFactor::MachineGuid),
+            "chassis" => Ok(MachineIdFactor::Chassis),
+            "bios" => Ok(MachineIdFactor::Bios),
+            "volume_serial" => Ok(MachineIdFactor::VolumeSerial),
+            "primary_network_adapter" => Ok(MachineIdFactor::PrimaryNetworkAdapter),
+            "memory" => Ok(MachineIdFactor::Memory),
+            "display" => Ok(MachineIdFactor::Display),
+            "windows_install" => Ok(MachineIdFactor::WindowsInstall),
+            "system_uuid" => Ok(MachineIdFactor::SystemUuid),
+            "tpm" => Ok(MachineIdFactor::Tpm),
+            "network_adapter" => Ok(MachineIdFactor::NetworkAdapter),
+            _ => Err(format!("Unknown factor name '{}', ignored", s)),
+        }
+    }
+}
+
+#[derive(Debug)]
+// WMI 相关的错误只在 Windows 上产生
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub enum MachineIdError {
+    WMIInitialization(String),
+    ChannelSend(String),
+    ChannelRecv(String),
+    QueryError(String),
+    WorkerThreadPanicked(String),
+    /// 没有收集到任何因子，附带各个失败查询的原因
+    NoFactorsFound(Vec<String>),
+    PepperUnavailable(String),
+}
+
+impl std::fmt::Display for MachineIdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MachineIdError::WMIInitialization(s) => {
+                write!(f, "WMI Initialization Error: {}", s)
+            }
+            MachineIdError::ChannelSend(s) => write!(f, "Channel Send Error: {}", s),
+            MachineIdError::ChannelRecv(s) => write!(f, "Channel Receive Error: {}", s),
+            MachineIdError::QueryError(s) => write!(f, "WMI Query Error: {}", s),
+            MachineIdError::WorkerThreadPanicked(s) => {
+                write!(f, "Worker thread panicked: {}", s)
+            }
+            MachineIdError::NoFactorsFound(warnings) if warnings.is_empty() => {
+                write!(f, "Could not gather any hardware factors")
+            }
+            MachineIdError::NoFactorsFound(warnings) => {
+                write!(
+                    f,
+                    "Could not gather any hardware factors: {}",
+                    warnings.join("; ")
+                )
+            }
+            MachineIdError::PepperUnavailable(s) => write!(f, "Pepper Unavailable: {}", s),
+        }
+    }
+}
+impl std::error::Error for MachineIdError {}
+
+impl MachineIdError {
+    /// 稳定的错误码，作为 JS 错误的 `code` 属性
+    pub fn code(&self) -> &'static str {
+        match self {
+            MachineIdError::WMIInitialization(_) => "WMI_INITIALIZATION",
+            MachineIdError::ChannelSend(_) => "CHANNEL_SEND",
+            MachineIdError::ChannelRecv(_) => "CHANNEL_RECV",
+            MachineIdError::QueryError(_) => "QUERY_ERROR",
+            MachineIdError::WorkerThreadPanicked(_) => "WORKER_THREAD_PANICKED",
+            MachineIdError::NoFactorsFound(_) => "NO_FACTORS_FOUND",
+            MachineIdError::PepperUnavailable(_) => "PEPPER_UNAVAILABLE",
+        }
+    }
+
+    /// 稳定的数字错误码，便于遥测按错误类型聚合，不随错误信息的措辞或语言变化
+    ///
+    /// | 错误码 | 字符串错误码 |
+    /// | --- | --- |
+    /// | 1 | `WMI_INITIALIZATION` |
+    /// | 2 | `CHANNEL_SEND` |
+    /// | 3 | `CHANNEL_RECV` |
+    /// | 4 | `QUERY_ERROR` |
+    /// | 5 | `WORKER_THREAD_PANICKED` |
+    /// | 6 | `NO_FACTORS_FOUND` |
+    /// | 7 | `PEPPER_UNAVAILABLE` |
+    ///
+    /// 新增的错误类型使用新的编号，已分配的编号不会复用
+    pub fn numeric_code(&self) -> u32 {
+        match self {
+            MachineIdError::WMIInitialization(_) => 1,
+            MachineIdError::ChannelSend(_) => 2,
+            MachineIdError::ChannelRecv(_) => 3,
+            MachineIdError::QueryError(_) => 4,
+            MachineIdError::WorkerThreadPanicked(_) => 5,
+            MachineIdError::NoFactorsFound(_) => 6,
+            MachineIdError::PepperUnavailable(_) => 7,
+        }
+    }
+}
+
+// 辅助函数，清理和标准化字符串
+//
+// 统一为 NFC 形式并且只转换 ASCII 字母的大小写，避免同一硬件因组合字符或特殊大小写映射 (如土耳其语的 I/İ) 得到不同的因子
+fn sanitize_string(s: Option<String>) -> Option<String> {
+    s.map(|val| val.trim().nfc().collect::<String>().to_ascii_lowercase())
+        .filter(|val| {
+            !val.is_empty()
+                && !val.contains("to be filled by o.e.m.")
+                && !val.contains("default string")
+                && !val.contains("none")
+                && val != "00000000"
+                && val != "o.e.m."
+        })
+}
+
+/// 读取调用方配置的 pepper，优先使用环境变量，其次是文件
+///
+/// 配置了来源但读取失败或内容为空时返回错误，而不是静默地退回到不带 pepper 的 ID
+fn load_pepper(options: &MachineIdOptions) -> Result<Option<String>, MachineIdError> {
+    let pepper = if let Some(name) = &options.pepper_env {
+        std::env::var(name).map_err(|e| {
+            MachineIdError::PepperUnavailable(format!("environment variable '{name}': {e}"))
+        })?
+    } else if let Some(path) = &options.pepper_file {
+        std::fs::read_to_string(path)
+            .map_err(|e| MachineIdError::PepperUnavailable(format!("file '{path}': {e}")))?
+            // 文件通常以换行结尾
+            .trim_end_matches(['\r', '\n'])
+            .to_string()
+    } else {
+        return Ok(None);
+    };
+    if pepper.is_empty() {
+        return Err(MachineIdError::PepperUnavailable(
+            "configured pepper is empty".to_string(),
+        ));
+    }
+    Ok(Some(pepper))
+}
+
+/// 将所有因子按顺序拼接后按 `hash_algorithm` (默认 SHA-256) 计算哈希，得到 Machine ID
+///
+/// 提供了 `hmac_key` 时改为计算 HMAC (BLAKE3 为密钥模式)，没有密钥就无法从已知硬件信息预先算出 ID；
+/// 配置了 salt 时会将其拼接在所有因子之前，配置了 pepper 时会将其拼接在所有因子之后
+fn hash_factors(
+    factors: &BTreeSet<String>,
+    options: &MachineIdOptions,
+) -> Result<String, MachineIdError> {
+    if factors.is_empty() {
+        return Err(MachineIdError::NoFactorsFound(Vec::new()));
+    }
+    let mut combined_string = factors.iter().cloned().collect::<Vec<String>>().join("|");
+    // 空字符串视为未设置，保证不传 salt 时结果与之前的版本一致
+    if let Some(salt) = options.salt.as_deref().filter(|it| !it.is_empty()) {
+        combined_string.insert_str(0, &format!("salt:{salt}|"));
+    }
+    if let Some(pepper) = load_pepper(options)? {
+        combined_string.push_str("|pepper:");
+        combined_string.push_str(&pepper);
+    }
+    let data = combined_string.as_bytes();
+    // HMAC 接受任意长度的密钥，这里不会失败
+    let hash = match (options.hash_algorithm, &options.hmac_key) {
+        (HashAlgorithm::Sha256, Some(key)) => {
+            let mut mac =
+                Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+        (HashAlgorithm::Sha512, Some(key)) => {
+            let mut mac =
+                Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any size");
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+        // BLAKE3 的密钥模式要求 32 字节的密钥，任意长度的密钥先哈希为 32 字节
+        (HashAlgorithm::Blake3, Some(key)) => {
+            blake3::keyed_hash(blake3::hash(key).as_bytes(), data)
+                .as_bytes()
+                .to_vec()
+        }
+        (HashAlgorithm::Sha256, None) => Sha256::digest(data).to_vec(),
+        (HashAlgorithm::Sha512, None) => Sha512::digest(data).to_vec(),
+        (HashAlgorithm::Blake3, None) => blake3::hash(data).as_bytes().to_vec(),
+    };
+    Ok(to_hex(&hash[..]))
+}
+
+/// 计算 Machine ID 使用的哈希算法，因子的收集与拼接与算法无关，切换算法只会改变最终的哈希值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// 64 位十六进制字符串，与之前的版本一致
+    #[default]
+    Sha256,
+    /// 128 位十六进制字符串
+    Sha512,
+    /// 64 位十六进制字符串
+    Blake3,
+}
+
+/// 单个 WMI 查询的默认超时时间，WMI 存储库损坏时查询可能一直阻塞
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub const DEFAULT_WMI_TIMEOUT_MS: u32 = 5_000;
+
+/// 进度回调，参数为 (步骤描述, 已完成的步骤数, 总步骤数, 该步骤新增的因子)，可能在任意线程中调用
+pub type ProgressCallback = Box<dyn Fn(&str, u32, u32, &[String]) + Send + Sync>;
+
+/// Machine ID 生成选项
+#[derive(Default)]
+pub struct MachineIdOptions {
+    /// 是否返回清理前的原始因子值，默认关闭以免意外泄露硬件序列号
+    pub include_raw: bool,
+    /// HMAC 密钥，为空时使用普通的 SHA-256
+    pub hmac_key: Option<Vec<u8>>,
+    /// 存放 pepper 的环境变量名，轮换密钥时无需修改代码
+    pub pepper_env: Option<String>,
+    /// 存放 pepper 的文件路径，仅在未设置 `pepper_env` 时使用
+    pub pepper_file: Option<String>,
+    /// 按应用区分的命名空间，同一台机器在不同的 salt 下得到不同的 ID，返回的因子不受影响
+    pub salt: Option<String>,
+    /// 哈希算法，默认为 SHA-256
+    pub hash_algorithm: HashAlgorithm,
+    /// 每完成一个因子的查询时调用
+    pub on_progress: Option<ProgressCallback>,
+    /// 收集到的因子数量达到该值后跳过剩余的查询，用于在部分查询很慢时提前结束
+    ///
+    /// 查询按固定顺序执行，跳过的步骤不会触发进度回调
+    pub min_factors: Option<u32>,
+    /// 仅 Windows，代替 `ROOT\CIMV2` 的 WMI 命名空间，用于修改过的系统镜像
+    ///
+    /// 显示器等位于其它命名空间的因子不受影响
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub wmi_namespace: Option<String>,
+    /// 仅 Windows，单个 WMI 查询的超时时间 (毫秒)，为空时使用 [`DEFAULT_WMI_TIMEOUT_MS`]
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub wmi_timeout_ms: Option<u32>,
+    /// 忽略请求的因子，改用 [`HARDWARE_ONLY_FACTORS`]，得到的 ID 只与物理硬件有关，重装系统后不变
+    pub hardware_only: bool,
+}
+
+impl MachineIdOptions {
+    /// 实际参与计算的因子，开启 `hardware_only` 时为 [`HARDWARE_ONLY_FACTORS`]
+    fn effective_factors(&self, requested: Vec<MachineIdFactor>) -> Vec<MachineIdFactor> {
+        if self.hardware_only {
+            HARDWARE_ONLY_FACTORS.to_vec()
+        } else {
+            requested
+        }
+    }
+}
+
+/// 生成的 Machine ID 及参与计算的因子
+pub struct MachineId {
+    pub machine_id: String,
+    pub factors: BTreeSet<String>,
+    /// 请求了但没有收集到的因子 key (如 `disk_serial`)，整个查询失败或没有数据时为因子名 (如 `memory`)
+    pub missing: Vec<String>,
+    /// (因子名, 清理前的原始值)，仅在 `include_raw` 时填充
+    pub raw_factors: Vec<(String, String)>,
+    /// 是否使用 HMAC 计算
+    pub hmac: bool,
+    /// 失败但不影响整体结果的查询，此时 ID 由剩余的因子计算
+    pub warnings: Vec<String>,
+}
+
+/// 收集到的因子，尚未计算哈希，与 salt、哈希算法等参数无关
+#[derive(Clone)]
+struct CollectedFactors {
+    values: BTreeSet<String>,
+    missing: Vec<String>,
+    raw: Vec<(String, String)>,
+    warnings: Vec<String>,
+}
+
+/// 请求的因子去重后的数量
+fn count_unique(factors: &[MachineIdFactor]) -> u32 {
+    factors
+        .iter()
+        .enumerate()
+        .filter(|(i, factor)| !factors[..*i].contains(factor))
+        .count() as u32
+}
+
+/// 因子收集器，统一负责字符串清理、原始值的记录和进度通知
+struct Factors<'a> {
+    values: BTreeSet<String>,
+    /// 当前步骤新增的因子，完成步骤时随进度回调一起发送
+    pending: Vec<String>,
+    /// 请求了但没有收集到的因子
+    missing: Vec<String>,
+    /// 当前步骤开始时 `missing` 的长度
+    missing_mark: usize,
+    min_factors: Option<u32>,
+    raw: Vec<(String, String)>,
+    include_raw: bool,
+    warnings: Vec<String>,
+    on_progress: Option<&'a ProgressCallback>,
+    completed_steps: u32,
+    total_steps: u32,
+}
+
+impl<'a> Factors<'a> {
+    fn new(options: &'a MachineIdOptions) -> Self {
+        Factors {
+            values: BTreeSet::new(),
+            pending: Vec::new(),
+            missing: Vec::new(),
+            missing_mark: 0,
+            // 为 0 时视为未设置，否则会跳过所有查询
+            min_factors: options.min_factors.filter(|it| *it > 0),
+            raw: Vec::new(),
+            include_raw: options.include_raw,
+            warnings: Vec::new(),
+            on_progress: options.on_progress.as_ref(),
+            completed_steps: 0,
+            total_steps: 0,
+        }
+    }
+
+    fn set_total(&mut self, total: u32) {
+        self.total_steps = total;
+    }
+
+    /// 完成一个步骤，通知进度回调
+    ///
+    /// 该步骤没有收集到任何因子 (查询失败或没有数据) 且没有记录具体缺失的 key 时，将 `factor` 记为缺失
+    fn step(&mut self, factor: &str, step: &str) {
+        if self.pending.is_empty() && self.missing.len() == self.missing_mark {
+            self.missing.push(factor.to_string());
+        }
+        self.missing_mark = self.missing.len();
+        self.completed_steps += 1;
+        let pending = std::mem::take(&mut self.pending);
+        if let Some(on_progress) = self.on_progress {
+            on_progress(step, self.completed_steps, self.total_steps, &pending);
+        }
+    }
+
+    /// 是否已经收集到 `min_factors` 个因子
+    fn satisfied(&self) -> bool {
+        self.min_factors
+            .is_some_and(|min| self.values.len() >= min as usize)
+    }
+
+    /// 清理原始值，按需记录清理前的值
+    fn sanitize(&mut self, key: &str, raw: Option<String>) -> Option<String> {
+        if self.include_raw
+            && let Some(raw) = &raw
+        {
+            self.raw.push((key.to_string(), raw.clone()));
+        }
+        sanitize_string(raw)
+    }
+
+    /// 清理后以 `key:value` 的形式插入，值不存在或是占位符时将 `key` 记为缺失
+    fn insert(&mut self, key: &str, raw: Option<String>) {
+        match self.sanitize(key, raw) {
+            Some(val) => self.insert_combined(format!("{key}:{val}")),
+            None => self.missing.push(key.to_string()),
+        }
+    }
+
+    /// 插入已经组合好的因子，如多个字段拼接而成的 GPU 因子
+    fn insert_combined(&mut self, value: String) {
+        if self.values.insert(value.clone()) {
+            self.pending.push(value);
+        }
+    }
+
+    /// 记录某个因子查询失败，继续收集其它因子
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    fn warn(&mut self, error: MachineIdError) {
+        self.warnings.push(error.to_string());
+    }
+
+    fn finish(mut self) -> CollectedFactors {
+        // 排序并去重，结果与查询顺序无关
+        self.missing.sort();
+        self.missing.dedup();
+        CollectedFactors {
+            values: self.values,
+            missing: self.missing,
+            raw: self.raw,
+            warnings: self.warnings,
+        }
+    }
+}
+
+/// 比较两组因子，返回 (新增的因子, 移除的因子)，均按 key 排序
+///
+/// 因子为 `key:value` 形式，某个 key 的值发生变化时表现为移除旧值并新增新值，
+/// 同一个 key 有多个值 (如多块硬盘) 时只会报告实际变化的那一个
+pub fn diff_factors(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let old = old.iter().collect::<BTreeSet<_>>();
+    let new = new.iter().collect::<BTreeSet<_>>();
+    let added = new.difference(&old).map(|it| it.to_string()).collect();
+    let removed = old.difference(&new).map(|it| it.to_string()).collect();
+    (added, removed)
+}
+
+/// 从多块主板 (如刀片服务器) 中确定性地选出一块，参数为 (制造商, 型号, 序列号)
+///
+/// WMI 不保证枚举顺序，按序列号排序后选择最小的一块，没有序列号的主板排在最后
+#[cfg(any(target_os = "windows", test))]
+fn select_baseboard(
+    boards: Vec<(Option<String>, Option<String>, Option<String>)>,
+) -> Option<(Option<String>, Option<String>, Option<String>)> {
+    boards
+        .into_iter()
+        .min_by(|a, b| (a.2.is_none(), &a.2, &a.0, &a.1).cmp(&(b.2.is_none(), &b.2, &b.0, &b.1)))
+}
+
+/// 判断 SMBIOS 系统 UUID 是否有效，未填写的固件常返回全 0 或全 F 的占位值
+#[cfg(any(target_os = "windows", target_os = "linux", test))]
+fn is_valid_system_uuid(uuid: &str) -> bool {
+    let digits = uuid
+        .chars()
+        .filter(|it| it.is_ascii_hexdigit())
+        .map(|it| it.to_ascii_uppercase())
+        .collect::<String>();
+    digits.len() == 32 && digits.bytes().any(|it| it != b'0') && digits.bytes().any(|it| it != b'F')
+}
+
+/// 判断 `Win32_DiskDrive` 是否为内置的固定磁盘
+///
+/// 部分 NVMe 驱动不填写 `InterfaceType`，`MediaType` 也可能为空或是 `Fixed hard disk` 等变体，
+/// 因此只排除明确为 USB 或可移动/外置介质的磁盘
+#[cfg(any(target_os = "windows", test))]
+fn is_internal_fixed_disk(media_type: Option<&str>, interface_type: Option<&str>) -> bool {
+    let is_usb = interface_type.is_some_and(|it| it.trim().eq_ignore_ascii_case("USB"));
+    let is_fixed = media_type.is_none_or(|it| {
+        let it = it.to_ascii_lowercase();
+        it.trim().is_empty() || (it.contains("fixed") && !it.contains("removable"))
+    });
+    !is_usb && is_fixed
+}
+
+/// 从内置网卡中选出主网卡的 MAC 地址
+///
+/// `adapters` 为 (接口索引, PNPDeviceID, MAC)，`default_routes` 为默认路由的 (接口索引, 跃点数)。
+/// 只考虑 PCI 网卡，优先选择跃点数最小的默认路由所在的网卡；默认路由都不在 PCI 网卡上时
+/// (如离线或通过扩展坞联网) 选择 PNPDeviceID 最小的网卡，避免枚举顺序变化导致结果不同
+#[cfg(any(target_os = "windows", test))]
+fn select_primary_mac(
+    adapters: &[(u32, String, String)],
+    default_routes: &[(u32, u32)],
+) -> Option<String> {
+    let pci_adapters = adapters
+        .iter()
+        .filter(|(_, pnp_device_id, _)| pnp_device_id.to_ascii_uppercase().starts_with(r"PCI\"))
+        .collect::<Vec<_>>();
+    let routed = default_routes
+        .iter()
+        .filter_map(|&(interface_index, metric)| {
+            pci_adapters
+                .iter()
+                .find(|(index, _, _)| *index == interface_index)
+                .map(|adapter| (metric, *adapter))
+        })
+        .min_by(|(a_metric, a), (b_metric, b)| a_metric.cmp(b_metric).then(a.1.cmp(&b.1)))
+        .map(|(_, adapter)| adapter);
+    routed
+        .or_else(|| pci_adapters.into_iter().min_by(|a, b| a.1.cmp(&b.1)))
+        .map(|(_, _, mac)| mac.clone())
+}
+
+/// 根据 PCI 厂商 ID 和厂商名判断是否为虚拟化软件创建的网卡，这类网卡的 MAC 地址通常是随机生成的
+#[cfg(any(target_os = "windows", target_os = "linux", test))]
+fn is_virtual_nic(vendor_id: &str, manufacturer: &str) -> bool {
+    // VMware、VirtualBox、virtio (Red Hat)、Microsoft
+    const VIRTUAL_VENDOR_IDS: &[&str] = &["15AD", "80EE", "1AF4", "1414"];
+    const VIRTUAL_MANUFACTURERS: &[&str] = &[
+        "microsoft",
+        "vmware",
+        "virtualbox",
+        "oracle",
+        "red hat",
+        "qemu",
+        "parallels",
+    ];
+    let manufacturer = manufacturer.to_ascii_lowercase();
+    VIRTUAL_VENDOR_IDS
+        .iter()
+        .any(|it| it.eq_ignore_ascii_case(vendor_id))
+        || VIRTUAL_MANUFACTURERS
+            .iter()
+            .any(|it| manufacturer.contains(it))
+}
+
+/// 从网卡中选出物理网卡的 MAC 地址，`adapters` 为 (PNPDeviceID, 厂商, MAC)
+///
+/// 与显卡一样只保留 `PCI\VEN_` 开头的设备，再排除虚拟网卡；结果按 PNPDeviceID 排序，
+/// 不受枚举顺序影响，重启后保持一致
+#[cfg(any(target_os = "windows", test))]
+fn select_physical_macs(adapters: &[(String, Option<String>, String)]) -> Vec<String> {
+    let mut physical = adapters
+        .iter()
+        .filter(|(pnp_device_id, manufacturer, _)| {
+            crate::virtualization::is_pci_device(pnp_device_id)
+                && !is_virtual_nic(
+                    pnp_device_id.get(8..12).unwrap_or_default(),
+                    manufacturer.as_deref().unwrap_or_default(),
+                )
+        })
+        .map(|(pnp_device_id, _, mac)| (pnp_device_id.to_ascii_uppercase(), mac.clone()))
+        .collect::<Vec<_>>();
+    physical.sort();
+    physical.dedup_by(|a, b| a.1.eq_ignore_ascii_case(&b.1));
+    physical.into_iter().map(|(_, mac)| mac).collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for &byte in bytes {
+        hex.push(DIGITS[(byte >> 4) as usize] as char);
+        hex.push(DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    hex
+}
+
+#[cfg(target_os = "windows")]
+use windows::collect_factors;
+
+#[cfg(target_os = "macos")]
+use macos::collect_factors;
+
+#[cfg(target_os = "linux")]
+use linux::collect_factors;
+
+/// 收集 `factors` 并计算 Machine ID
+///
+/// 收集到的因子会缓存 [`DEFAULT_CACHE_TTL_MS`] (可通过 [`set_cache_ttl_ms`] 修改)，有效期内相同的请求不再重新查询，
+/// 也不会触发进度回调；只缓存因子，salt、哈希算法、HMAC 密钥和 pepper 每次调用时重新计算，不参与缓存的 key。
+/// 有查询失败或超时 (`warnings` 不为空) 的结果不缓存
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+pub fn get_machine_id_with_factors(
+    factors: Vec<MachineIdFactor>,
+    options: &MachineIdOptions,
+) -> Result<MachineId, MachineIdError> {
+    let key = CacheKey::new(&factors, options);
+    let collected = match cached_factors(&key) {
+        Some(collected) => collected,
+        None => {
+            let collected = collect_factors(factors, options)?;
+            // 没有收集到任何因子时下面会返回错误；部分查询失败或超时时结果可能只是暂时不完整，
+            // 缓存会在有效期内固定一个不同的 ID，因此都不缓存
+            if !collected.values.is_empty() && collected.warnings.is_empty() {
+                cache_factors(key, collected.clone());
+            }
+            collected
+        }
+    };
+    // 所有查询都失败时，只有 warnings 记录了具体原因
+    if collected.values.is_empty() {
+        return Err(MachineIdError::NoFactorsFound(collected.warnings));
+    }
+    Ok(MachineId {
+        machine_id: hash_factors(&collected.values, options)?,
+        factors: collected.values,
+        missing: collected.missing,
+        raw_factors: collected.raw,
+        hmac: options.hmac_key.is_some(),
+        warnings: collected.warnings,
+    })
+}
+
+/// 收集到的因子的默认缓存时间，硬件在一次会话中很少变化
+pub const DEFAULT_CACHE_TTL_MS: u32 = 60_000;
+
+/// 缓存时间，0 表示关闭缓存
+static CACHE_TTL_MS: AtomicU32 = AtomicU32::new(DEFAULT_CACHE_TTL_MS);
+
+/// 影响因子收集结果的请求参数，去重并排序后的因子与顺序无关
+#[derive(PartialEq, Eq, Hash)]
+struct CacheKey {
+    factors: Vec<u8>,
+    hardware_only: bool,
+    min_factors: Option<u32>,
+    include_raw: bool,
+    wmi_namespace: Option<String>,
+    wmi_timeout_ms: u32,
+}
+
+impl CacheKey {
+    fn new(factors: &[MachineIdFactor], options: &MachineIdOptions) -> Self {
+        let mut factors = options
+            .effective_factors(factors.to_vec())
+            .into_iter()
+            .map(|it| it as u8)
+            .collect::<Vec<_>>();
+        factors.sort_unstable();
+        factors.dedup();
+        CacheKey {
+            factors,
+            hardware_only: options.hardware_only,
+            min_factors: options.min_factors.filter(|it| *it > 0),
+            include_raw: options.include_raw,
+            wmi_namespace: options.wmi_namespace.clone(),
+            // 较短的超时可能得到较少的因子，不能与默认超时的请求共享结果
+            wmi_timeout_ms: options.wmi_timeout_ms.unwrap_or(DEFAULT_WMI_TIMEOUT_MS),
+        }
+    }
+}
+
+type CacheEntries = HashMap<CacheKey, (Instant, CollectedFactors)>;
+
+fn cache_entries() -> MutexGuard<'static, CacheEntries> {
+    static ENTRIES: OnceLock<Mutex<CacheEntries>> = OnceLock::new();
+    ENTRIES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+fn cached_factors(key: &CacheKey) -> Option<CollectedFactors> {
+    let ttl = CACHE_TTL_MS.load(Ordering::Relaxed);
+    if ttl == 0 {
+        return None;
+    }
+    cache_entries()
+        .get(key)
+        .filter(|(created_at, _)| created_at.elapsed() < Duration::from_millis(ttl as u64))
+        .map(|(_, collected)| collected.clone())
+}
+
+fn cache_factors(key: CacheKey, collected: CollectedFactors) {
+    if CACHE_TTL_MS.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let mut entries = cache_entries();
+    // 过期的结果不会再被使用，顺便清理，避免不同参数的请求使缓存无限增长
+    let ttl = Duration::from_millis(CACHE_TTL_MS.load(Ordering::Relaxed) as u64);
+    entries.retain(|_, (created_at, _)| created_at.elapsed() < ttl);
+    entries.insert(key, (Instant::now(), collected));
+}
+
+/// 设置收集到的因子的缓存时间 (毫秒)，为 0 时关闭缓存并清空已有结果
+pub fn set_cache_ttl_ms(ms: u32) {
+    CACHE_TTL_MS.store(ms, Ordering::Relaxed);
+    if ms == 0 {
+        clear_cache();
+    }
+}
+
+/// 清空缓存的因子，下一次调用重新查询，用于更换硬件之后
+pub fn clear_cache() {
+    cache_entries().clear();
+}
+
 #[cfg(target_os = "windows")]
 pub mod windows {
+    use super::{
+        CollectedFactors, DEFAULT_WMI_TIMEOUT_MS, Factors, MachineIdError, MachineIdFactor,
+        MachineIdOptions, count_unique, is_internal_fixed_disk, select_baseboard,
+    };
+    use crate::wmi_executor::{self, ExecutorError};
     use serde::Deserialize;
-    use sha2::{Digest, Sha256};
-    use std::collections::BTreeSet;
-    use std::sync::mpsc::{Receiver, RecvError, SendError, Sender, channel};
-    use std::thread;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    /// 允许通过 `dump_wmi_class` 导出的 WMI 类，仅限 Machine ID 会查询的类，避免被用于任意查询
+    const DUMPABLE_WMI_CLASSES: &[&str] = &[
+        "Win32_BaseBoard",
+        "Win32_Processor",
+        "Win32_DiskDrive",
+        "Win32_DiskPartition",
+        "Win32_VideoController",
+        "Win32_SystemEnclosure",
+        "Win32_BIOS",
+        "Win32_NetworkAdapter",
+        "Win32_IP4RouteTable",
+        "Win32_PhysicalMemory",
+        "Win32_ComputerSystemProduct",
+    ];
 
     #[derive(Debug, Deserialize)]
     #[serde(rename = "Win32_BaseBoard")]
@@ -30,6 +764,8 @@ pub mod windows {
         serial_number: Option<String>,
         model: Option<String>,
         index: u32,
+        media_type: Option<String>,
+        interface_type: Option<String>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -49,66 +785,146 @@ pub mod windows {
         pnp_device_id: Option<String>,
     }
 
-    #[derive(Debug)]
+    #[derive(Debug, Deserialize)]
+    #[serde(rename = "Win32_SystemEnclosure")]
+    #[serde(rename_all = "PascalCase")]
+    struct SystemEnclosure {
+        chassis_types: Option<Vec<u16>>,
+        serial_number: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename = "Win32_BIOS")]
+    #[serde(rename_all = "PascalCase")]
+    struct Bios {
+        #[serde(rename = "SMBIOSBIOSVersion")]
+        smbios_bios_version: Option<String>,
+        manufacturer: Option<String>,
+        release_date: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename = "Win32_NetworkAdapter")]
+    #[serde(rename_all = "PascalCase")]
+    struct NetworkAdapter {
+        #[serde(rename = "MACAddress")]
+        mac_address: Option<String>,
+        manufacturer: Option<String>,
+        interface_index: Option<u32>,
+        #[serde(rename = "PNPDeviceID")]
+        pnp_device_id: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename = "Win32_IP4RouteTable")]
+    #[serde(rename_all = "PascalCase")]
+    struct IP4Route {
+        interface_index: i32,
+        metric1: i32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename = "Win32_PhysicalMemory")]
+    #[serde(rename_all = "PascalCase")]
+    struct PhysicalMemory {
+        serial_number: Option<String>,
+        part_number: Option<String>,
+        capacity: Option<u64>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename = "Win32_ComputerSystemProduct")]
+    struct ComputerSystemProduct {
+        #[serde(rename = "UUID")]
+        uuid: Option<String>,
+    }
+
+    /// 查询需要管理员权限，`SpecVersion` 形如 `2.0, 0, 1.38`
+    #[derive(Debug, Deserialize)]
+    #[serde(rename = "Win32_Tpm")]
+    #[serde(rename_all = "PascalCase")]
+    struct Tpm {
+        manufacturer_id: Option<u32>,
+        manufacturer_id_txt: Option<String>,
+        spec_version: Option<String>,
+    }
+
+    /// EDID 中的字符串以 UTF-16 码元数组返回，末尾用 0 填充
+    #[derive(Debug, Deserialize)]
+    #[serde(rename = "WmiMonitorID")]
+    struct MonitorId {
+        #[serde(rename = "ManufacturerName")]
+        manufacturer_name: Option<Vec<u16>>,
+        #[serde(rename = "ProductCodeID")]
+        product_code_id: Option<Vec<u16>>,
+        #[serde(rename = "SerialNumberID")]
+        serial_number_id: Option<Vec<u16>>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     enum WMIQueryRequest {
         GetBaseboard,
         GetProcessor,
         GetDisksDerives,
         GetDiskPartitions,
         GetVideoControllers,
-        Shutdown,
+        GetSystemEnclosure,
+        GetBios,
+        GetNetworkAdapters,
+        GetDefaultRoutes,
+        GetPhysicalMemory,
+        GetMonitorIds,
+        GetComputerSystemProduct,
+        GetTpm,
+        DumpClass(&'static str),
     }
 
     #[derive(Debug)]
     enum WMIQueryResult {
-        Baseboard(Option<BaseBoard>),
+        Baseboard(Vec<BaseBoard>),
         Processor(Option<Processor>),
         DiskDrives(Vec<DiskDrive>),
         DiskPartitions(Vec<DiskPartition>),
         VideoControllers(Vec<VideoController>),
+        SystemEnclosure(Option<SystemEnclosure>),
+        Bios(Option<Bios>),
+        NetworkAdapters(Vec<NetworkAdapter>),
+        DefaultRoutes(Vec<IP4Route>),
+        PhysicalMemory(Vec<PhysicalMemory>),
+        MonitorIds(Vec<MonitorId>),
+        ComputerSystemProduct(Option<ComputerSystemProduct>),
+        Tpm(Option<Tpm>),
+        // wmi::Variant 可能包含 COM 指针，不能跨线程传递，因此在执行线程中序列化为 JSON
+        Json(String),
         Error(MachineIdError),
     }
 
-    #[derive(Debug)]
-    pub enum MachineIdError {
-        WMIInitialization(String),
-        ChannelSend(String),
-        ChannelRecv(String),
-        QueryError(String),
-        WorkerThreadPanicked(String),
-        NoFactorsFound,
-    }
-
-    impl std::fmt::Display for MachineIdError {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    impl WMIQueryRequest {
+        /// 位于专用命名空间的查询，其它查询使用 `ROOT\CIMV2` 或调用方指定的命名空间
+        fn dedicated_namespace(&self) -> Option<&'static str> {
             match self {
-                MachineIdError::WMIInitialization(s) => {
-                    write!(f, "WMI Initialization Error: {}", s)
-                }
-                MachineIdError::ChannelSend(s) => write!(f, "Channel Send Error: {}", s),
-                MachineIdError::ChannelRecv(s) => write!(f, "Channel Receive Error: {}", s),
-                MachineIdError::QueryError(s) => write!(f, "WMI Query Error: {}", s),
-                MachineIdError::WorkerThreadPanicked(s) => {
-                    write!(f, "Worker thread panicked: {}", s)
-                }
-                MachineIdError::NoFactorsFound => {
-                    write!(f, "Could not gather any hardware factors")
-                }
+                WMIQueryRequest::GetMonitorIds => Some(r"ROOT\WMI"),
+                WMIQueryRequest::GetTpm => Some(r"ROOT\CIMV2\Security\MicrosoftTpm"),
+                _ => None,
             }
         }
     }
-    impl std::error::Error for MachineIdError {}
 
-    // 转换 mpsc::SendError 为自定义错误
-    impl<T> From<SendError<T>> for MachineIdError {
-        fn from(err: SendError<T>) -> Self {
-            MachineIdError::ChannelSend(err.to_string())
-        }
-    }
-    // 转换 mpsc::RecvError 为自定义错误
-    impl From<RecvError> for MachineIdError {
-        fn from(err: RecvError) -> Self {
-            MachineIdError::ChannelRecv(err.to_string())
+    const WBEM_E_INVALID_NAMESPACE: u32 = 0x8004100E;
+
+    // 转换 WMI 执行线程的错误为自定义错误
+    impl From<ExecutorError> for MachineIdError {
+        fn from(err: ExecutorError) -> Self {
+            match err {
+                ExecutorError::Send(s) => MachineIdError::ChannelSend(s),
+                ExecutorError::Recv(s) => MachineIdError::ChannelRecv(s),
+                ExecutorError::Panicked(s) => MachineIdError::WorkerThreadPanicked(s),
+                ExecutorError::Timeout(timeout) => MachineIdError::QueryError(format!(
+                    "WMI query timed out after {}ms",
+                    timeout.as_millis()
+                )),
+                err @ ExecutorError::Exhausted(_) => MachineIdError::QueryError(err.to_string()),
+            }
         }
     }
     // 转换 WMIError (如果需要更具体的WMI错误类型)
@@ -118,159 +934,247 @@ pub mod windows {
         }
     }
 
-    // 辅助函数，清理和标准化字符串
-    fn sanitize_string(s: Option<String>) -> Option<String> {
-        s.map(|val| val.trim().to_lowercase()).filter(|val| {
-            !val.is_empty()
-                && !val.contains("to be filled by o.e.m.")
-                && !val.contains("default string")
-                && !val.contains("none")
-                && val != "00000000"
-                && val != "o.e.m."
-        })
+    /// 在共享的 WMI 线程中执行请求并等待结果，`wmi_namespace` 为空时使用 `ROOT\CIMV2`，
+    /// 连接该命名空间失败时返回 `WMIInitialization` 错误
+    ///
+    /// 专用命名空间连接失败只影响对应的因子，返回 `QueryError`；超过 `timeout` 没有返回时同样返回 `QueryError`，
+    /// 并丢弃阻塞的执行线程，后续的查询在新的线程中执行
+    fn query(
+        request: WMIQueryRequest,
+        wmi_namespace: Option<&str>,
+        timeout: Duration,
+    ) -> Result<WMIQueryResult, MachineIdError> {
+        Ok(submit(request, wmi_namespace)?.wait(timeout)?)
     }
 
-    fn wmi_worker_thread(
-        rx_request: Receiver<WMIQueryRequest>,
-        tx_response: Sender<WMIQueryResult>,
-    ) {
-        // 在新线程中初始化 COM
-        let com_lib_result = wmi::COMLibrary::new();
-        let wmi_con_result =
-            com_lib_result.and_then(|com_lib| wmi::WMIConnection::new(com_lib.into()));
-
-        let wmi_con = match wmi_con_result {
-            Ok(con) => con,
-            Err(e) => {
-                // 如果 COM/WMI 初始化失败，通知主线程并退出
-                match e {
-                    wmi::WMIError::HResultError { hres } => {
-                        let _ = tx_response.send(WMIQueryResult::Error(
-                            MachineIdError::WMIInitialization(format!(
-                                "WMI worker failed to initialize: {}({hres})",
-                                windows::core::HRESULT::from_nt(hres).message()
-                            )),
-                        ));
-                    }
-                    _ => {
-                        let _ = tx_response.send(WMIQueryResult::Error(
-                            MachineIdError::WMIInitialization(format!(
-                                "WMI worker failed to initialize: {}",
-                                e
-                            )),
-                        ));
-                    }
-                }
-                return;
+    /// 提交请求但不等待，结果通过 [`wmi_executor::Pending::wait`] 取回
+    fn submit(
+        request: WMIQueryRequest,
+        wmi_namespace: Option<&str>,
+    ) -> Result<wmi_executor::Pending<WMIQueryResult>, MachineIdError> {
+        let dedicated = request.dedicated_namespace();
+        let namespace = dedicated
+            .or(wmi_namespace)
+            .unwrap_or(wmi_executor::ROOT_CIMV2)
+            .to_string();
+        let job = move |context: &mut wmi_executor::WmiContext| match context.connection(&namespace)
+        {
+            Ok(wmi_con) => handle_request(wmi_con, request),
+            // 没有 TPM (或禁用了 TPM) 的机器上不存在该命名空间，视为没有 TPM
+            Err(wmi::WMIError::HResultError { hres })
+                if matches!(request, WMIQueryRequest::GetTpm)
+                    && hres as u32 == WBEM_E_INVALID_NAMESPACE =>
+            {
+                WMIQueryResult::Tpm(None)
+            }
+            Err(e) if dedicated.is_some() => WMIQueryResult::Error(MachineIdError::QueryError(
+                format!("Failed to connect to {}: {}", namespace, e),
+            )),
+            Err(wmi::WMIError::HResultError { hres }) => {
+                WMIQueryResult::Error(MachineIdError::WMIInitialization(format!(
+                    "WMI worker failed to initialize: {}({hres})",
+                    windows::core::HRESULT::from_nt(hres).message()
+                )))
             }
+            Err(e) => WMIQueryResult::Error(MachineIdError::WMIInitialization(format!(
+                "WMI worker failed to initialize: {}",
+                e
+            ))),
         };
+        Ok(wmi_executor::spawn(job)?)
+    }
 
-        for request in rx_request {
-            // 通道关闭时循环会自动结束
-            let result_to_send = match request {
-                WMIQueryRequest::GetBaseboard => match wmi_con.query::<BaseBoard>() {
-                    Ok(results) => WMIQueryResult::Baseboard(results.into_iter().next()),
-                    Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("Baseboard query failed: {}", e))),
-                },
-                WMIQueryRequest::GetProcessor => match wmi_con.query::<Processor>() {
-                    Ok(results) => WMIQueryResult::Processor(results.into_iter().next()),
-                    Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("Processor query failed: {}", e))),
-                },
-                WMIQueryRequest::GetDisksDerives => match wmi_con.raw_query::<DiskDrive>("SELECT SerialNumber, Model, Index, MediaType, InterfaceType FROM Win32_DiskDrive WHERE MediaType = 'Fixed hard disk media' AND InterfaceType != 'USB'") {
-                    Ok(results) => WMIQueryResult::DiskDrives(results),
-                    Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("DiskDrives query failed: {}", e))),
-                },
-                WMIQueryRequest::GetDiskPartitions => match wmi_con.raw_query::<DiskPartition>("SELECT BootPartition, DiskIndex FROM Win32_DiskPartition WHERE BootPartition = 'TRUE'") {
-                    Ok(results) => WMIQueryResult::DiskPartitions(results),
-                    Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("DiskPartitions query failed: {}", e))),
-                },
-                WMIQueryRequest::GetVideoControllers => match wmi_con.query::<VideoController>() {
-                    Ok(results) => WMIQueryResult::VideoControllers(results),
-                    Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("VideoControllers query failed: {}", e))),
+    /// 生成 `factor` 需要的 WMI 查询，同一个查询被多个因子使用时会出现多次
+    fn requests_for(factor: &MachineIdFactor) -> &'static [WMIQueryRequest] {
+        match factor {
+            MachineIdFactor::Baseboard => &[WMIQueryRequest::GetBaseboard],
+            MachineIdFactor::Processor => &[WMIQueryRequest::GetProcessor],
+            MachineIdFactor::DiskDrives => &[
+                WMIQueryRequest::GetDiskPartitions,
+                WMIQueryRequest::GetDisksDerives,
+            ],
+            MachineIdFactor::VideoControllers => &[WMIQueryRequest::GetVideoControllers],
+            MachineIdFactor::Chassis => &[WMIQueryRequest::GetSystemEnclosure],
+            MachineIdFactor::Bios => &[WMIQueryRequest::GetBios],
+            MachineIdFactor::PrimaryNetworkAdapter => &[
+                WMIQueryRequest::GetNetworkAdapters,
+                WMIQueryRequest::GetDefaultRoutes,
+            ],
+            MachineIdFactor::NetworkAdapter => &[WMIQueryRequest::GetNetworkAdapters],
+            MachineIdFactor::Memory => &[WMIQueryRequest::GetPhysicalMemory],
+            MachineIdFactor::Display => &[WMIQueryRequest::GetMonitorIds],
+            MachineIdFactor::SystemUuid => &[WMIQueryRequest::GetComputerSystemProduct],
+            MachineIdFactor::Tpm => &[WMIQueryRequest::GetTpm],
+            MachineIdFactor::MachineGuid
+            | MachineIdFactor::VolumeSerial
+            | MachineIdFactor::WindowsInstall => &[],
+        }
+    }
+
+    fn handle_request(wmi_con: &wmi::WMIConnection, request: WMIQueryRequest) -> WMIQueryResult {
+        match request {
+            WMIQueryRequest::GetBaseboard => match wmi_con.query::<BaseBoard>() {
+                Ok(results) => WMIQueryResult::Baseboard(results),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("Baseboard query failed: {}", e))),
+            },
+            WMIQueryRequest::GetProcessor => match wmi_con.query::<Processor>() {
+                Ok(results) => WMIQueryResult::Processor(results.into_iter().next()),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("Processor query failed: {}", e))),
+            },
+            WMIQueryRequest::GetDisksDerives => match wmi_con.raw_query::<DiskDrive>("SELECT SerialNumber, Model, Index, MediaType, InterfaceType FROM Win32_DiskDrive") {
+                Ok(results) => WMIQueryResult::DiskDrives(results),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("DiskDrives query failed: {}", e))),
+            },
+            WMIQueryRequest::GetDiskPartitions => match wmi_con.raw_query::<DiskPartition>("SELECT BootPartition, DiskIndex FROM Win32_DiskPartition WHERE BootPartition = 'TRUE'") {
+                Ok(results) => WMIQueryResult::DiskPartitions(results),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("DiskPartitions query failed: {}", e))),
+            },
+            WMIQueryRequest::GetVideoControllers => match wmi_con.query::<VideoController>() {
+                Ok(results) => WMIQueryResult::VideoControllers(results),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("VideoControllers query failed: {}", e))),
+            },
+            WMIQueryRequest::GetSystemEnclosure => match wmi_con.query::<SystemEnclosure>() {
+                Ok(results) => WMIQueryResult::SystemEnclosure(results.into_iter().next()),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("SystemEnclosure query failed: {}", e))),
+            },
+            WMIQueryRequest::GetBios => match wmi_con.query::<Bios>() {
+                Ok(results) => WMIQueryResult::Bios(results.into_iter().next()),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("BIOS query failed: {}", e))),
+            },
+            WMIQueryRequest::GetNetworkAdapters => match wmi_con.raw_query::<NetworkAdapter>("SELECT MACAddress, Manufacturer, InterfaceIndex, PNPDeviceID FROM Win32_NetworkAdapter WHERE PhysicalAdapter = TRUE AND MACAddress IS NOT NULL") {
+                Ok(results) => WMIQueryResult::NetworkAdapters(results),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("NetworkAdapters query failed: {}", e))),
+            },
+            WMIQueryRequest::GetDefaultRoutes => match wmi_con.raw_query::<IP4Route>("SELECT InterfaceIndex, Metric1 FROM Win32_IP4RouteTable WHERE Destination = '0.0.0.0'") {
+                Ok(results) => WMIQueryResult::DefaultRoutes(results),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("DefaultRoutes query failed: {}", e))),
+            },
+            WMIQueryRequest::GetPhysicalMemory => match wmi_con.query::<PhysicalMemory>() {
+                Ok(results) => WMIQueryResult::PhysicalMemory(results),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("PhysicalMemory query failed: {}", e))),
+            },
+            WMIQueryRequest::GetMonitorIds => match wmi_con.raw_query::<MonitorId>("SELECT ManufacturerName, ProductCodeID, SerialNumberID FROM WmiMonitorID WHERE Active = TRUE") {
+                Ok(results) => WMIQueryResult::MonitorIds(results),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("WmiMonitorID query failed: {}", e))),
+            },
+            WMIQueryRequest::GetComputerSystemProduct => match wmi_con.query::<ComputerSystemProduct>() {
+                Ok(results) => WMIQueryResult::ComputerSystemProduct(results.into_iter().next()),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("ComputerSystemProduct query failed: {}", e))),
+            },
+            WMIQueryRequest::GetTpm => match wmi_con.query::<Tpm>() {
+                Ok(results) => WMIQueryResult::Tpm(results.into_iter().next()),
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("Tpm query failed: {}", e))),
+            },
+            WMIQueryRequest::DumpClass(class) => match wmi_con.raw_query::<HashMap<String, wmi::Variant>>(format!("SELECT * FROM {}", class)) {
+                Ok(rows) => match serde_json::to_string_pretty(&rows) {
+                    Ok(json) => WMIQueryResult::Json(json),
+                    Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("Serialize failed: {}", e))),
                 },
-                WMIQueryRequest::Shutdown => {
-                    break; // 退出循环，线程结束
-                }
-            };
-            if tx_response.send(result_to_send).is_err() {
-                // eprintln!("WMI worker: Failed to send response to main thread (channel closed). Shutting down.");
-                break; // 主线程可能已经退出了，工作线程也应该退出
-            }
+                Err(e) => WMIQueryResult::Error(MachineIdError::QueryError(format!("{} query failed: {}", class, e))),
+            },
         }
     }
 
-    #[derive(PartialEq, Eq)]
-    pub enum MachineIdFactor {
-        Baseboard = 1,
-        Processor,
-        VideoControllers,
-        DiskDrives,
+    /// 将一个查询的结果交给 `handler`
+    ///
+    /// 超时或单个查询失败只影响对应的因子，记入 warnings 后继续，只有最终没有任何因子时才视为失败；
+    /// WMI 初始化失败时后续查询都无法进行，中止整个收集
+    fn take_result(
+        result: Result<WMIQueryResult, MachineIdError>,
+        factors: &mut Factors,
+        handler: impl FnOnce(WMIQueryResult, &mut Factors),
+    ) -> Result<(), MachineIdError> {
+        match result {
+            Ok(WMIQueryResult::Error(e @ MachineIdError::WMIInitialization(_)))
+            | Err(e @ MachineIdError::WMIInitialization(_)) => return Err(e),
+            Ok(WMIQueryResult::Error(e)) | Err(e) => factors.warn(e),
+            Ok(result) => handler(result, factors),
+        }
+        Ok(())
     }
 
-    /// 通过 WMI 查询主板生产商、产品和序列号生产 Machine ID
-    pub fn get_machine_id_with_factors(
+    /// 通过 WMI 查询主板生产商、产品和序列号等因子
+    pub(super) fn collect_factors(
         generation_factors: Vec<MachineIdFactor>,
-    ) -> Result<(String, BTreeSet<String>), MachineIdError> {
-        let (tx_request, rx_request) = channel::<WMIQueryRequest>();
-        let (tx_response, rx_response) = channel::<WMIQueryResult>();
+        options: &MachineIdOptions,
+    ) -> Result<CollectedFactors, MachineIdError> {
+        let generation_factors = options.effective_factors(generation_factors);
+        let mut factors = Factors::new(options);
+        factors.set_total(count_unique(&generation_factors));
+        let timeout =
+            Duration::from_millis(options.wmi_timeout_ms.unwrap_or(DEFAULT_WMI_TIMEOUT_MS) as u64);
 
-        let worker_handle = thread::spawn(move || {
-            wmi_worker_thread(rx_request, tx_response);
-        });
-        let mut factors = BTreeSet::new();
+        // 各个查询相互独立，提前全部提交，由多个执行线程同时执行，下面按顺序取回结果并组装因子；
+        // 设置了 min_factors 时仍然逐个查询，收集到足够的因子后跳过剩余 (可能很慢) 的查询
+        let mut prefetched = Vec::new();
+        if factors.min_factors.is_none() {
+            for (index, factor) in generation_factors.iter().enumerate() {
+                // 重复的因子只会查询一次
+                if generation_factors[..index].contains(factor) {
+                    continue;
+                }
+                for request in requests_for(factor) {
+                    // 提交失败时不预取，取结果时重新提交，失败的原因记入 warnings
+                    if let Ok(pending) = submit(*request, options.wmi_namespace.as_deref()) {
+                        prefetched.push((*request, pending));
+                    }
+                }
+            }
+        }
 
         macro_rules! query_wmi {
             ($req:expr, $handler:expr) => {
-                tx_request.send($req)?; // Propagates SendError as MachineIdError
-                match rx_response.recv()? {
-                    // Propagates RecvError as MachineIdError
-                    WMIQueryResult::Error(e) => return Err(e),
-                    result => $handler(result, &mut factors),
-                }
+                let result = match prefetched.iter().position(|(it, _)| *it == $req) {
+                    Some(index) => prefetched
+                        .swap_remove(index)
+                        .1
+                        .wait(timeout)
+                        .map_err(MachineIdError::from),
+                    None => query($req, options.wmi_namespace.as_deref(), timeout),
+                };
+                take_result(result, &mut factors, $handler)?;
             };
         }
 
-        if generation_factors.contains(&MachineIdFactor::Baseboard) {
-            query_wmi!(WMIQueryRequest::GetBaseboard, |result,
-                                                       factors: &mut BTreeSet<
-                String,
-            >| {
-                if let WMIQueryResult::Baseboard(Some(bios)) = result {
-                    if let Some(val) = sanitize_string(bios.manufacturer) {
-                        factors.insert(format!("bios_manufacturer:{}", val));
-                    }
-                    if let Some(val) = sanitize_string(bios.product) {
-                        factors.insert(format!("bios_model:{}", val));
-                    }
-                    if let Some(val) = sanitize_string(bios.serial_number) {
-                        factors.insert(format!("bios_serial:{}", val));
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::Baseboard) {
+            query_wmi!(
+                WMIQueryRequest::GetBaseboard,
+                |result, factors: &mut Factors| {
+                    if let WMIQueryResult::Baseboard(boards) = result {
+                        let boards = boards
+                            .into_iter()
+                            .map(|it| (it.manufacturer, it.product, it.serial_number))
+                            .collect();
+                        if let Some((manufacturer, product, serial_number)) =
+                            select_baseboard(boards)
+                        {
+                            factors.insert("bios_manufacturer", manufacturer);
+                            factors.insert("bios_model", product);
+                            factors.insert("bios_serial", serial_number);
+                        }
                     }
-                } else if let WMIQueryResult::Baseboard(None) = result {
-                    // Optionally log or handle case where BIOS info is empty but not an error
                 }
-            });
+            );
+            factors.step("baseboard", "querying Win32_BaseBoard");
         }
-        if generation_factors.contains(&MachineIdFactor::Processor) {
-            query_wmi!(WMIQueryRequest::GetProcessor, |result,
-                                                       factors: &mut BTreeSet<
-                String,
-            >| {
-                if let WMIQueryResult::Processor(Some(cpu)) = result {
-                    if let Some(val) = sanitize_string(cpu.name) {
-                        factors.insert(format!("cpu_name:{}", val));
-                    }
-                    if let Some(val) = sanitize_string(cpu.processor_id) {
-                        factors.insert(format!("cpu_id:{}", val));
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::Processor) {
+            query_wmi!(
+                WMIQueryRequest::GetProcessor,
+                |result, factors: &mut Factors| {
+                    if let WMIQueryResult::Processor(Some(cpu)) = result {
+                        factors.insert("cpu_name", cpu.name);
+                        factors.insert("cpu_id", cpu.processor_id);
                     }
                 }
-            });
+            );
+            factors.step("processor", "querying Win32_Processor");
         }
-        if generation_factors.contains(&MachineIdFactor::DiskDrives) {
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::DiskDrives) {
             let mut system_disk_index = None;
             // 先查询分区，再根据分区的索引查询磁盘，目标是获取系统盘的序列化
             query_wmi!(
                 WMIQueryRequest::GetDiskPartitions,
-                |result, _factors: &mut BTreeSet<String>| {
+                |result, _factors: &mut Factors| {
                     if let WMIQueryResult::DiskPartitions(partitions) = result {
                         system_disk_index = partitions.first().map(|it| it.disk_index)
                     }
@@ -279,96 +1183,1320 @@ pub mod windows {
             if let Some(disk_index) = system_disk_index {
                 query_wmi!(
                     WMIQueryRequest::GetDisksDerives,
-                    |result, factors: &mut BTreeSet<String>| {
+                    |result, factors: &mut Factors| {
                         if let WMIQueryResult::DiskDrives(disks) = result {
-                            let system_disk =
-                                disks.into_iter().find(|disk| disk.index == disk_index);
-                            if let Some(disk) = system_disk {
-                                if let Some(val) = sanitize_string(disk.model) {
-                                    factors.insert(format!("disk_model:{}", val));
-                                }
-                                if let Some(val) = sanitize_string(disk.serial_number) {
-                                    factors.insert(format!("disk_serial:{}", val));
-                                }
-                            }
+                            // WQL 中的 `InterfaceType != 'USB'` 会排除 InterfaceType 为 NULL 的 NVMe 磁盘，改为查询后过滤
+                            let system_disk = disks.into_iter().find(|disk| {
+                                disk.index == disk_index
+                                    && is_internal_fixed_disk(
+                                        disk.media_type.as_deref(),
+                                        disk.interface_type.as_deref(),
+                                    )
+                            });
+                            let (model, serial_number) = system_disk
+                                .map(|disk| (disk.model, disk.serial_number))
+                                .unwrap_or_default();
+                            factors.insert("disk_model", model);
+                            factors.insert("disk_serial", serial_number);
                         }
                     }
                 );
             }
+            factors.step("disk_drives", "querying Win32_DiskDrive");
         }
-        if generation_factors.contains(&MachineIdFactor::VideoControllers) {
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::VideoControllers) {
             query_wmi!(
                 WMIQueryRequest::GetVideoControllers,
-                |result, factors: &mut BTreeSet<String>| {
+                |result, factors: &mut Factors| {
                     if let WMIQueryResult::VideoControllers(gpus) = result {
                         for (i, vc) in gpus.into_iter().enumerate() {
                             let is_pci = vc
                                 .pnp_device_id
-                                .as_ref()
-                                .map(|it| it.starts_with(r"PCI\VEN_"))
-                                .unwrap_or(false);
+                                .as_deref()
+                                .is_some_and(crate::virtualization::is_pci_device);
                             if !is_pci {
                                 continue;
                             }
                             let mut gpu_factors = Vec::new();
-                            if let Some(val) = sanitize_string(vc.adapter_compatibility) {
-                                gpu_factors.push(format!("gpu{}_manufacturer:{}", i, val));
+                            let key = format!("gpu{}_manufacturer", i);
+                            if let Some(val) = factors.sanitize(&key, vc.adapter_compatibility) {
+                                gpu_factors.push(format!("{}:{}", key, val));
                             }
-                            if let Some(val) = sanitize_string(vc.name) {
-                                gpu_factors.push(format!("gpu{}_model:{}", i, val));
+                            let key = format!("gpu{}_model", i);
+                            if let Some(val) = factors.sanitize(&key, vc.name) {
+                                gpu_factors.push(format!("{}:{}", key, val));
                             }
-                            if let Some(val) = sanitize_string(vc.pnp_device_id) {
-                                gpu_factors.push(format!("gpu{}_pnp_id:{}", i, val));
+                            let key = format!("gpu{}_pnp_id", i);
+                            if let Some(val) = factors.sanitize(&key, vc.pnp_device_id) {
+                                gpu_factors.push(format!("{}:{}", key, val));
                             }
                             if !gpu_factors.is_empty() {
                                 gpu_factors.sort();
-                                factors.insert(gpu_factors.join(";"));
+                                factors.insert_combined(gpu_factors.join(";"));
                             }
                         }
                     }
                 }
             );
+            factors.step("video_controllers", "querying Win32_VideoController");
         }
 
-        if tx_request.send(WMIQueryRequest::Shutdown).is_err() {
-            // 工作线程可能已经因为发送错误而提前退出了，这里记录一下但通常不认为是主流程的错误
-            // eprintln!("Main thread: Failed to send Shutdown to worker, it might have already exited.");
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::Chassis) {
+            query_wmi!(
+                WMIQueryRequest::GetSystemEnclosure,
+                |result, factors: &mut Factors| {
+                    if let WMIQueryResult::SystemEnclosure(Some(enclosure)) = result {
+                        // ChassisTypes 是数组，通常只有一个值，如 3 (Desktop)、10 (Notebook)、23 (Rack Mount)
+                        let chassis_type = enclosure
+                            .chassis_types
+                            .and_then(|types| types.first().map(|it| it.to_string()));
+                        factors.insert("chassis_type", chassis_type);
+                        factors.insert("chassis_serial", enclosure.serial_number);
+                    }
+                }
+            );
+            factors.step("chassis", "querying Win32_SystemEnclosure");
+        }
+
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::Bios) {
+            query_wmi!(WMIQueryRequest::GetBios, |result, factors: &mut Factors| {
+                if let WMIQueryResult::Bios(Some(bios)) = result {
+                    factors.insert("bios_version", bios.smbios_bios_version);
+                    factors.insert("bios_vendor", bios.manufacturer);
+                    factors.insert("bios_release_date", bios.release_date);
+                }
+            });
+            factors.step("bios", "querying Win32_BIOS");
+        }
+
+        if !factors.satisfied()
+            && generation_factors.contains(&MachineIdFactor::PrimaryNetworkAdapter)
+        {
+            let mut adapters = Vec::new();
+            query_wmi!(
+                WMIQueryRequest::GetNetworkAdapters,
+                |result, _factors: &mut Factors| {
+                    if let WMIQueryResult::NetworkAdapters(results) = result {
+                        adapters = results
+                            .into_iter()
+                            .filter_map(|it| {
+                                Some((it.interface_index?, it.pnp_device_id?, it.mac_address?))
+                            })
+                            .collect();
+                    }
+                }
+            );
+            let mut default_routes = Vec::new();
+            // 路由表查询失败时退回到按 PNPDeviceID 选择
+            query_wmi!(
+                WMIQueryRequest::GetDefaultRoutes,
+                |result, _factors: &mut Factors| {
+                    if let WMIQueryResult::DefaultRoutes(routes) = result {
+                        default_routes = routes
+                            .into_iter()
+                            .map(|it| (it.interface_index as u32, it.metric1.max(0) as u32))
+                            .collect();
+                    }
+                }
+            );
+            factors.insert(
+                "primary_mac",
+                super::select_primary_mac(&adapters, &default_routes),
+            );
+            factors.step("primary_network_adapter", "querying Win32_NetworkAdapter");
+        }
+
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::NetworkAdapter) {
+            query_wmi!(
+                WMIQueryRequest::GetNetworkAdapters,
+                |result, factors: &mut Factors| {
+                    if let WMIQueryResult::NetworkAdapters(results) = result {
+                        let adapters = results
+                            .into_iter()
+                            .filter_map(|it| {
+                                Some((it.pnp_device_id?, it.manufacturer, it.mac_address?))
+                            })
+                            .collect::<Vec<_>>();
+                        for (i, mac) in super::select_physical_macs(&adapters)
+                            .into_iter()
+                            .enumerate()
+                        {
+                            factors.insert(&format!("nic{i}_mac"), Some(mac));
+                        }
+                    }
+                }
+            );
+            factors.step("network_adapter", "querying Win32_NetworkAdapter");
         }
 
-        match worker_handle.join() {
-            Ok(_) => (), // Worker thread joined successfully
-            Err(e) => {
-                // e is Box<dyn Any + Send + 'static>, convert to string for error
-                let panic_msg = if let Some(s) = e.downcast_ref::<String>() {
-                    s.clone()
-                } else if let Some(s) = e.downcast_ref::<&str>() {
-                    s.to_string()
-                } else {
-                    "Unknown panic in worker thread".to_string()
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::Memory) {
+            query_wmi!(
+                WMIQueryRequest::GetPhysicalMemory,
+                |result, factors: &mut Factors| {
+                    if let WMIQueryResult::PhysicalMemory(modules) = result {
+                        let mut entries = Vec::new();
+                        for module in modules {
+                            // 没有序列号的内存条 (常见于虚拟机和白牌内存) 不参与计算
+                            let Some(serial) =
+                                factors.sanitize("memory_serial", module.serial_number)
+                            else {
+                                continue;
+                            };
+                            let part_number = factors
+                                .sanitize("memory_part_number", module.part_number)
+                                .unwrap_or_default();
+                            let capacity = module.capacity.unwrap_or(0);
+                            entries.push(format!("{serial}/{part_number}/{capacity}"));
+                        }
+                        if !entries.is_empty() {
+                            entries.sort();
+                            factors.insert_combined(format!("memory:{}", entries.join(";")));
+                        }
+                    }
+                }
+            );
+            factors.step("memory", "querying Win32_PhysicalMemory");
+        }
+
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::Display) {
+            query_wmi!(
+                WMIQueryRequest::GetMonitorIds,
+                |result, factors: &mut Factors| {
+                    if let WMIQueryResult::MonitorIds(monitors) = result {
+                        let mut entries = Vec::new();
+                        for monitor in monitors {
+                            // 通用驱动或 KVM 切换器返回的 EDID 通常没有有效的序列号
+                            let Some(serial) = factors.sanitize(
+                                "display_serial",
+                                decode_edid_string(monitor.serial_number_id),
+                            ) else {
+                                continue;
+                            };
+                            let manufacturer = factors
+                                .sanitize(
+                                    "display_manufacturer",
+                                    decode_edid_string(monitor.manufacturer_name),
+                                )
+                                .unwrap_or_default();
+                            let product = factors
+                                .sanitize(
+                                    "display_product",
+                                    decode_edid_string(monitor.product_code_id),
+                                )
+                                .unwrap_or_default();
+                            entries.push(format!("{manufacturer}/{product}/{serial}"));
+                        }
+                        if !entries.is_empty() {
+                            entries.sort();
+                            factors.insert_combined(format!("display:{}", entries.join(";")));
+                        }
+                    }
+                }
+            );
+            factors.step("display", "querying WmiMonitorID");
+        }
+
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::MachineGuid) {
+            factors.insert("machine_guid", read_machine_guid());
+            factors.step("machine_guid", "reading MachineGuid");
+        }
+
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::VolumeSerial) {
+            factors.insert("volume_serial", read_volume_serial());
+            factors.step("volume_serial", "reading volume serial");
+        }
+
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::WindowsInstall) {
+            let (product_id, install_date) = read_windows_install();
+            factors.insert("win_product_id", product_id);
+            factors.insert("win_install_date", install_date);
+            factors.step("windows_install", "reading Windows installation");
+        }
+
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::SystemUuid) {
+            query_wmi!(
+                WMIQueryRequest::GetComputerSystemProduct,
+                |result, factors: &mut Factors| {
+                    if let WMIQueryResult::ComputerSystemProduct(Some(product)) = result {
+                        factors.insert(
+                            "system_uuid",
+                            product.uuid.filter(|it| super::is_valid_system_uuid(it)),
+                        );
+                    }
+                }
+            );
+            factors.step("system_uuid", "querying Win32_ComputerSystemProduct");
+        }
+
+        // WMI 不提供背书密钥 (EK)，这里只使用厂商和规范版本；固件版本会随 TPM 固件更新改变，不参与计算
+        if !factors.satisfied() && generation_factors.contains(&MachineIdFactor::Tpm) {
+            query_wmi!(WMIQueryRequest::GetTpm, |result, factors: &mut Factors| {
+                if let WMIQueryResult::Tpm(Some(tpm)) = result {
+                    let manufacturer = tpm
+                        .manufacturer_id_txt
+                        .filter(|it| !it.trim().is_empty())
+                        .or_else(|| {
+                            tpm.manufacturer_id
+                                .filter(|it| *it != 0)
+                                .map(|it| format!("{it:08X}"))
+                        });
+                    factors.insert("tpm_manufacturer", manufacturer);
+                    factors.insert(
+                        "tpm_version",
+                        tpm.spec_version
+                            .and_then(|it| it.split(',').next().map(|it| it.trim().to_string())),
+                    );
+                }
+            });
+            factors.step("tpm", "querying Win32_Tpm");
+        }
+
+        Ok(factors.finish())
+    }
+
+    /// 导出 WMI 类的原始数据 (`SELECT *`) 为 JSON，用于排查因子缺失是 WMI 没有返回数据还是解析有误
+    ///
+    /// 只允许 [`DUMPABLE_WMI_CLASSES`] 中的类，类名不区分大小写
+    pub fn dump_wmi_class(class: &str) -> Result<String, MachineIdError> {
+        let class = DUMPABLE_WMI_CLASSES
+            .iter()
+            .find(|it| it.eq_ignore_ascii_case(class))
+            .ok_or_else(|| {
+                MachineIdError::QueryError(format!("WMI class '{}' is not allowed", class))
+            })?;
+
+        let timeout = Duration::from_millis(DEFAULT_WMI_TIMEOUT_MS as u64);
+        match query(WMIQueryRequest::DumpClass(class), None, timeout)? {
+            WMIQueryResult::Json(json) => Ok(json),
+            WMIQueryResult::Error(e) => Err(e),
+            _ => Err(MachineIdError::QueryError(
+                "Unexpected WMI worker response".to_string(),
+            )),
+        }
+    }
+
+    /// 将 EDID 的 UTF-16 码元数组转换为字符串，忽略末尾填充的 0
+    fn decode_edid_string(chars: Option<Vec<u16>>) -> Option<String> {
+        let chars = chars?;
+        let end = chars.iter().position(|&it| it == 0).unwrap_or(chars.len());
+        Some(String::from_utf16_lossy(&chars[..end]))
+    }
+
+    /// 读取系统安装时生成的 MachineGuid，虚拟机缺少硬件序列号时可以作为补充
+    fn read_machine_guid() -> Option<String> {
+        use winreg::RegKey;
+        use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_READ, KEY_WOW64_64KEY};
+        RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey_with_flags(
+                r"SOFTWARE\Microsoft\Cryptography",
+                KEY_READ | KEY_WOW64_64KEY,
+            )
+            .and_then(|key| key.get_value::<String, _>("MachineGuid"))
+            .ok()
+    }
+
+    /// 读取系统安装的 (ProductId, InstallDate)，InstallDate 为安装时的 Unix 时间戳
+    ///
+    /// 批量授权的镜像可能共享同一个 ProductId，因此同时使用安装时间区分不同的安装
+    fn read_windows_install() -> (Option<String>, Option<String>) {
+        use winreg::RegKey;
+        use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_READ, KEY_WOW64_64KEY};
+        let Ok(key) = RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey_with_flags(
+            r"SOFTWARE\Microsoft\Windows NT\CurrentVersion",
+            KEY_READ | KEY_WOW64_64KEY,
+        ) else {
+            return (None, None);
+        };
+        (
+            key.get_value::<String, _>("ProductId").ok(),
+            key.get_value::<u32, _>("InstallDate")
+                .ok()
+                .map(|it| it.to_string()),
+        )
+    }
+
+    /// 读取 `%SystemDrive%\` 的卷序列号，不需要管理员权限
+    fn read_volume_serial() -> Option<String> {
+        use windows::Win32::Storage::FileSystem::GetVolumeInformationW;
+        use windows::core::HSTRING;
+        let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+        let root_path = HSTRING::from(format!("{}\\", system_drive.trim_end_matches('\\')));
+        let mut serial_number: u32 = 0;
+        unsafe {
+            GetVolumeInformationW(&root_path, None, Some(&mut serial_number), None, None, None)
+        }
+        .ok()?;
+        Some(format!("{:08x}", serial_number))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_timed_out_query_only_skips_its_factor() {
+            let options = MachineIdOptions::default();
+            let mut factors = Factors::new(&options);
+            let slow = wmi_executor::spawn(|_| {
+                std::thread::sleep(Duration::from_millis(500));
+                WMIQueryResult::Processor(None)
+            })
+            .unwrap();
+            let fast = wmi_executor::spawn(|_| {
+                WMIQueryResult::Bios(Some(Bios {
+                    smbios_bios_version: Some("1.0.0".to_string()),
+                    manufacturer: Some("Contoso".to_string()),
+                    release_date: None,
+                }))
+            })
+            .unwrap();
+            let timeout = Duration::from_millis(50);
+
+            let result = slow.wait(timeout).map_err(MachineIdError::from);
+            take_result(result, &mut factors, |_, factors: &mut Factors| {
+                factors.insert("cpu_name", Some("unreachable".to_string()))
+            })
+            .unwrap();
+            let result = fast.wait(timeout).map_err(MachineIdError::from);
+            take_result(result, &mut factors, |result, factors: &mut Factors| {
+                if let WMIQueryResult::Bios(Some(bios)) = result {
+                    factors.insert("bios_vendor", bios.manufacturer);
+                }
+            })
+            .unwrap();
+
+            let collected = factors.finish();
+            assert_eq!(
+                collected.values.into_iter().collect::<Vec<_>>(),
+                vec!["bios_vendor:Contoso".to_string()]
+            );
+            assert_eq!(collected.warnings.len(), 1);
+            assert!(collected.warnings[0].contains("timed out"));
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub mod macos {
+    use super::{CollectedFactors, Factors, MachineIdError, MachineIdFactor, MachineIdOptions};
+    use crate::macos::{boot_disk_bsd_name, disk_identity, platform_expert_string, sysctl_string};
+
+    /// 通过 IOKit 与 sysctl 收集平台 UUID、硬件序列号、CPU 型号和启动盘序列号
+    ///
+    /// 支持 `Baseboard`、`Processor`、`DiskDrives` 和 `SystemUuid` 因子，其它因子会记录在 `missing` 中
+    pub(super) fn collect_factors(
+        generation_factors: Vec<MachineIdFactor>,
+        options: &MachineIdOptions,
+    ) -> Result<CollectedFactors, MachineIdError> {
+        let generation_factors = options.effective_factors(generation_factors);
+        let mut factors = Factors::new(options);
+        factors.set_total(super::count_unique(&generation_factors));
+        let requested = |factors: &Factors, factor| {
+            !factors.satisfied() && generation_factors.contains(&factor)
+        };
+
+        if requested(&factors, MachineIdFactor::Baseboard) {
+            factors.insert("platform_uuid", platform_expert_string("IOPlatformUUID"));
+            factors.insert("serial", platform_expert_string("IOPlatformSerialNumber"));
+            factors.insert("model", sysctl_string("hw.model").ok());
+            factors.step("baseboard", "reading IOPlatformExpertDevice");
+        }
+
+        if requested(&factors, MachineIdFactor::Processor) {
+            factors.insert("cpu_name", sysctl_string("machdep.cpu.brand_string").ok());
+            factors.step("processor", "reading machdep.cpu.brand_string");
+        }
+
+        // APFS 的卷 UUID 会随系统更新 (签名系统卷的快照) 改变，这里只使用磁盘硬件的型号和序列号
+        if requested(&factors, MachineIdFactor::DiskDrives) {
+            let (model, serial_number) = boot_disk_bsd_name()
+                .map(|bsd_name| disk_identity(&bsd_name))
+                .unwrap_or_default();
+            factors.insert("disk_model", model);
+            factors.insert("disk_serial", serial_number);
+            factors.step("disk_drives", "reading boot disk from IOKit");
+        }
+
+        // 值与 Baseboard 的 `platform_uuid` 相同，使用与 Windows 一致的 key
+        if requested(&factors, MachineIdFactor::SystemUuid) {
+            factors.insert("system_uuid", platform_expert_string("IOPlatformUUID"));
+            factors.step("system_uuid", "reading IOPlatformUUID");
+        }
+
+        // 其它因子在 macOS 上没有对应的数据，逐个完成步骤，记录为缺失
+        for (factor, name) in [
+            (MachineIdFactor::VideoControllers, "video_controllers"),
+            (MachineIdFactor::MachineGuid, "machine_guid"),
+            (MachineIdFactor::Chassis, "chassis"),
+            (MachineIdFactor::Bios, "bios"),
+            (MachineIdFactor::VolumeSerial, "volume_serial"),
+            (
+                MachineIdFactor::PrimaryNetworkAdapter,
+                "primary_network_adapter",
+            ),
+            (MachineIdFactor::Memory, "memory"),
+            (MachineIdFactor::Display, "display"),
+            (MachineIdFactor::WindowsInstall, "windows_install"),
+            (MachineIdFactor::Tpm, "tpm"),
+            (MachineIdFactor::NetworkAdapter, "network_adapter"),
+        ] {
+            if requested(&factors, factor) {
+                factors.step(name, "not available on macOS");
+            }
+        }
+
+        Ok(factors.finish())
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod linux {
+    use super::{CollectedFactors, Factors, MachineIdError, MachineIdFactor, MachineIdOptions};
+    use std::path::Path;
+
+    /// 按顺序尝试的路径，`/etc/machine-id` 在首次启动运行 `systemd-machine-id-setup` 之前可能为空
+    /// 或为 `uninitialized`，此时退回到 D-Bus 的 machine-id，即 `org.freedesktop.DBus.Peer.GetMachineId`
+    /// 返回的值，直接读取文件可以避免依赖正在运行的 D-Bus 守护进程
+    const MACHINE_ID_PATHS: &[&str] = &["/etc/machine-id", "/var/lib/dbus/machine-id"];
+
+    /// 通过 sysfs、procfs 收集因子，因子的 key 与 Windows 保持一致
+    ///
+    /// `/sys/class/dmi/id` 中的序列号 (`board_serial`、`chassis_serial`、`product_uuid`) 只有 root 可读，
+    /// 普通用户运行时会记录在 `missing` 中；`Memory` 与 `WindowsInstall` 在 Linux 上没有对应的数据
+    pub(super) fn collect_factors(
+        generation_factors: Vec<MachineIdFactor>,
+        options: &MachineIdOptions,
+    ) -> Result<CollectedFactors, MachineIdError> {
+        let generation_factors = options.effective_factors(generation_factors);
+        let mut factors = Factors::new(options);
+        factors.set_total(super::count_unique(&generation_factors));
+        let requested = |factors: &Factors, factor| {
+            !factors.satisfied() && generation_factors.contains(&factor)
+        };
+
+        if requested(&factors, MachineIdFactor::Baseboard) {
+            factors.insert("bios_manufacturer", read_dmi("board_vendor"));
+            factors.insert("bios_model", read_dmi("board_name"));
+            factors.insert("bios_serial", read_dmi("board_serial"));
+            factors.step("baseboard", "reading /sys/class/dmi/id/board_*");
+        }
+        if requested(&factors, MachineIdFactor::Processor) {
+            let cpu_name = std::fs::read_to_string("/proc/cpuinfo")
+                .ok()
+                .and_then(|cpuinfo| parse_cpuinfo_field(&cpuinfo, "model name"));
+            factors.insert("cpu_name", cpu_name);
+            factors.step("processor", "reading /proc/cpuinfo");
+        }
+        if requested(&factors, MachineIdFactor::DiskDrives) {
+            let root = root_block_device();
+            let (model, serial_number) = root
+                .as_ref()
+                .map(|(_, disk)| read_disk_identity(disk))
+                .unwrap_or_default();
+            factors.insert("disk_model", model);
+            factors.insert("disk_serial", serial_number);
+            factors.step("disk_drives", "reading /sys/class/block");
+        }
+        if requested(&factors, MachineIdFactor::VideoControllers) {
+            for (i, (slot, pci_id)) in read_pci_display_controllers().into_iter().enumerate() {
+                let mut gpu_factors = Vec::new();
+                let key = format!("gpu{}_pci_id", i);
+                if let Some(val) = factors.sanitize(&key, Some(pci_id)) {
+                    gpu_factors.push(format!("{}:{}", key, val));
+                }
+                let key = format!("gpu{}_slot", i);
+                if let Some(val) = factors.sanitize(&key, Some(slot)) {
+                    gpu_factors.push(format!("{}:{}", key, val));
+                }
+                if !gpu_factors.is_empty() {
+                    factors.insert_combined(gpu_factors.join(";"));
+                }
+            }
+            factors.step("video_controllers", "reading /sys/bus/pci/devices");
+        }
+        if requested(&factors, MachineIdFactor::Chassis) {
+            factors.insert("chassis_type", read_dmi("chassis_type"));
+            factors.insert("chassis_serial", read_dmi("chassis_serial"));
+            factors.step("chassis", "reading /sys/class/dmi/id/chassis_*");
+        }
+        if requested(&factors, MachineIdFactor::Bios) {
+            factors.insert("bios_version", read_dmi("bios_version"));
+            factors.insert("bios_vendor", read_dmi("bios_vendor"));
+            factors.insert("bios_release_date", read_dmi("bios_date"));
+            factors.step("bios", "reading /sys/class/dmi/id/bios_*");
+        }
+        if requested(&factors, MachineIdFactor::PrimaryNetworkAdapter) {
+            factors.insert("primary_mac", read_primary_mac());
+            factors.step("primary_network_adapter", "reading /sys/class/net");
+        }
+        if requested(&factors, MachineIdFactor::NetworkAdapter) {
+            for (i, mac) in read_physical_macs().into_iter().enumerate() {
+                factors.insert(&format!("nic{i}_mac"), Some(mac));
+            }
+            factors.step("network_adapter", "reading /sys/class/net");
+        }
+        if requested(&factors, MachineIdFactor::Memory) {
+            factors.step("memory", "memory modules are not available on Linux");
+        }
+        if requested(&factors, MachineIdFactor::Display) {
+            let mut entries = Vec::new();
+            for edid in read_edids() {
+                let Some((manufacturer, product, serial)) = parse_edid(&edid) else {
+                    continue;
+                };
+                let Some(serial) = factors.sanitize("display_serial", serial) else {
+                    continue;
                 };
-                return Err(MachineIdError::WorkerThreadPanicked(panic_msg));
+                entries.push(format!("{manufacturer}/{product}/{serial}").to_ascii_lowercase());
+            }
+            if !entries.is_empty() {
+                entries.sort();
+                entries.dedup();
+                factors.insert_combined(format!("display:{}", entries.join(";")));
+            }
+            factors.step("display", "reading /sys/class/drm/*/edid");
+        }
+        // 与 Windows 的 MachineGuid 相同，绑定到当前的系统安装
+        if requested(&factors, MachineIdFactor::MachineGuid) {
+            factors.insert("machine_id", read_machine_id().map(|(id, _)| id));
+            factors.step("machine_guid", "reading /etc/machine-id");
+        }
+        if requested(&factors, MachineIdFactor::VolumeSerial) {
+            let uuid = root_block_device().and_then(|(volume, _)| read_filesystem_uuid(&volume));
+            factors.insert("volume_serial", uuid);
+            factors.step("volume_serial", "reading /dev/disk/by-uuid");
+        }
+        if requested(&factors, MachineIdFactor::WindowsInstall) {
+            factors.step(
+                "windows_install",
+                "Windows installation is not available on Linux",
+            );
+        }
+        if requested(&factors, MachineIdFactor::SystemUuid) {
+            let uuid = read_dmi("product_uuid").filter(|it| super::is_valid_system_uuid(it));
+            factors.insert("system_uuid", uuid);
+            factors.step("system_uuid", "reading /sys/class/dmi/id/product_uuid");
+        }
+        if requested(&factors, MachineIdFactor::Tpm) {
+            factors.step("tpm", "TPM is not available on Linux");
+        }
+
+        Ok(factors.finish())
+    }
+
+    /// 读取 machine-id，返回 (machine-id, 来源路径)，所有路径都不可用时返回 `None`
+    pub fn read_machine_id() -> Option<(String, &'static str)> {
+        MACHINE_ID_PATHS.iter().find_map(|&path| {
+            let content = std::fs::read_to_string(path).ok()?;
+            parse_machine_id(&content).map(|id| (id, path))
+        })
+    }
+
+    /// machine-id 为 32 位十六进制字符串，其它内容 (空文件、`uninitialized`) 视为未初始化
+    pub fn parse_machine_id(content: &str) -> Option<String> {
+        let id = content.trim();
+        (id.len() == 32 && id.bytes().all(|it| it.is_ascii_hexdigit()))
+            .then(|| id.to_ascii_lowercase())
+    }
+
+    fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let content = content.trim();
+        (!content.is_empty()).then(|| content.to_string())
+    }
+
+    fn read_dmi(name: &str) -> Option<String> {
+        read_trimmed(format!("/sys/class/dmi/id/{name}"))
+    }
+
+    /// 返回 `/proc/cpuinfo` 第一个处理器中 `field` 的值
+    fn parse_cpuinfo_field(cpuinfo: &str, field: &str) -> Option<String> {
+        cpuinfo.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == field).then(|| value.trim().to_string())
+        })
+    }
+
+    /// 从 `/proc/self/mountinfo` 中找出根文件系统的挂载源，如 `/dev/nvme0n1p2`
+    pub fn parse_root_mount_source(mountinfo: &str) -> Option<String> {
+        mountinfo.lines().find_map(|line| {
+            let (mount, filesystem) = line.split_once(" - ")?;
+            let mount_point = mount.split_whitespace().nth(4)?;
+            let source = filesystem.split_whitespace().nth(1)?;
+            (mount_point == "/" && source.starts_with("/dev/")).then(|| source.to_string())
+        })
+    }
+
+    /// 返回根文件系统所在的 (块设备名, 物理磁盘名)，如 (`nvme0n1p2`, `nvme0n1`)
+    ///
+    /// LVM、LUKS 等 device-mapper 设备沿 `slaves` 找到底层分区；容器中的 overlay 根目录没有块设备，返回 `None`
+    fn root_block_device() -> Option<(String, String)> {
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").ok()?;
+        let source = std::fs::canonicalize(parse_root_mount_source(&mountinfo)?).ok()?;
+        let volume = source.file_name()?.to_str()?.to_string();
+        let mut device = volume.clone();
+        for _ in 0..4 {
+            let slaves = Path::new("/sys/class/block").join(&device).join("slaves");
+            let mut names = std::fs::read_dir(slaves)
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            names.sort();
+            match names.into_iter().next() {
+                Some(slave) => device = slave,
+                None => break,
             }
         }
+        let path = Path::new("/sys/class/block").join(&device);
+        let disk = if path.join("partition").exists() {
+            std::fs::canonicalize(&path)
+                .ok()?
+                .parent()?
+                .file_name()?
+                .to_str()?
+                .to_string()
+        } else {
+            device
+        };
+        Some((volume, disk))
+    }
+
+    /// 读取磁盘的 (型号, 序列号)
+    ///
+    /// NVMe 与 virtio 直接提供 `serial`，SATA/SAS 磁盘的序列号位于 SCSI VPD 0x80 页，前 4 字节为页头
+    fn read_disk_identity(disk: &str) -> (Option<String>, Option<String>) {
+        let base = Path::new("/sys/class/block").join(disk);
+        let model = read_trimmed(base.join("device/model"));
+        let serial = read_trimmed(base.join("device/serial"))
+            .or_else(|| read_trimmed(base.join("serial")))
+            .or_else(|| {
+                let page = std::fs::read(base.join("device/vpd_pg80")).ok()?;
+                let serial = String::from_utf8_lossy(page.get(4..)?).trim().to_string();
+                (!serial.is_empty()).then_some(serial)
+            });
+        (model, serial)
+    }
+
+    /// 在 `/dev/disk/by-uuid` 中查找指向 `volume` 的链接，即文件系统 UUID
+    fn read_filesystem_uuid(volume: &str) -> Option<String> {
+        let target = Path::new("/dev").join(volume);
+        let mut uuids = std::fs::read_dir("/dev/disk/by-uuid")
+            .ok()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                (std::fs::canonicalize(entry.path()).ok()? == target)
+                    .then(|| entry.file_name().into_string().ok())
+                    .flatten()
+            })
+            .collect::<Vec<_>>();
+        uuids.sort();
+        uuids.into_iter().next()
+    }
+
+    /// 枚举显示控制器 (PCI 类代码 0x03)，返回按插槽排序的 (插槽, `vendor:device:subsystem_vendor:subsystem_device`)
+    fn read_pci_display_controllers() -> Vec<(String, String)> {
+        let Ok(entries) = std::fs::read_dir("/sys/bus/pci/devices") else {
+            return Vec::new();
+        };
+        let mut devices = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if !read_trimmed(path.join("class"))?.starts_with("0x03") {
+                    return None;
+                }
+                let id = ["vendor", "device", "subsystem_vendor", "subsystem_device"]
+                    .iter()
+                    .map(|name| read_trimmed(path.join(name)))
+                    .collect::<Option<Vec<_>>>()?
+                    .iter()
+                    .map(|it| it.trim_start_matches("0x").to_string())
+                    .collect::<Vec<_>>()
+                    .join(":");
+                Some((path.file_name()?.to_str()?.to_string(), id))
+            })
+            .collect::<Vec<_>>();
+        devices.sort();
+        devices
+    }
+
+    /// 解析 `/proc/net/route`，返回默认路由的 (接口名, 跃点数)
+    pub fn parse_default_routes(route: &str) -> Vec<(String, u32)> {
+        route
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+                let (destination, metric, mask) = (fields.get(1)?, fields.get(6)?, fields.get(7)?);
+                (*destination == "00000000" && *mask == "00000000")
+                    .then(|| Some((fields[0].to_string(), metric.parse().ok()?)))
+                    .flatten()
+            })
+            .collect()
+    }
 
-        if factors.is_empty() {
-            return Err(MachineIdError::NoFactorsFound);
+    /// 与 Windows 相同，只考虑 PCI 网卡，优先选择跃点数最小的默认路由所在的网卡，否则选择接口名最小的网卡
+    fn read_primary_mac() -> Option<String> {
+        let mut adapters = std::fs::read_dir("/sys/class/net")
+            .ok()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                // 虚拟网卡 (lo、bridge、veth 等) 没有 device 链接，USB 网卡的路径中包含 usb
+                let device = std::fs::canonicalize(entry.path().join("device")).ok()?;
+                let device = device.to_str()?;
+                if !device.contains("/pci") || device.contains("/usb") {
+                    return None;
+                }
+                let mac = read_trimmed(entry.path().join("address"))?;
+                Some((entry.file_name().into_string().ok()?, mac))
+            })
+            .collect::<Vec<_>>();
+        adapters.sort();
+        let routes = std::fs::read_to_string("/proc/net/route")
+            .map(|route| parse_default_routes(&route))
+            .unwrap_or_default();
+        routes
+            .iter()
+            .filter_map(|(name, metric)| {
+                let adapter = adapters.iter().find(|(it, _)| it == name)?;
+                Some((*metric, adapter))
+            })
+            .min_by(|(a_metric, a), (b_metric, b)| a_metric.cmp(b_metric).then(a.0.cmp(&b.0)))
+            .map(|(_, adapter)| adapter)
+            .or_else(|| adapters.first())
+            .map(|(_, mac)| mac.clone())
+    }
+
+    /// 读取所有内置 (PCI) 物理网卡的 MAC 地址，按 PCI 设备路径排序，与接口名无关
+    fn read_physical_macs() -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir("/sys/class/net") else {
+            return Vec::new();
+        };
+        let mut adapters = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let device = std::fs::canonicalize(entry.path().join("device")).ok()?;
+                let path = device.to_str()?.to_string();
+                if !path.contains("/pci") || path.contains("/usb") {
+                    return None;
+                }
+                let vendor = read_trimmed(device.join("vendor"))?;
+                if super::is_virtual_nic(vendor.trim_start_matches("0x"), "") {
+                    return None;
+                }
+                Some((path, read_trimmed(entry.path().join("address"))?))
+            })
+            .collect::<Vec<_>>();
+        adapters.sort();
+        adapters.dedup_by(|a, b| a.1 == b.1);
+        adapters.into_iter().map(|(_, mac)| mac).collect()
+    }
+
+    fn read_edids() -> Vec<Vec<u8>> {
+        let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| std::fs::read(entry.ok()?.path().join("edid")).ok())
+            .filter(|edid| !edid.is_empty())
+            .collect()
+    }
+
+    /// 解析 EDID，返回 (厂商 PNP ID, 产品代码, 序列号)，与 `WmiMonitorID` 的字段对应
+    ///
+    /// 序列号优先使用 0xFF 显示描述符中的字符串，没有时使用基本块中的 32 位序列号
+    pub fn parse_edid(edid: &[u8]) -> Option<(String, String, Option<String>)> {
+        if edid.len() < 128 || edid[..8] != [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00] {
+            return None;
         }
-        // println!("factors:\n{factors:?}");
-        let combined_string = factors
+        let id = u16::from_be_bytes([edid[8], edid[9]]);
+        let manufacturer = [(id >> 10) & 0x1F, (id >> 5) & 0x1F, id & 0x1F]
+            .iter()
+            .map(|&it| (b'@' + it as u8) as char)
+            .collect::<String>();
+        let product = format!("{:04X}", u16::from_le_bytes([edid[10], edid[11]]));
+        let serial = [54, 72, 90, 108]
             .iter()
-            .map(|it| it.clone())
-            .collect::<Vec<String>>()
-            .join("|");
-        let mut hasher = Sha256::new();
-        hasher.update(combined_string);
-        let hash = hasher.finalize();
-        Ok((to_hex(&hash[..]), factors))
+            .find_map(|&offset| {
+                let descriptor = &edid[offset..offset + 18];
+                (descriptor[..3] == [0, 0, 0] && descriptor[3] == 0xFF).then(|| {
+                    String::from_utf8_lossy(&descriptor[5..])
+                        .split('\n')
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string()
+                })
+            })
+            .filter(|it| !it.is_empty())
+            .or_else(|| {
+                let serial = u32::from_le_bytes([edid[12], edid[13], edid[14], edid[15]]);
+                (serial != 0).then(|| serial.to_string())
+            });
+        Some((manufacturer, product, serial))
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_string_ascii_case_only() {
+        // 大写 I 必须映射为 i，而不是土耳其语中的无点 ı；İ 等非 ASCII 字符保持原样
+        assert_eq!(
+            sanitize_string(Some("  INTEL(R) CORE(TM) I7 ".to_string())),
+            Some("intel(r) core(tm) i7".to_string())
+        );
+        assert_eq!(
+            sanitize_string(Some("İSTANBUL".to_string())),
+            Some("İstanbul".to_string())
+        );
+        assert_eq!(
+            sanitize_string(Some("ıSTANBUL".to_string())),
+            Some("ıstanbul".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sanitize_string_nfc() {
+        let decomposed = sanitize_string(Some("Cafe\u{301} Board".to_string()));
+        let composed = sanitize_string(Some("Caf\u{e9} Board".to_string()));
+        assert_eq!(decomposed, composed);
+        assert_eq!(composed, Some("caf\u{e9} board".to_string()));
+    }
+
+    #[test]
+    fn test_hash_factors_hmac() {
+        // RFC 4231 测试用例 2
+        let factors = BTreeSet::from(["what do ya want for nothing?".to_string()]);
+        let options = MachineIdOptions {
+            hmac_key: Some(b"Jefe".to_vec()),
+            ..Default::default()
+        };
+        assert_eq!(
+            hash_factors(&factors, &options).unwrap(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_ne!(
+            hash_factors(&factors, &MachineIdOptions::default()).unwrap(),
+            hash_factors(&factors, &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_factors_algorithm() {
+        let factors = BTreeSet::from(["abc".to_string()]);
+        let hash = |hash_algorithm| {
+            hash_factors(
+                &factors,
+                &MachineIdOptions {
+                    hash_algorithm,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            hash(HashAlgorithm::Sha256),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hash(HashAlgorithm::Sha512),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+        assert_eq!(
+            hash(HashAlgorithm::Blake3),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+    }
+
+    #[test]
+    fn test_no_factors_found_message() {
+        assert_eq!(
+            MachineIdError::NoFactorsFound(vec![]).to_string(),
+            "Could not gather any hardware factors"
+        );
+        assert_eq!(
+            MachineIdError::NoFactorsFound(vec!["a".to_string(), "b".to_string()]).to_string(),
+            "Could not gather any hardware factors: a; b"
+        );
+    }
+
+    #[test]
+    fn test_hash_factors_pepper() {
+        let factors = BTreeSet::from(["cpu_id:bfebfbff000906ea".to_string()]);
+        let missing = MachineIdOptions {
+            pepper_env: Some("VIRT_DETECT_TEST_PEPPER_MISSING".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            hash_factors(&factors, &missing),
+            Err(MachineIdError::PepperUnavailable(_))
+        ));
+
+        let path = std::env::temp_dir().join("virt_detect_test_pepper");
+        std::fs::write(&path, "secret\n").unwrap();
+        let from_file = MachineIdOptions {
+            pepper_file: Some(path.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let peppered = hash_factors(&factors, &from_file).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_ne!(
+            peppered,
+            hash_factors(&factors, &MachineIdOptions::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_factors_salt() {
+        let factors = BTreeSet::from(["cpu_id:bfebfbff000906ea".to_string()]);
+        let salted = |salt: &str| {
+            hash_factors(
+                &factors,
+                &MachineIdOptions {
+                    salt: Some(salt.to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let unsalted = hash_factors(&factors, &MachineIdOptions::default()).unwrap();
+        assert_ne!(salted("app-a"), salted("app-b"));
+        assert_ne!(salted("app-a"), unsalted);
+        assert_eq!(salted(""), unsalted);
+    }
+
+    #[test]
+    fn test_factors_progress_and_min_factors() {
+        use std::sync::{Arc, Mutex};
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let options = MachineIdOptions {
+            on_progress: Some(Box::new(move |step, _, _, factors| {
+                sink.lock()
+                    .unwrap()
+                    .push((step.to_string(), factors.to_vec()));
+            })),
+            min_factors: Some(2),
+            ..Default::default()
+        };
+        let mut factors = Factors::new(&options);
+        factors.insert("cpu_name", Some("Intel".to_string()));
+        factors.step("processor", "cpu");
+        assert!(!factors.satisfied());
+        // 重复的因子不会再次发送
+        factors.insert("cpu_name", Some("Intel".to_string()));
+        factors.insert("bios_serial", Some("ABC123".to_string()));
+        factors.step("bios", "bios");
+        assert!(factors.satisfied());
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                ("cpu".to_string(), vec!["cpu_name:intel".to_string()]),
+                ("bios".to_string(), vec!["bios_serial:abc123".to_string()]),
+            ]
+        );
+        // 未取得值的 key 和没有产生任何值的因子都会记录为缺失
+        factors.insert("disk_serial", None);
+        factors.step("disk_drives", "disk");
+        factors.step("chassis", "chassis");
+        assert_eq!(factors.missing, vec!["disk_serial", "chassis"]);
+    }
+
+    #[test]
+    fn test_is_valid_system_uuid() {
+        assert!(is_valid_system_uuid("4C4C4544-0042-3510-8052-B4C04F384E32"));
+        assert!(!is_valid_system_uuid(
+            "00000000-0000-0000-0000-000000000000"
+        ));
+        assert!(!is_valid_system_uuid(
+            "FFFFFFFF-FFFF-FFFF-FFFF-FFFFFFFFFFFF"
+        ));
+        assert!(!is_valid_system_uuid(
+            "ffffffff-ffff-ffff-ffff-ffffffffffff"
+        ));
+        assert!(!is_valid_system_uuid("Not Settable"));
+    }
+
+    #[test]
+    fn test_select_baseboard() {
+        let board = |serial: Option<&str>| {
+            (
+                Some("Dell Inc.".to_string()),
+                Some("0K240Y".to_string()),
+                serial.map(str::to_string),
+            )
+        };
+        let boards = vec![
+            board(Some("CN7016BLADE02")),
+            board(None),
+            board(Some("CN7016BLADE01")),
+        ];
+        let mut reversed = boards.clone();
+        reversed.reverse();
+        // 与 WMI 的枚举顺序无关
+        assert_eq!(
+            select_baseboard(boards.clone()).and_then(|it| it.2),
+            Some("CN7016BLADE01".to_string())
+        );
+        assert_eq!(select_baseboard(boards), select_baseboard(reversed));
+        assert_eq!(
+            select_baseboard(vec![board(None), board(Some("CN7016BLADE02"))]).and_then(|it| it.2),
+            Some("CN7016BLADE02".to_string())
+        );
+        assert_eq!(select_baseboard(vec![]), None);
+    }
+
+    #[test]
+    fn test_is_internal_fixed_disk() {
+        // NVMe 磁盘常见的取值: InterfaceType 为 SCSI 或 NULL，MediaType 为 NULL 或 `Fixed hard disk`
+        assert!(is_internal_fixed_disk(
+            Some("Fixed hard disk media"),
+            Some("SCSI")
+        ));
+        assert!(is_internal_fixed_disk(None, None));
+        assert!(is_internal_fixed_disk(Some("Fixed hard disk"), None));
+        assert!(is_internal_fixed_disk(
+            Some("Fixed hard disk media"),
+            Some("IDE")
+        ));
+        assert!(!is_internal_fixed_disk(
+            Some("Fixed hard disk media"),
+            Some("USB")
+        ));
+        assert!(!is_internal_fixed_disk(
+            Some("External hard disk media"),
+            Some("SCSI")
+        ));
+        assert!(!is_internal_fixed_disk(
+            Some("Removable Media"),
+            Some("SCSI")
+        ));
+    }
+
+    #[test]
+    fn test_select_primary_mac() {
+        let adapters = vec![
+            (
+                12,
+                r"PCI\VEN_8086&DEV_15F3\4&2A3B".to_string(),
+                "AA:00:00:00:00:01".to_string(),
+            ),
+            (
+                7,
+                r"PCI\VEN_8086&DEV_2723\4&1C2D".to_string(),
+                "AA:00:00:00:00:02".to_string(),
+            ),
+            (
+                20,
+                r"USB\VID_0BDA&PID_8153\0001".to_string(),
+                "AA:00:00:00:00:03".to_string(),
+            ),
+        ];
+        // 默认路由在扩展坞的 USB 网卡上时忽略，退回到 PNPDeviceID 最小的 PCI 网卡
+        assert_eq!(
+            select_primary_mac(&adapters, &[(20, 5)]).as_deref(),
+            Some("AA:00:00:00:00:01")
+        );
+        assert_eq!(
+            select_primary_mac(&adapters, &[(7, 25), (12, 50)]).as_deref(),
+            Some("AA:00:00:00:00:02")
+        );
+        assert_eq!(select_primary_mac(&adapters[2..], &[]), None);
+    }
+
+    #[test]
+    fn test_select_physical_macs() {
+        let adapter = |pnp_device_id: &str, manufacturer: &str, mac: &str| {
+            (
+                pnp_device_id.to_string(),
+                Some(manufacturer.to_string()),
+                mac.to_string(),
+            )
+        };
+        let adapters = vec![
+            adapter(
+                r"PCI\VEN_8086&DEV_2723\4&1C2D",
+                "Intel Corporation",
+                "AA:00:00:00:00:02",
+            ),
+            adapter(r"ROOT\VMS_MP\0000", "Microsoft", "00:15:5D:00:00:01"),
+            adapter(
+                r"PCI\VEN_15AD&DEV_07B0\FF00",
+                "VMware, Inc.",
+                "00:50:56:00:00:01",
+            ),
+            adapter(r"PCI\VEN_10EC&DEV_8168\01", "Realtek", "AA:00:00:00:00:01"),
+            adapter(
+                r"USB\VID_0BDA&PID_8153\0001",
+                "Realtek",
+                "AA:00:00:00:00:03",
+            ),
+        ];
+        assert_eq!(
+            select_physical_macs(&adapters),
+            ["AA:00:00:00:00:01", "AA:00:00:00:00:02"]
+        );
+        assert!(is_virtual_nic("1af4", ""));
+        assert!(!is_virtual_nic("8086", "Intel Corporation"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_machine_id() {
+        assert_eq!(
+            linux::parse_machine_id("4C4C4544004A5A10803BB3C04F4E3532\n").as_deref(),
+            Some("4c4c4544004a5a10803bb3c04f4e3532")
+        );
+        assert_eq!(linux::parse_machine_id(""), None);
+        assert_eq!(linux::parse_machine_id("uninitialized\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_linux_sources() {
+        let mountinfo = "22 1 0:21 / /proc rw - proc proc rw\n\
+            28 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw\n";
+        assert_eq!(
+            linux::parse_root_mount_source(mountinfo).as_deref(),
+            Some("/dev/nvme0n1p2")
+        );
+        assert_eq!(
+            linux::parse_root_mount_source("1 0 0:1 / / rw - overlay overlay rw\n"),
+            None
+        );
+
+        let route = "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\n\
+            wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\n\
+            eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\n\
+            eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\n";
+        assert_eq!(
+            linux::parse_default_routes(route),
+            [("wlan0".to_string(), 600), ("eth0".to_string(), 100)]
+        );
 
-    fn to_hex(bytes: &[u8]) -> String {
-        bytes
+        let mut edid = vec![0u8; 128];
+        edid[..8].copy_from_slice(&[0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00]);
+        // DEL (Dell)，产品代码 0xA0B1
+        edid[8..12].copy_from_slice(&[0x10, 0xAC, 0xB1, 0xA0]);
+        edid[12..16].copy_from_slice(&42u32.to_le_bytes());
+        assert_eq!(
+            linux::parse_edid(&edid),
+            Some((
+                "DEL".to_string(),
+                "A0B1".to_string(),
+                Some("42".to_string())
+            ))
+        );
+        edid[72..77].copy_from_slice(&[0, 0, 0, 0xFF, 0]);
+        edid[77..90].copy_from_slice(b"CN0ABC123\n   ");
+        assert_eq!(
+            linux::parse_edid(&edid).and_then(|it| it.2).as_deref(),
+            Some("CN0ABC123")
+        );
+        assert_eq!(linux::parse_edid(&edid[..64]), None);
+    }
+
+    #[test]
+    fn test_diff_factors() {
+        let old = vec![
+            "cpu_id:bfebfbff000906ea".to_string(),
+            "disk_serial:s3z1nb0k".to_string(),
+            "disk_serial:wd-123".to_string(),
+        ];
+        let new = vec![
+            "disk_serial:wd-123".to_string(),
+            "disk_serial:s4x2".to_string(),
+            "cpu_id:bfebfbff000906ea".to_string(),
+        ];
+        let (added, removed) = diff_factors(&old, &new);
+        assert_eq!(added, vec!["disk_serial:s4x2".to_string()]);
+        assert_eq!(removed, vec!["disk_serial:s3z1nb0k".to_string()]);
+
+        let (added, removed) = diff_factors(&old, &old);
+        assert!(added.is_empty() && removed.is_empty());
+    }
+
+    #[test]
+    fn test_factor_from_str() {
+        assert_eq!(
+            "Baseboard".parse::<MachineIdFactor>(),
+            Ok(MachineIdFactor::Baseboard)
+        );
+        assert_eq!(
+            " volume_serial ".parse::<MachineIdFactor>(),
+            Ok(MachineIdFactor::VolumeSerial)
+        );
+        assert!("quantum_entangler".parse::<MachineIdFactor>().is_err());
+    }
+
+    #[test]
+    fn test_to_hex() {
+        let bytes = (0..=255u8).collect::<Vec<_>>();
+        let expected = bytes
             .iter()
             .map(|it| format!("{:02x}", it))
-            .collect::<String>()
+            .collect::<String>();
+        assert_eq!(to_hex(&bytes), expected);
+        assert_eq!(to_hex(&[]), "");
+    }
+
+    #[test]
+    fn test_sanitize_string_placeholders() {
+        assert_eq!(
+            sanitize_string(Some("To Be Filled By O.E.M.".to_string())),
+            None
+        );
+        assert_eq!(sanitize_string(Some("Default String".to_string())), None);
+        assert_eq!(sanitize_string(Some("00000000".to_string())), None);
+        assert_eq!(sanitize_string(Some("   ".to_string())), None);
+        assert_eq!(sanitize_string(None), None);
+    }
+
+    #[test]
+    fn test_cache_key_ignores_order_and_hash_options() {
+        let options = MachineIdOptions::default();
+        let key = CacheKey::new(
+            &[MachineIdFactor::Processor, MachineIdFactor::Baseboard],
+            &options,
+        );
+        assert!(
+            key == CacheKey::new(
+                &[
+                    MachineIdFactor::Baseboard,
+                    MachineIdFactor::Processor,
+                    MachineIdFactor::Baseboard
+                ],
+                &MachineIdOptions {
+                    salt: Some("app".to_string()),
+                    hash_algorithm: HashAlgorithm::Blake3,
+                    ..Default::default()
+                }
+            )
+        );
+        assert!(
+            key != CacheKey::new(
+                &[MachineIdFactor::Processor, MachineIdFactor::Baseboard],
+                &MachineIdOptions {
+                    include_raw: true,
+                    ..Default::default()
+                }
+            )
+        );
+        assert!(
+            key != CacheKey::new(
+                &[MachineIdFactor::Processor, MachineIdFactor::Baseboard],
+                &MachineIdOptions {
+                    wmi_timeout_ms: Some(500),
+                    ..Default::default()
+                }
+            )
+        );
     }
 }
@@ -0,0 +1,345 @@
+#![cfg(target_os = "macos")]
+use libc::{c_char, c_int, c_void, size_t, sysctlbyname};
+use std::ffi::CString;
+
+type CFTypeRef = *const c_void;
+type CFIndex = isize;
+type CFTypeID = usize;
+type IoObject = u32;
+
+const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+// kIOMainPortDefault / kIOMasterPortDefault 均为 MACH_PORT_NULL
+const K_IO_MAIN_PORT_DEFAULT: u32 = 0;
+// kIORegistryIterateRecursively | kIORegistryIterateParents
+const K_IO_REGISTRY_ITERATE_PARENTS: u32 = 0x1 | 0x2;
+
+#[link(name = "CoreFoundation", kind = "framework")]
+unsafe extern "C" {
+    fn CFStringCreateWithCString(
+        alloc: CFTypeRef,
+        c_str: *const c_char,
+        encoding: u32,
+    ) -> CFTypeRef;
+    fn CFStringGetCString(s: CFTypeRef, buffer: *mut c_char, size: CFIndex, encoding: u32) -> u8;
+    fn CFGetTypeID(cf: CFTypeRef) -> CFTypeID;
+    fn CFStringGetTypeID() -> CFTypeID;
+    fn CFDataGetTypeID() -> CFTypeID;
+    fn CFDataGetLength(data: CFTypeRef) -> CFIndex;
+    fn CFDataGetBytePtr(data: CFTypeRef) -> *const u8;
+    fn CFRelease(cf: CFTypeRef);
+    fn CFStringGetSystemEncoding() -> u32;
+    fn CFDictionaryGetTypeID() -> CFTypeID;
+    fn CFDictionaryGetValue(dict: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+    fn CFStringConvertEncodingToWindowsCodepage(encoding: u32) -> u32;
+}
+
+#[link(name = "IOKit", kind = "framework")]
+unsafe extern "C" {
+    fn IOServiceMatching(name: *const c_char) -> CFTypeRef;
+    fn IOServiceGetMatchingService(main_port: u32, matching: CFTypeRef) -> IoObject;
+    fn IORegistryEntryCreateCFProperty(
+        entry: IoObject,
+        key: CFTypeRef,
+        allocator: CFTypeRef,
+        options: u32,
+    ) -> CFTypeRef;
+    fn IOBSDNameMatching(main_port: u32, options: u32, bsd_name: *const c_char) -> CFTypeRef;
+    fn IORegistryEntrySearchCFProperty(
+        entry: IoObject,
+        plane: *const c_char,
+        key: CFTypeRef,
+        allocator: CFTypeRef,
+        options: u32,
+    ) -> CFTypeRef;
+    fn IOObjectRelease(object: IoObject) -> c_int;
+}
+
+/// 持有 CoreFoundation 对象，离开作用域时调用 `CFRelease`
+struct CfOwned(CFTypeRef);
+
+impl Drop for CfOwned {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { CFRelease(self.0) };
+        }
+    }
+}
+
+/// 持有 IOKit 对象，离开作用域时调用 `IOObjectRelease`
+struct IoOwned(IoObject);
+
+impl Drop for IoOwned {
+    fn drop(&mut self) {
+        if self.0 != 0 {
+            unsafe { IOObjectRelease(self.0) };
+        }
+    }
+}
+
+/// 系统文本编码 (`CFStringGetSystemEncoding`，受 `__CF_USER_TEXT_ENCODING` 影响) 对应的 Windows 代码页，
+/// 没有对应的代码页时返回 `None`
+#[cfg(feature = "encoding")]
+pub fn system_encoding_code_page() -> Option<u32> {
+    // kCFStringEncodingInvalidId
+    const INVALID_ID: u32 = 0xFFFF_FFFF;
+    let code_page =
+        unsafe { CFStringConvertEncodingToWindowsCodepage(CFStringGetSystemEncoding()) };
+    (code_page != INVALID_ID).then_some(code_page)
+}
+
+/// 读取整数类型的 sysctl 值，失败时返回 errno
+pub fn sysctl_int(name: &str) -> Result<c_int, c_int> {
+    let name_c = CString::new(name).map_err(|_| libc::EINVAL)?;
+    let mut value: c_int = 0;
+    let mut size: size_t = std::mem::size_of::<c_int>();
+    let ret = unsafe {
+        sysctlbyname(
+            name_c.as_ptr(),
+            &mut value as *mut _ as *mut c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret == 0 {
+        Ok(value)
+    } else {
+        Err(unsafe { *libc::__error() })
+    }
+}
+
+/// 读取 64 位整数类型的 sysctl 值 (如 `hw.memsize`)，失败时返回 errno
+pub fn sysctl_u64(name: &str) -> Result<u64, c_int> {
+    let name_c = CString::new(name).map_err(|_| libc::EINVAL)?;
+    let mut value: u64 = 0;
+    let mut size: size_t = std::mem::size_of::<u64>();
+    let ret = unsafe {
+        sysctlbyname(
+            name_c.as_ptr(),
+            &mut value as *mut _ as *mut c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret == 0 {
+        Ok(value)
+    } else {
+        Err(unsafe { *libc::__error() })
+    }
+}
+
+/// 通过 `host_statistics64` 获取可回收的内存字节数 (free + inactive + speculative)
+pub fn vm_available_bytes() -> Option<u64> {
+    let mut stats: libc::vm_statistics64 = unsafe { std::mem::zeroed() };
+    let mut count = libc::HOST_VM_INFO64_COUNT;
+    #[allow(deprecated)]
+    let host = unsafe { libc::mach_host_self() };
+    let ret = unsafe {
+        libc::host_statistics64(
+            host,
+            libc::HOST_VM_INFO64,
+            &mut stats as *mut _ as libc::host_info64_t,
+            &mut count,
+        )
+    };
+    if ret != libc::KERN_SUCCESS {
+        return None;
+    }
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if page_size <= 0 {
+        return None;
+    }
+    let pages =
+        stats.free_count as u64 + stats.inactive_count as u64 + stats.speculative_count as u64;
+    Some(pages * page_size as u64)
+}
+
+/// 读取字符串类型的 sysctl 值，失败时返回 errno
+pub fn sysctl_string(name: &str) -> Result<String, c_int> {
+    let name_c = CString::new(name).map_err(|_| libc::EINVAL)?;
+    let mut size: size_t = 0;
+    // 第一次调用只获取所需的缓冲区大小
+    let ret = unsafe {
+        sysctlbyname(
+            name_c.as_ptr(),
+            std::ptr::null_mut(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 {
+        return Err(unsafe { *libc::__error() });
+    }
+    let mut buffer = vec![0u8; size];
+    let ret = unsafe {
+        sysctlbyname(
+            name_c.as_ptr(),
+            buffer.as_mut_ptr() as *mut c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 {
+        return Err(unsafe { *libc::__error() });
+    }
+    buffer.truncate(size);
+    Ok(String::from_utf8_lossy(&buffer)
+        .trim_matches('\0')
+        .to_string())
+}
+
+fn cf_string(value: &str) -> Option<CfOwned> {
+    let value = CString::new(value).ok()?;
+    let cf_string = CfOwned(unsafe {
+        CFStringCreateWithCString(std::ptr::null(), value.as_ptr(), K_CF_STRING_ENCODING_UTF8)
+    });
+    (!cf_string.0.is_null()).then_some(cf_string)
+}
+
+/// 读取 CFString (按 UTF-8) 或 CFData 的字节，其它类型返回 `None`
+fn cf_value_bytes(value: CFTypeRef) -> Option<Vec<u8>> {
+    let type_id = unsafe { CFGetTypeID(value) };
+    if type_id == unsafe { CFStringGetTypeID() } {
+        let mut buffer = vec![0 as c_char; 512];
+        let ok = unsafe {
+            CFStringGetCString(
+                value,
+                buffer.as_mut_ptr(),
+                buffer.len() as CFIndex,
+                K_CF_STRING_ENCODING_UTF8,
+            )
+        };
+        if ok == 0 {
+            return None;
+        }
+        let value = unsafe { std::ffi::CStr::from_ptr(buffer.as_ptr()) };
+        Some(value.to_bytes().to_vec())
+    } else if type_id == unsafe { CFDataGetTypeID() } {
+        let len = unsafe { CFDataGetLength(value) };
+        let ptr = unsafe { CFDataGetBytePtr(value) };
+        if ptr.is_null() || len <= 0 {
+            return None;
+        }
+        Some(unsafe { std::slice::from_raw_parts(ptr, len as usize) }.to_vec())
+    } else {
+        None
+    }
+}
+
+/// 从 `IOPlatformExpertDevice` 读取属性的原始字节
+///
+/// CFString 属性按 UTF-8 返回，CFData 属性原样返回
+pub fn platform_expert_property(key: &str) -> Option<Vec<u8>> {
+    let service_name = CString::new("IOPlatformExpertDevice").ok()?;
+
+    // IOServiceGetMatchingService 会消耗 matching 字典的引用，无需手动释放
+    let matching = unsafe { IOServiceMatching(service_name.as_ptr()) };
+    if matching.is_null() {
+        return None;
+    }
+    let service = IoOwned(unsafe { IOServiceGetMatchingService(K_IO_MAIN_PORT_DEFAULT, matching) });
+    if service.0 == 0 {
+        return None;
+    }
+
+    let cf_key = cf_string(key)?;
+    let property = CfOwned(unsafe {
+        IORegistryEntryCreateCFProperty(service.0, cf_key.0, std::ptr::null(), 0)
+    });
+    if property.0.is_null() {
+        return None;
+    }
+    cf_value_bytes(property.0)
+}
+
+/// 根文件系统所在的整盘 BSD 名称，如 `/dev/disk3s1s1` 对应 `disk3`
+pub fn boot_disk_bsd_name() -> Option<String> {
+    let root = CString::new("/").ok()?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(root.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let source = unsafe { std::ffi::CStr::from_ptr(stat.f_mntfromname.as_ptr()) }
+        .to_str()
+        .ok()?;
+    whole_disk_name(source)
+}
+
+/// 去掉 `/dev/` 前缀和分区后缀 (`s1`、`s1s1`)，只保留整盘名称
+fn whole_disk_name(source: &str) -> Option<String> {
+    let name = source.strip_prefix("/dev/")?;
+    let digits = name.strip_prefix("disk")?;
+    let end = digits
+        .find(|it: char| !it.is_ascii_digit())
+        .unwrap_or(digits.len());
+    (end > 0).then(|| format!("disk{}", &digits[..end]))
+}
+
+/// 读取磁盘的 (型号, 序列号)
+///
+/// 从 BSD 名称对应的 `IOMedia` 沿父节点向上查找，APFS 容器会经过其物理存储 (如 `disk0s2`) 到达 NVMe/SATA 设备；
+/// NVMe 设备直接提供 `Serial Number`，SATA 设备位于 `Device Characteristics` 字典中
+pub fn disk_identity(bsd_name: &str) -> (Option<String>, Option<String>) {
+    let Ok(bsd_name) = CString::new(bsd_name) else {
+        return (None, None);
+    };
+    // IOServiceGetMatchingService 会消耗 matching 字典的引用，无需手动释放
+    let matching = unsafe { IOBSDNameMatching(K_IO_MAIN_PORT_DEFAULT, 0, bsd_name.as_ptr()) };
+    if matching.is_null() {
+        return (None, None);
+    }
+    let media = IoOwned(unsafe { IOServiceGetMatchingService(K_IO_MAIN_PORT_DEFAULT, matching) });
+    if media.0 == 0 {
+        return (None, None);
+    }
+    let search = |key: &str| -> Option<CfOwned> {
+        let plane = CString::new("IOService").ok()?;
+        let cf_key = cf_string(key)?;
+        let value = CfOwned(unsafe {
+            IORegistryEntrySearchCFProperty(
+                media.0,
+                plane.as_ptr(),
+                cf_key.0,
+                std::ptr::null(),
+                K_IO_REGISTRY_ITERATE_PARENTS,
+            )
+        });
+        (!value.0.is_null()).then_some(value)
+    };
+    let characteristic = |key: &str| -> Option<String> {
+        let characteristics = search("Device Characteristics")?;
+        if unsafe { CFGetTypeID(characteristics.0) } != unsafe { CFDictionaryGetTypeID() } {
+            return None;
+        }
+        let cf_key = cf_string(key)?;
+        // CFDictionaryGetValue 遵循 Get 规则，返回值不需要释放
+        let value = unsafe { CFDictionaryGetValue(characteristics.0, cf_key.0) };
+        if value.is_null() {
+            return None;
+        }
+        bytes_to_string(cf_value_bytes(value)?)
+    };
+    let property = |key: &str| bytes_to_string(cf_value_bytes(search(key)?.0)?);
+
+    let model = property("Model Number").or_else(|| characteristic("Product Name"));
+    let serial = property("Serial Number").or_else(|| characteristic("Serial Number"));
+    (model, serial)
+}
+
+fn bytes_to_string(bytes: Vec<u8>) -> Option<String> {
+    let value = bytes
+        .split(|it| *it == 0)
+        .filter(|it| !it.is_empty())
+        .map(|it| String::from_utf8_lossy(it).to_string())
+        .collect::<Vec<_>>()
+        .join(";");
+    if value.is_empty() { None } else { Some(value) }
+}
+
+/// 从 `IOPlatformExpertDevice` 读取属性并转换为字符串，CFData 中的 `\0` 分隔符会被替换为 `;`
+pub fn platform_expert_string(key: &str) -> Option<String> {
+    bytes_to_string(platform_expert_property(key)?)
+}
@@ -1,77 +1,792 @@
-#[cfg(all(target_arch = "x86_64", target_os = "windows"))]
+// 32 位 x86 与 x86_64 的 CPUID 接口相同，只是位于不同的模块中
+#[cfg(target_arch = "x86")]
+use std::arch::x86 as arch;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64 as arch;
+
+use crate::i18n::localize;
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_os = "windows"
+))]
 /// 通过 cpuid 检测是否处于 hyperv 环境下
 ///
 /// 如果处于 hyperv 那么 `check_virtual_support` 和 `is_virtualization_enabled_in_firmware_windows` 可能无法正常工作
 pub fn check_hyperv_environment_cpuid() -> (bool, bool, String) {
-    use std::arch::x86_64::__cpuid;
-    let cpuid_leaf_40000000 = unsafe { __cpuid(0x40000000) };
-    let mut hyperv_signature_bytes = Vec::new();
-    hyperv_signature_bytes.extend_from_slice(&cpuid_leaf_40000000.ebx.to_ne_bytes());
-    hyperv_signature_bytes.extend_from_slice(&cpuid_leaf_40000000.ecx.to_ne_bytes());
-    hyperv_signature_bytes.extend_from_slice(&cpuid_leaf_40000000.edx.to_ne_bytes());
-
-    let hyperv_signature = String::from_utf8_lossy(&hyperv_signature_bytes)
-        .trim_matches('\0')
-        .to_string();
+    let identity = cpu_identity();
+    let hyperv_signature = identity.hypervisor_signature.clone();
     let is_hyperv_present =
         hyperv_signature.starts_with("Microsoft Hv") || hyperv_signature.starts_with("MicrosoftXv");
 
-    let cpuid_leaf_1 = unsafe { __cpuid(0x1) };
-    let is_guest_vm = (cpuid_leaf_1.ecx & (1 << 31)) != 0;
+    (
+        is_hyperv_present,
+        identity.hypervisor_present,
+        hyperv_signature,
+    )
+}
 
-    (is_hyperv_present, is_guest_vm, hyperv_signature)
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_os = "windows"
+))]
+/// 在虚拟机中检测宿主是否为当前虚拟机开启了嵌套虚拟化，返回 (是否可用, 说明)
+///
+/// 宿主开启嵌套虚拟化后会向客户机暴露 VMX/SVM，此时 Hypervisor 位与 VMX/SVM 位同时置位；
+/// 开启了 Hyper-V 的宿主机 (根分区) 同样带有 Hypervisor 位，需要通过 CreatePartitions 权限排除
+pub fn check_nested_enabled_in_guest() -> (bool, String) {
+    let identity = cpu_identity();
+    if !identity.hypervisor_present {
+        return (
+            false,
+            localize(
+                "未检测到 Hypervisor，当前不是虚拟机",
+                "No hypervisor detected; this is not a virtual machine",
+            )
+            .to_string(),
+        );
+    }
+    if get_hyperv_partition_role() == "root" {
+        return (
+            false,
+            localize(
+                "当前为 Hyper-V 根分区 (宿主机)，不是虚拟机",
+                "This is the Hyper-V root partition (host), not a virtual machine",
+            )
+            .to_string(),
+        );
+    }
+    let (exposed, _, feature_name) = check_virtual_support();
+    let signature = &identity.hypervisor_signature;
+    if exposed {
+        (
+            true,
+            localize(
+                format!("宿主已向当前虚拟机 ({signature}) 暴露 {feature_name}，可以使用嵌套虚拟化"),
+                format!(
+                    "The host exposes {feature_name} to this virtual machine ({signature}); nested virtualization is available"
+                ),
+            ),
+        )
+    } else {
+        (
+            false,
+            localize(
+                format!("宿主未向当前虚拟机 ({signature}) 暴露 {feature_name}，嵌套虚拟化不可用"),
+                format!(
+                    "The host does not expose {feature_name} to this virtual machine ({signature}); nested virtualization is unavailable"
+                ),
+            ),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuVendor {
+    Intel,
+    Amd,
+    Arm,
+    Other,
+}
+
+impl CpuVendor {
+    /// 根据 cpuid 叶 0 返回的厂商字符串分类，aarch64 上始终为 `Arm`
+    pub fn from_vendor_id(vendor_id: &str) -> Self {
+        if cfg!(target_arch = "aarch64") {
+            CpuVendor::Arm
+        } else if vendor_id.contains("GenuineIntel") {
+            CpuVendor::Intel
+        } else if vendor_id.contains("AuthenticAMD") {
+            CpuVendor::Amd
+        } else {
+            CpuVendor::Other
+        }
+    }
 }
 
 /// 检查是否支持虚拟化
 ///
 /// ！注意：该函数仅支持检测 CPU 是否支持虚拟化，但不支持检测 BIOS 是否启用了虚拟化
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 pub fn check_virtual_support() -> (bool, String, &'static str) {
-    use std::arch::x86_64::__cpuid_count;
-
     // 检查 Intel VT-x (VMX) 或 AMD-V (SVM)
-    // EAX=1: 处理器信息和功能位
-    // 首先，获取供应商 ID 以便进行针对性检查
-    let cpuid_vendor = unsafe { __cpuid_count(0, 0) };
-    // 将 ebx, edx, ecx 中的字符拼接起来
-    let vendor_id_bytes: [u8; 12] = [
-        (cpuid_vendor.ebx & 0xFF) as u8,
-        ((cpuid_vendor.ebx >> 8) & 0xFF) as u8,
-        ((cpuid_vendor.ebx >> 16) & 0xFF) as u8,
-        ((cpuid_vendor.ebx >> 24) & 0xFF) as u8,
-        (cpuid_vendor.edx & 0xFF) as u8,
-        ((cpuid_vendor.edx >> 8) & 0xFF) as u8,
-        ((cpuid_vendor.edx >> 16) & 0xFF) as u8,
-        ((cpuid_vendor.edx >> 24) & 0xFF) as u8,
-        (cpuid_vendor.ecx & 0xFF) as u8,
-        ((cpuid_vendor.ecx >> 8) & 0xFF) as u8,
-        ((cpuid_vendor.ecx >> 16) & 0xFF) as u8,
-        ((cpuid_vendor.ecx >> 24) & 0xFF) as u8,
-    ];
-    let vendor_id = String::from_utf8_lossy(&vendor_id_bytes);
-
-    if vendor_id.contains("GenuineIntel") {
-        // 检查 VMX (Intel VT-x)
-        // EAX=1, ECX 寄存器的第 5 位
-        let cpuid_features = unsafe { __cpuid_count(1, 0) };
-        let vmx_supported = (cpuid_features.ecx & (1 << 5)) != 0;
-        (vmx_supported, vendor_id.to_string(), "Intel VT-x (VMX)")
-    } else if vendor_id.contains("AuthenticAMD") {
-        // 检查 SVM (AMD-V)
-        // EAX=0x80000001, ECX 寄存器的第 2 位
-        let cpuid_ext_features = unsafe { __cpuid_count(0x80000001, 0) };
-        let svm_supported = (cpuid_ext_features.ecx & (1 << 2)) != 0;
-        (svm_supported, vendor_id.to_string(), "AMD-V (SVM)")
-    } else {
-        (false, vendor_id.to_string(), "Unknown")
+    let identity = cpu_identity();
+    let vendor_id = identity.vendor_id.clone();
+
+    match identity.vendor {
+        CpuVendor::Intel => {
+            // 检查 VMX (Intel VT-x)
+            // EAX=1, ECX 寄存器的第 5 位
+            let vmx_supported = (identity.features_ecx & (1 << 5)) != 0;
+            (vmx_supported, vendor_id, "Intel VT-x (VMX)")
+        }
+        CpuVendor::Amd => {
+            // 检查 SVM (AMD-V)
+            // EAX=0x80000001, ECX 寄存器的第 2 位
+            let svm_supported = (cpuid(0x80000001, 0).ecx & (1 << 2)) != 0;
+            (svm_supported, vendor_id, "AMD-V (SVM)")
+        }
+        _ => (false, vendor_id, "Unknown"),
     }
 }
 
-#[cfg(target_arch = "aarch64")]
+#[cfg(all(target_arch = "aarch64", target_os = "windows"))]
+/// Windows on ARM 无法在用户态读取 ID_AA64PFR0_EL1 判断是否支持 EL2，改为根据系统的报告推断：
+/// 固件已开放虚拟化扩展或 Hypervisor 正在运行时，处理器一定支持虚拟化
+pub fn check_virtual_support() -> (bool, String, &'static str) {
+    use windows::Win32::System::Threading::{IsProcessorFeaturePresent, PF_VIRT_FIRMWARE_ENABLED};
+    let supported = unsafe { IsProcessorFeaturePresent(PF_VIRT_FIRMWARE_ENABLED) }.as_bool()
+        || whv_hypervisor_present() == Some(true);
+    (
+        supported,
+        "ARM".to_string(),
+        "ARMv8 Virtualization Extensions (EL2)",
+    )
+}
+
+#[cfg(all(target_arch = "aarch64", not(target_os = "windows")))]
 pub fn check_virtual_support() -> (bool, String, &'static str) {
     (false, "N/A".to_string(), "Not supported")
 }
 
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+/// 检查在客户机中运行 Hypervisor 所需的特性，返回 (EPT, 无限制客户机, 嵌套分页, 说明)，无限制客户机无法确认时为 `None`
+///
+/// AMD 的 NPT 位于 CPUID 0x8000000A.EDX 第 0 位，SVM 本身即可运行实模式客户机，因此不区分无限制客户机；
+/// Intel 的 EPT 与无限制客户机位于 IA32_VMX_PROCBASED_CTLS2 MSR 中，用户态只能借助操作系统读取
+pub fn check_nested_virtualization_features() -> (bool, Option<bool>, bool, String) {
+    let (supported, _, feature_name) = check_virtual_support();
+    if !supported {
+        return (
+            false,
+            Some(false),
+            false,
+            localize(
+                format!("CPU 不支持或未暴露 {feature_name}"),
+                format!("The CPU does not support or expose {feature_name}"),
+            ),
+        );
+    }
+    match cpu_identity().vendor {
+        CpuVendor::Amd => {
+            let nested_paging = cpuid(0x8000000A, 0).edx & 1 != 0;
+            let details = if nested_paging {
+                localize(
+                    "CPUID 0x8000000A 报告支持 AMD RVI (NPT)",
+                    "CPUID 0x8000000A reports AMD RVI (NPT) support",
+                )
+            } else {
+                localize(
+                    "CPUID 0x8000000A 报告不支持 AMD RVI (NPT)",
+                    "CPUID 0x8000000A reports no AMD RVI (NPT) support",
+                )
+            };
+            (
+                false,
+                Some(nested_paging),
+                nested_paging,
+                details.to_string(),
+            )
+        }
+        CpuVendor::Intel => match read_vmx_secondary_controls() {
+            Some((ept, unrestricted_guest, source)) => (
+                ept,
+                unrestricted_guest,
+                ept,
+                {
+                    let ept = yes_no(ept);
+                    let unrestricted_guest =
+                        unrestricted_guest.map_or(localize("未知", "unknown"), yes_no);
+                    localize(
+                        format!("{source}: EPT {ept}，无限制客户机 {unrestricted_guest}"),
+                        format!("{source}: EPT {ept}, unrestricted guest {unrestricted_guest}"),
+                    )
+                },
+            ),
+            None => (
+                false,
+                None,
+                false,
+                localize(
+                    "无法读取 IA32_VMX_PROCBASED_CTLS2，EPT 与无限制客户机状态未知",
+                    "Cannot read IA32_VMX_PROCBASED_CTLS2; EPT and unrestricted guest support are unknown",
+                )
+                .to_string(),
+            ),
+        },
+        _ => (
+            false,
+            Some(false),
+            false,
+            localize("未知的 CPU 厂商", "Unknown CPU vendor").to_string(),
+        ),
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        localize("支持", "supported")
+    } else {
+        localize("不支持", "not supported")
+    }
+}
+
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_os = "linux"))]
+/// 返回 (EPT, 无限制客户机, 来源)
+///
+/// 5.8 及以上的内核在 `/proc/cpuinfo` 的 `vmx flags` 中列出 VMX 能力；
+/// 旧内核退回到 `/dev/cpu/0/msr`，需要 root 权限并加载 msr 模块
+fn read_vmx_secondary_controls() -> Option<(bool, Option<bool>, &'static str)> {
+    if let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo")
+        && cpuinfo.lines().any(|line| line.starts_with("vmx flags"))
+    {
+        return Some((
+            cpuinfo_has_flag(&cpuinfo, "vmx flags", "ept"),
+            Some(cpuinfo_has_flag(
+                &cpuinfo,
+                "vmx flags",
+                "unrestricted_guest",
+            )),
+            "/proc/cpuinfo",
+        ));
+    }
+    use std::os::unix::fs::FileExt;
+    const IA32_VMX_PROCBASED_CTLS2: u64 = 0x48B;
+    let mut value = [0u8; 8];
+    std::fs::File::open("/dev/cpu/0/msr")
+        .and_then(|file| file.read_exact_at(&mut value, IA32_VMX_PROCBASED_CTLS2))
+        .ok()?;
+    // 高 32 位为允许置 1 的控制位，EPT 为第 1 位，无限制客户机为第 7 位
+    let allowed = (u64::from_le_bytes(value) >> 32) as u32;
+    Some((
+        allowed & (1 << 1) != 0,
+        Some(allowed & (1 << 7) != 0),
+        "IA32_VMX_PROCBASED_CTLS2",
+    ))
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_os = "windows"
+))]
+/// 返回 (EPT, 无限制客户机, 来源)
+///
+/// Windows 不允许用户态读取 MSR，只能通过 SLAT 推断 EPT；无限制客户机无法确认，返回 `None`
+fn read_vmx_secondary_controls() -> Option<(bool, Option<bool>, &'static str)> {
+    let (slat, _) = check_slat_support_windows();
+    Some((slat, None, "SLAT"))
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    not(any(target_os = "windows", target_os = "linux"))
+))]
+fn read_vmx_secondary_controls() -> Option<(bool, Option<bool>, &'static str)> {
+    None
+}
+
+#[cfg(target_arch = "aarch64")]
+pub fn check_nested_virtualization_features() -> (bool, Option<bool>, bool, String) {
+    (false, Some(false), false, "Not supported".to_string())
+}
+
+/// 启发式判断的结论，锁定状态位于 IA32_FEATURE_CONTROL (Intel) / VM_CR (AMD) MSR 中，用户态无法读取，
+/// 因此只能根据 CPU 支持、操作系统报告未启用且没有 Hypervisor 推测
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    any(target_os = "windows", target_os = "linux")
+))]
+fn firmware_disabled_hint() -> &'static str {
+    localize(
+        "[启发式判断] CPU 支持虚拟化，但已在固件中禁用 — 请检查 BIOS/UEFI 设置，部分机型可能被厂商锁定。",
+        "[Heuristic] The CPU supports virtualization but it is disabled in firmware. Check the BIOS/UEFI settings; some models may be locked by the vendor.",
+    )
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    any(target_os = "windows", target_os = "linux")
+))]
+/// CPU 支持 VMX/SVM 且没有运行在 Hypervisor 中，此时操作系统报告未启用多半是固件关闭了虚拟化
+fn is_supported_without_hypervisor() -> bool {
+    !cpu_identity().hypervisor_present && check_virtual_support().0
+}
+
+#[cfg(any(target_os = "linux", test))]
+/// 判断 `/proc/cpuinfo` 第一个处理器的 `field` (如 `flags`、`vmx flags`) 中是否包含 `flag`
+///
+/// 5.6 及以上的内核在 IA32_FEATURE_CONTROL 已锁定且未开启 VMX 时会清除 `vmx` 标志，
+/// 因此 CPUID 支持而这里没有时，可以确认是固件禁用
+fn cpuinfo_has_flag(cpuinfo: &str, field: &str, flag: &str) -> bool {
+    cpuinfo
+        .lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == field).then_some(value)
+        })
+        .is_some_and(|flags| flags.split_whitespace().any(|it| it == flag))
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+/// 执行 cpuid 指令
+///
+/// 较新的工具链中 `__cpuid_count` 已经是安全函数，保留 unsafe 以兼容旧版本
+#[allow(unused_unsafe)]
+fn cpuid(leaf: u32, sub_leaf: u32) -> arch::CpuidResult {
+    unsafe { arch::__cpuid_count(leaf, sub_leaf) }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+/// CPUID 叶 0、1、0x40000000 的结果，所有探测共用同一份，保证厂商与 Hypervisor 签名一致
+pub struct CpuIdentity {
+    /// 叶 0 的最大基本叶
+    pub max_leaf: u32,
+    /// 叶 0 的厂商字符串，如 `GenuineIntel`
+    pub vendor_id: String,
+    pub vendor: CpuVendor,
+    /// 叶 1 的 EAX，包含 family/model/stepping
+    pub signature: u32,
+    /// 叶 1 的 ECX 功能位
+    pub features_ecx: u32,
+    /// 叶 1 ECX 第 31 位，运行在 Hypervisor 中
+    pub hypervisor_present: bool,
+    /// 0x40000000 的 EAX，Hypervisor 支持的最大叶
+    pub hypervisor_max_leaf: u32,
+    /// 0x40000000 的厂商签名，如 `Microsoft Hv`、`KVMKVMKVM`
+    ///
+    /// 物理机上该叶返回的是最高基本叶的数据，使用前需要检查 `hypervisor_present`
+    pub hypervisor_signature: String,
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+/// 读取并缓存 CPU 标识，CPUID 结果在进程生命周期内不会变化
+pub fn cpu_identity() -> &'static CpuIdentity {
+    static IDENTITY: std::sync::OnceLock<CpuIdentity> = std::sync::OnceLock::new();
+    IDENTITY.get_or_init(|| {
+        let leaf_0 = cpuid(0, 0);
+        let mut vendor_id = Vec::with_capacity(12);
+        vendor_id.extend_from_slice(&leaf_0.ebx.to_le_bytes());
+        vendor_id.extend_from_slice(&leaf_0.edx.to_le_bytes());
+        vendor_id.extend_from_slice(&leaf_0.ecx.to_le_bytes());
+        let vendor_id = String::from_utf8_lossy(&vendor_id).to_string();
+
+        let leaf_1 = cpuid(1, 0);
+        let hypervisor = cpuid(0x40000000, 0);
+        let mut signature = Vec::with_capacity(12);
+        signature.extend_from_slice(&hypervisor.ebx.to_le_bytes());
+        signature.extend_from_slice(&hypervisor.ecx.to_le_bytes());
+        signature.extend_from_slice(&hypervisor.edx.to_le_bytes());
+
+        CpuIdentity {
+            max_leaf: leaf_0.eax,
+            vendor: CpuVendor::from_vendor_id(&vendor_id),
+            vendor_id,
+            signature: leaf_1.eax,
+            features_ecx: leaf_1.ecx,
+            hypervisor_present: leaf_1.ecx & (1 << 31) != 0,
+            hypervisor_max_leaf: hypervisor.eax,
+            hypervisor_signature: String::from_utf8_lossy(&signature)
+                .trim_matches('\0')
+                .to_string(),
+        }
+    })
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+/// 通过 x2APIC 拓扑 (0x1F / 0xB) 获取每个物理核心的逻辑处理器数 (SMT)
+///
+/// 不支持这两个叶的旧款 AMD 处理器退回到 0x8000001E
+fn threads_per_core_cpuid() -> Option<u32> {
+    let max_leaf = cpu_identity().max_leaf;
+    let topology_leaf = if max_leaf >= 0x1F {
+        Some(0x1F)
+    } else if max_leaf >= 0xB {
+        Some(0xB)
+    } else {
+        None
+    };
+    if let Some(leaf) = topology_leaf {
+        for sub_leaf in 0..8 {
+            let level = cpuid(leaf, sub_leaf);
+            // ECX[15:8] 为层级类型，0 表示无效，1 表示 SMT 层
+            match (level.ecx >> 8) & 0xFF {
+                0 => break,
+                1 => {
+                    let threads = level.ebx & 0xFFFF;
+                    return (threads > 0).then_some(threads);
+                }
+                _ => continue,
+            }
+        }
+    }
+    if cpuid(0x80000000, 0).eax >= 0x8000001E {
+        // EBX[15:8] 为每个核心的线程数减一
+        return Some(((cpuid(0x8000001E, 0).ebx >> 8) & 0xFF) + 1);
+    }
+    None
+}
+
+/// 在 Hyper-V 客户机中读取 0x40000003 (功能) 和 0x40000004 (建议) 叶，
+/// 返回 (0x40000003.EAX, 0x40000003.EBX, 0x40000004.EAX, 0x40000004.EBX)
+///
+/// 物理机、非 Hyper-V 的 Hypervisor 或非 x86/x86_64 架构返回 `None`
+pub fn read_hyperv_guest_leaves() -> Option<(u32, u32, u32, u32)> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        // 物理机上 0x40000000 会返回最高基本叶的数据，需要先检查 Hypervisor 位
+        let identity = cpu_identity();
+        if !identity.hypervisor_present
+            || identity.hypervisor_signature != "Microsoft Hv"
+            || identity.hypervisor_max_leaf < 0x40000004
+        {
+            return None;
+        }
+        let features = cpuid(0x40000003, 0);
+        let recommendations = cpuid(0x40000004, 0);
+        Some((
+            features.eax,
+            features.ebx,
+            recommendations.eax,
+            recommendations.ebx,
+        ))
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        None
+    }
+}
+
+/// 读取 CPUID 0x40000000 的 Hypervisor 厂商签名 (去除末尾的 NUL)，未运行在 Hypervisor 中或非 x86/x86_64 架构时返回 `None`
+pub fn read_hypervisor_signature() -> Option<String> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        let identity = cpu_identity();
+        identity
+            .hypervisor_present
+            .then(|| identity.hypervisor_signature.clone())
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HypervisorVendor {
+    HyperV,
+    VMware,
+    VirtualBox,
+    Kvm,
+    Xen,
+    Parallels,
+    Qemu,
+    Unknown,
+    None,
+}
+
+impl HypervisorVendor {
+    /// 根据 0x40000000 的厂商签名分类，`None` 表示未运行在 Hypervisor 中
+    ///
+    /// KVM、VirtualBox 开启 Hyper-V 半虚拟化接口后同样返回 `Microsoft Hv`，此时会被识别为 `HyperV`
+    pub fn from_signature(signature: Option<&str>) -> Self {
+        match signature.map(str::trim) {
+            None => HypervisorVendor::None,
+            Some("Microsoft Hv" | "MicrosoftXv") => HypervisorVendor::HyperV,
+            Some("VMwareVMware") => HypervisorVendor::VMware,
+            Some("VBoxVBoxVBox") => HypervisorVendor::VirtualBox,
+            Some("KVMKVMKVM" | "Linux KVM Hv") => HypervisorVendor::Kvm,
+            Some("XenVMMXenVMM") => HypervisorVendor::Xen,
+            Some("prl hyperv" | "lrpepyh  vr") => HypervisorVendor::Parallels,
+            Some("TCGTCGTCGTCG") => HypervisorVendor::Qemu,
+            Some(_) => HypervisorVendor::Unknown,
+        }
+    }
+}
+
+/// 判断当前所在的分区，返回 `root` (Hyper-V 根分区，即宿主机)、`guest` (虚拟机) 或 `none` (物理机)
+///
+/// 0x40000003.EBX 为分区权限，只有根分区拥有 CreatePartitions (bit 0)；
+/// 其它 Hypervisor (KVM、VMware 等) 没有根分区的概念，只要 Hypervisor 位置位就视为 `guest`
+pub fn get_hyperv_partition_role() -> &'static str {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if !cpu_identity().hypervisor_present {
+            return "none";
+        }
+        match read_hyperv_guest_leaves() {
+            Some((_, privileges, _, _)) if privileges & 1 != 0 => "root",
+            _ => "guest",
+        }
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        "none"
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", test))]
+/// 解码 CPUID 叶 1 的 EAX，返回 (family, model, stepping)
+///
+/// 按照 Intel/AMD 手册的规则合并扩展 family 与扩展 model
+fn decode_cpu_signature(eax: u32) -> (u32, u32, u32) {
+    let stepping = eax & 0xF;
+    let base_model = (eax >> 4) & 0xF;
+    let base_family = (eax >> 8) & 0xF;
+    let extended_model = (eax >> 16) & 0xF;
+    let extended_family = (eax >> 20) & 0xFF;
+
+    let family = if base_family == 0xF {
+        base_family + extended_family
+    } else {
+        base_family
+    };
+    let model = if base_family == 0x6 || base_family == 0xF {
+        (extended_model << 4) + base_model
+    } else {
+        base_model
+    };
+    (family, model, stepping)
+}
+
+/// 获取 CPU 的 (family, model, stepping)，用于匹配勘误和已知有问题的处理器
+///
+/// 仅 x86/x86_64 可用，其它架构没有对应的概念，返回 (0, 0, 0)
+pub fn get_cpu_signature() -> (u32, u32, u32) {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        decode_cpu_signature(cpu_identity().signature)
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        (0, 0, 0)
+    }
+}
+
+/// 检测 TSC 是否为 invariant (频率恒定，不受 P/C-state 影响)，缺少时客户机容易出现时钟漂移
+///
+/// x86/x86_64 读取 CPUID 0x80000007 EDX 第 8 位，注意部分 Hypervisor 会屏蔽该位；
+/// aarch64 的通用定时器按架构规定频率恒定，始终返回 `Some(true)`；其它架构无法判断，返回 `None`
+pub fn has_invariant_tsc() -> Option<bool> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if cpuid(0x80000000, 0).eax < 0x80000007 {
+            return Some(false);
+        }
+        Some(cpuid(0x80000007, 0).edx & (1 << 8) != 0)
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        Some(true)
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        None
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+/// 推测 CPU 是否支持 APIC 虚拟化 (Intel APICv / AMD AVIC)
+///
+/// ！注意：这是启发式结果。真正的控制位位于 VMX/SVM 能力 MSR 中，用户态无法读取，
+/// 这里只能根据 family/model 对照已知支持的处理器代际进行推测，BIOS 或 Hypervisor 仍可能将其关闭
+pub fn check_apic_virtualization() -> (bool, String) {
+    // Intel 服务器平台，从 Ivy Bridge-EP 开始支持 APICv
+    const INTEL_APICV_MODELS: &[(u32, &str)] = &[
+        (0x3E, "Ivy Bridge-EP"),
+        (0x3F, "Haswell-EP"),
+        (0x4F, "Broadwell-EP"),
+        (0x56, "Broadwell-DE"),
+        (0x55, "Skylake-SP / Cascade Lake / Cooper Lake"),
+        (0x6A, "Ice Lake-SP"),
+        (0x6C, "Ice Lake-D"),
+        (0x8F, "Sapphire Rapids"),
+        (0xCF, "Emerald Rapids"),
+        (0xAD, "Granite Rapids"),
+        (0xAF, "Sierra Forest"),
+    ];
+
+    let (family, model, _) = get_cpu_signature();
+
+    match cpu_identity().vendor {
+        CpuVendor::Intel => match INTEL_APICV_MODELS
+            .iter()
+            .find(|(it, _)| family == 6 && *it == model)
+        {
+            Some((_, name)) => (
+                true,
+                localize(
+                    format!(
+                        "[启发式] {name} (family {family:#x}, model {model:#x}) 通常支持 APICv。"
+                    ),
+                    format!(
+                        "[Heuristic] {name} (family {family:#x}, model {model:#x}) usually supports APICv."
+                    ),
+                ),
+            ),
+            None => (
+                false,
+                localize(
+                    format!(
+                        "[启发式] family {family:#x}, model {model:#x} 不在已知支持 APICv 的服务器平台列表中，客户端处理器通常不支持。"
+                    ),
+                    format!(
+                        "[Heuristic] family {family:#x}, model {model:#x} is not a known APICv-capable server platform; client processors usually lack it."
+                    ),
+                ),
+            ),
+        },
+        CpuVendor::Amd if family >= 0x17 => (
+            true,
+            localize(
+                format!(
+                    "[启发式] Zen 架构 (family {family:#x}) 及更新的处理器支持 AVIC，但可能未在 BIOS 或 Hypervisor 中启用。"
+                ),
+                format!(
+                    "[Heuristic] Zen (family {family:#x}) and newer processors support AVIC, but it may be disabled in the BIOS or hypervisor."
+                ),
+            ),
+        ),
+        CpuVendor::Amd => (
+            false,
+            localize(
+                format!("[启发式] family {family:#x} 早于 Zen 架构，不支持 AVIC。"),
+                format!("[Heuristic] family {family:#x} predates Zen and does not support AVIC."),
+            ),
+        ),
+        _ => (
+            false,
+            localize(
+                "[启发式] 未知的 CPU 厂商，无法推测 APIC 虚拟化支持。",
+                "[Heuristic] Unknown CPU vendor; APIC virtualization support cannot be inferred.",
+            )
+            .to_string(),
+        ),
+    }
+}
+
+/// 获取 CPU 拓扑，返回 (物理核心数, 逻辑处理器数)
+///
+/// Windows 与 Linux 按核心逐个统计，混合架构 (如 Alder Lake 的 E 核没有 SMT) 上同样准确，且不受 cgroup 和
+/// 进程亲和性的限制；系统接口不可用时退回到 `available_parallelism`，再按 CPUID 的 SMT 线程数换算物理核心数
+pub fn get_cpu_topology() -> (u32, u32) {
+    #[cfg(target_os = "macos")]
+    if let (Ok(physical), Ok(logical)) = (
+        crate::macos::sysctl_int("hw.physicalcpu"),
+        crate::macos::sysctl_int("hw.logicalcpu"),
+    ) {
+        return (physical as u32, logical as u32);
+    }
+    #[cfg(target_os = "windows")]
+    if let Some(topology) = get_cpu_topology_windows() {
+        return topology;
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(topology) = get_cpu_topology_linux() {
+        return topology;
+    }
+
+    let logical = std::thread::available_parallelism()
+        .map(|it| it.get() as u32)
+        .unwrap_or(1);
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if let Some(threads) = threads_per_core_cpuid() {
+        return ((logical / threads).max(1), logical);
+    }
+    (logical, logical)
+}
+
+#[cfg(target_os = "windows")]
+/// 通过 `GetLogicalProcessorInformationEx` 枚举所有处理器组中的核心，每个核心的亲和性掩码中的位数即为其逻辑处理器数
+fn get_cpu_topology_windows() -> Option<(u32, u32)> {
+    use windows::Win32::System::SystemInformation::{
+        GetLogicalProcessorInformationEx, RelationProcessorCore,
+        SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX,
+    };
+
+    let mut length = 0u32;
+    // 第一次调用只获取所需的缓冲区大小，会返回 ERROR_INSUFFICIENT_BUFFER
+    let _ = unsafe { GetLogicalProcessorInformationEx(RelationProcessorCore, None, &mut length) };
+    if length == 0 {
+        return None;
+    }
+    // 以 u64 分配，保证结构体中 usize 字段的对齐
+    let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+    unsafe {
+        GetLogicalProcessorInformationEx(
+            RelationProcessorCore,
+            Some(buffer.as_mut_ptr().cast()),
+            &mut length,
+        )
+    }
+    .ok()?;
+
+    let bytes = buffer.as_ptr().cast::<u8>();
+    let (mut cores, mut logical) = (0u32, 0u32);
+    let mut offset = 0usize;
+    while offset < length as usize {
+        let info = unsafe {
+            &*bytes
+                .add(offset)
+                .cast::<SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX>()
+        };
+        if info.Size == 0 {
+            break;
+        }
+        if info.Relationship == RelationProcessorCore {
+            let processor = unsafe { &info.Anonymous.Processor };
+            // GroupMask 声明为长度 1 的数组，实际包含 GroupCount 个元素
+            let masks = unsafe {
+                std::slice::from_raw_parts(
+                    processor.GroupMask.as_ptr(),
+                    processor.GroupCount as usize,
+                )
+            };
+            cores += 1;
+            logical += masks.iter().map(|it| it.Mask.count_ones()).sum::<u32>();
+        }
+        offset += info.Size as usize;
+    }
+    (cores > 0).then_some((cores, logical.max(cores)))
+}
+
+#[cfg(target_os = "linux")]
+/// 读取每个在线 CPU 的 `topology/core_cpus_list` (旧内核为 `thread_siblings_list`)，同一核心的逻辑处理器列表相同
+fn get_cpu_topology_linux() -> Option<(u32, u32)> {
+    let mut siblings = vec![];
+    for entry in std::fs::read_dir("/sys/devices/system/cpu").ok()?.flatten() {
+        let name = entry.file_name();
+        let is_cpu = name
+            .to_str()
+            .and_then(|it| it.strip_prefix("cpu"))
+            .is_some_and(|it| !it.is_empty() && it.bytes().all(|b| b.is_ascii_digit()));
+        if !is_cpu {
+            continue;
+        }
+        // 离线的 CPU 没有 topology 目录，不计入
+        let topology = entry.path().join("topology");
+        if let Ok(list) = std::fs::read_to_string(topology.join("core_cpus_list"))
+            .or_else(|_| std::fs::read_to_string(topology.join("thread_siblings_list")))
+        {
+            siblings.push(list.trim().to_string());
+        }
+    }
+    count_cores(&siblings)
+}
+
+#[cfg(any(target_os = "linux", test))]
+/// 根据每个逻辑处理器所在核心的 CPU 列表统计 (物理核心数, 逻辑处理器数)
+fn count_cores(siblings: &[String]) -> Option<(u32, u32)> {
+    if siblings.is_empty() {
+        return None;
+    }
+    let cores = siblings
+        .iter()
+        .collect::<std::collections::BTreeSet<_>>()
+        .len();
+    Some((cores as u32, siblings.len() as u32))
+}
+
 #[cfg(target_os = "linux")]
 /// 检查 KVM 版本
 pub fn check_kvm_via_api_linux() -> (bool, String) {
@@ -81,7 +796,31 @@ pub fn check_kvm_via_api_linux() -> (bool, String) {
 
     const KVM_GET_API_VERSION: libc::c_ulong = 0xAE00;
     if !Path::new("/dev/kvm").exists() {
-        return (false, "/dev/kvm 设备文件不存在".to_string());
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if is_supported_without_hypervisor() {
+            let flag = match cpu_identity().vendor {
+                CpuVendor::Amd => "svm",
+                _ => "vmx",
+            };
+            let cleared_by_kernel = std::fs::read_to_string("/proc/cpuinfo")
+                .is_ok_and(|cpuinfo| !cpuinfo_has_flag(&cpuinfo, "flags", flag));
+            if cleared_by_kernel {
+                let hint = firmware_disabled_hint();
+                return (
+                    false,
+                    localize(
+                        format!("/dev/kvm 设备文件不存在，且内核已清除 {flag} 标志。{hint}"),
+                        format!(
+                            "/dev/kvm does not exist and the kernel has cleared the {flag} flag. {hint}"
+                        ),
+                    ),
+                );
+            }
+        }
+        return (
+            false,
+            localize("/dev/kvm 设备文件不存在", "/dev/kvm does not exist").to_string(),
+        );
     }
     match OpenOptions::new().read(true).write(true).open("/dev/kvm") {
         Ok(file) => {
@@ -90,25 +829,43 @@ pub fn check_kvm_via_api_linux() -> (bool, String) {
             match api_version {
                 12 => (
                     true,
-                    format!(
-                        "/dev/kvm 可访问且 API 版本为 {} (预期值)。KVM 已启用。",
-                        api_version
+                    localize(
+                        format!(
+                            "/dev/kvm 可访问且 API 版本为 {} (预期值)。KVM 已启用。",
+                            api_version
+                        ),
+                        format!(
+                            "/dev/kvm is accessible with API version {} (expected). KVM is enabled.",
+                            api_version
+                        ),
                     ),
                 ),
                 0.. => (
                     true,
-                    format!(
-                        "/dev/kvm 可访问，API 版本为 {}。KVM 可能已启用。",
-                        api_version
+                    localize(
+                        format!(
+                            "/dev/kvm 可访问，API 版本为 {}。KVM 可能已启用。",
+                            api_version
+                        ),
+                        format!(
+                            "/dev/kvm is accessible with API version {}. KVM is probably enabled.",
+                            api_version
+                        ),
                     ),
                 ),
                 _ => {
                     let err_no = unsafe { *libc::__errno_location() };
                     (
                         false,
-                        format!(
-                            "/dev/kvm 打开成功，但 ioctl(KVM_GET_API_VERSION) 失败。错误码: {}. KVM 可能未完全启用或权限不足。",
-                            err_no
+                        localize(
+                            format!(
+                                "/dev/kvm 打开成功，但 ioctl(KVM_GET_API_VERSION) 失败。错误码: {}. KVM 可能未完全启用或权限不足。",
+                                err_no
+                            ),
+                            format!(
+                                "/dev/kvm was opened but ioctl(KVM_GET_API_VERSION) failed with errno {}. KVM may not be fully enabled or permissions are insufficient.",
+                                err_no
+                            ),
                         ),
                     )
                 }
@@ -116,72 +873,775 @@ pub fn check_kvm_via_api_linux() -> (bool, String) {
         }
         Err(e) => (
             false,
-            format!(
-                "无法打开 /dev/kvm: {}. 确保有足够权限，且 kvm 内核模块 (kvm_intel 或 kvm_amd) 已加载。",
-                e
+            localize(
+                format!(
+                    "无法打开 /dev/kvm: {}. 确保有足够权限，且 kvm 内核模块 (kvm_intel 或 kvm_amd) 已加载。",
+                    e
+                ),
+                format!(
+                    "Cannot open /dev/kvm: {}. Make sure you have permission and the kvm module (kvm_intel or kvm_amd) is loaded.",
+                    e
+                ),
             ),
         ),
     }
 }
 
-#[cfg(target_os = "macos")]
-pub fn check_hypervisor_support_macos() -> (bool, String) {
-    use libc::{c_int, c_void, size_t, sysctlbyname};
-    use std::ffi::CString;
-    use std::mem;
+#[cfg(target_os = "linux")]
+/// 列出支持 SR-IOV 的网卡，即 `/sys/class/net/*/device/sriov_totalvfs` 大于 0 的网卡
+pub fn list_sriov_nics_linux() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/net") else {
+        return vec![];
+    };
+    let mut nics = entries
+        .flatten()
+        .filter(|entry| {
+            std::fs::read_to_string(entry.path().join("device/sriov_totalvfs"))
+                .ok()
+                .and_then(|it| it.trim().parse::<u32>().ok())
+                .is_some_and(|total_vfs| total_vfs > 0)
+        })
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    nics.sort();
+    nics
+}
+
+#[cfg(target_os = "linux")]
+/// 检测当前是否运行在容器中，返回容器类型，不在容器中时返回 `None`
+///
+/// 优先使用 `container` 环境变量或 systemd 写入的 `/run/systemd/container`，
+/// 其次是 LXD 的 `/dev/lxd/sock`、Docker 的 `/.dockerenv` 和 Podman 的 `/run/.containerenv`
+pub fn detect_container_linux() -> Option<&'static str> {
+    use std::path::Path;
+
+    let has_lxd_sock = Path::new("/dev/lxd/sock").exists();
+    let declared = std::env::var("container")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/run/systemd/container").ok());
+    if let Some(value) = declared
+        .as_deref()
+        .map(str::trim)
+        .filter(|it| !it.is_empty())
+    {
+        return Some(container_kind(value, has_lxd_sock));
+    }
+    if has_lxd_sock {
+        Some("lxd")
+    } else if Path::new("/.dockerenv").exists() {
+        Some("docker")
+    } else if Path::new("/run/.containerenv").exists() {
+        Some("podman")
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+/// 检测当前是否运行在 WSL 发行版中，返回 WSL 版本 (1 或 2)，不在 WSL 中时返回 `None`
+///
+/// 优先根据内核版本判断，读取不到时退回到 `/proc/version`；使用 `.wslconfig` 自定义的内核可能没有
+/// `microsoft` 标记，此时根据 WSL 写入的 `/run/WSL` 与 `WSL_INTEROP`、`WSL_DISTRO_NAME` 环境变量判断，
+/// 只有 WSL2 可以使用自定义内核
+pub fn detect_wsl_guest_linux() -> Option<u32> {
+    let release = std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .or_else(|_| std::fs::read_to_string("/proc/version"))
+        .unwrap_or_default();
+    wsl_version_from_release(&release).or_else(|| {
+        let has_wsl_env = ["WSL_INTEROP", "WSL_DISTRO_NAME"]
+            .iter()
+            .any(|name| std::env::var_os(name).is_some());
+        (has_wsl_env || std::path::Path::new("/run/WSL").is_dir()).then_some(2)
+    })
+}
+
+#[cfg(any(target_os = "linux", test))]
+/// 根据内核版本判断 WSL 版本
+///
+/// WSL1 没有真正的 Linux 内核，版本号由 Windows 模拟，如 `4.4.0-19041-Microsoft`；
+/// WSL2 的内核版本如 `5.15.90.1-microsoft-standard-WSL2`，早期版本没有 `-WSL2` 后缀
+fn wsl_version_from_release(release: &str) -> Option<u32> {
+    let lower = release.to_ascii_lowercase();
+    if lower.contains("wsl2") || lower.contains("microsoft-standard") {
+        Some(2)
+    } else if release.contains("Microsoft") {
+        Some(1)
+    } else if lower.contains("microsoft") {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+/// 检测 cgroup 层级版本，返回 2 (unified)、1 (legacy) 或 0 (未挂载)
+///
+/// hybrid 模式下 v1 控制器挂载在 `/sys/fs/cgroup/<controller>`，v2 挂载在 `/sys/fs/cgroup/unified`，
+/// 此时按 unified 层级返回 2
+pub fn get_cgroup_version_linux() -> u32 {
+    use std::path::Path;
+
+    let root = Path::new("/sys/fs/cgroup");
+    if root.join("cgroup.controllers").exists() || root.join("unified/cgroup.controllers").exists()
+    {
+        2
+    } else if root.join("memory").exists() || root.join("cpu").exists() {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(any(
+    all(
+        target_os = "windows",
+        any(feature = "features", feature = "machine-id")
+    ),
+    test
+))]
+/// 根据 PNPDeviceID 判断是否为 PCI 设备，远程桌面、Hyper-V 合成显卡等虚拟设备不在 PCI 总线上
+pub fn is_pci_device(pnp_device_id: &str) -> bool {
+    pnp_device_id
+        .get(..8)
+        .is_some_and(|it| it.eq_ignore_ascii_case(r"PCI\VEN_"))
+}
+
+#[cfg(any(all(target_os = "windows", feature = "features"), test))]
+/// 判断 `Win32_VideoController` 是否为虚拟显卡，此时无法使用硬件加速的图形功能
+///
+/// 不在 PCI 总线上的显卡 (远程桌面、Hyper-V 合成显卡) 均视为虚拟显卡；
+/// VMware、VirtualBox、QEMU 等模拟的显卡位于虚拟 PCI 总线上，需要根据厂商 ID 和名称识别
+pub fn is_virtual_gpu(name: &str, pnp_device_id: &str) -> bool {
+    // VMware、VirtualBox、QEMU 标准 VGA、Red Hat (QXL)、virtio-gpu
+    const VIRTUAL_VENDOR_IDS: &[&str] = &["15AD", "80EE", "1234", "1B36", "1AF4"];
+    const VIRTUAL_NAMES: &[&str] = &[
+        "microsoft remote display adapter",
+        "microsoft basic display adapter",
+        "hyper-v video",
+        "vmware svga",
+        "virtualbox graphics adapter",
+        "citrix indirect display",
+    ];
+    if !is_pci_device(pnp_device_id) {
+        return true;
+    }
+    let vendor_id = pnp_device_id.get(8..12).unwrap_or_default();
+    let name = name.to_ascii_lowercase();
+    VIRTUAL_VENDOR_IDS
+        .iter()
+        .any(|it| it.eq_ignore_ascii_case(vendor_id))
+        || VIRTUAL_NAMES.iter().any(|it| name.contains(it))
+}
 
-    let name_c = match CString::new("kern.hv_support") {
-        Ok(s) => s,
-        Err(_) => return (false, "无法创建 CString 用于 sysctlbyname。".to_string()),
+#[cfg(target_os = "linux")]
+/// 读取 `/proc/meminfo`，返回 (物理内存总量, 可用内存) MB
+pub fn get_memory_info_linux() -> Option<(u64, u64)> {
+    let content = std::fs::read_to_string("/proc/meminfo").ok()?;
+    parse_meminfo(&content)
+}
+
+#[cfg(any(target_os = "linux", test))]
+/// 解析 `/proc/meminfo`，返回 (MemTotal, MemAvailable) MB
+///
+/// 3.14 之前的内核没有 `MemAvailable`，退回到 `MemFree + Buffers + Cached`
+fn parse_meminfo(content: &str) -> Option<(u64, u64)> {
+    let field = |name: &str| {
+        content.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
     };
+    let total = field("MemTotal")?;
+    let available = field("MemAvailable").unwrap_or_else(|| {
+        field("MemFree").unwrap_or(0) + field("Buffers").unwrap_or(0) + field("Cached").unwrap_or(0)
+    });
+    Some((total / 1024, available / 1024))
+}
 
-    let mut value: c_int = 0;
-    let mut size: size_t = mem::size_of::<c_int>();
-    let oldp = &mut value as *mut _ as *mut c_void;
-    let oldlenp = &mut size as *mut size_t;
+#[cfg(target_os = "macos")]
+/// 通过 `hw.memsize` 和 VM 统计信息获取 (物理内存总量, 可用内存) MB
+///
+/// 可用内存按 `vm_stat` 中的 free、inactive 和 speculative 页计算
+pub fn get_memory_info_macos() -> Option<(u64, u64)> {
+    let total = crate::macos::sysctl_u64("hw.memsize").ok()?;
+    let available = crate::macos::vm_available_bytes().unwrap_or(0);
+    Some((total / 1024 / 1024, available / 1024 / 1024))
+}
 
-    let ret = unsafe { sysctlbyname(name_c.as_ptr(), oldp, oldlenp, std::ptr::null_mut(), 0) };
+#[cfg(any(target_os = "linux", test))]
+/// 将 `container` 环境变量的值映射为容器类型
+///
+/// LXD 的系统容器同样声明为 `lxc`，需要结合 `/dev/lxd/sock` 区分
+fn container_kind(value: &str, has_lxd_sock: bool) -> &'static str {
+    match value {
+        "lxc" if has_lxd_sock => "lxd",
+        "lxc" | "lxc-libvirt" => "lxc",
+        "systemd-nspawn" => "systemd-nspawn",
+        "docker" => "docker",
+        "podman" => "podman",
+        _ => "other",
+    }
+}
 
-    if ret == 0 {
-        if value == 1 {
-            (
-                true,
-                "kern.hv_support (Hypervisor Framework) 为 1，虚拟化已启用。".to_string(),
+#[cfg(target_os = "macos")]
+/// 检查 Hypervisor Framework 是否可用，返回 (是否可用, 详情)，`sysctlbyname` 调用失败无法确认时为 `None`
+pub fn check_hypervisor_support_macos() -> (Option<bool>, String) {
+    match crate::macos::sysctl_int("kern.hv_support") {
+        Ok(1) => (
+            Some(true),
+            localize(
+                "kern.hv_support (Hypervisor Framework) 为 1，虚拟化已启用。",
+                "kern.hv_support (Hypervisor Framework) is 1, virtualization is enabled.",
             )
-        } else {
-            (
-                false,
+            .to_string(),
+        ),
+        Ok(value) => (
+            Some(false),
+            localize(
                 format!(
                     "kern.hv_support (Hypervisor Framework) 为 {}，虚拟化未启用或不受支持。",
                     value
                 ),
+                format!(
+                    "kern.hv_support (Hypervisor Framework) is {}, virtualization is disabled or unsupported.",
+                    value
+                ),
+            ),
+        ),
+        // 10.10 之前的系统没有 Hypervisor Framework，也就没有该键
+        Err(libc::ENOENT) => (
+            Some(false),
+            localize(
+                "系统中不存在 kern.hv_support，macOS 版本过旧 (低于 10.10)，不提供 Hypervisor Framework。",
+                "kern.hv_support does not exist; this macOS version (before 10.10) has no Hypervisor Framework.",
             )
+            .to_string(),
+        ),
+        Err(err_no) => (
+            None,
+            localize(
+                format!(
+                    "sysctlbyname 调用失败，无法确定 Hypervisor Framework 是否可用。错误码: {}",
+                    err_no
+                ),
+                format!(
+                    "sysctlbyname failed, cannot tell whether the Hypervisor Framework is available. errno: {}",
+                    err_no
+                ),
+            ),
+        ),
+    }
+}
+
+#[cfg(target_os = "macos")]
+/// 检测当前是否运行在虚拟机中，返回 (是否为客户机, Hypervisor 厂商, 详情)
+///
+/// Apple Virtualization.framework (`VZVirtualMachine`) 的客户机无法通过 `kern.hv_support` 区分，
+/// 需要检查 IORegistry 中平台的 `compatible` / `model` 是否为 `VirtualMac` 或 `apple-virt`
+pub fn detect_vm_macos() -> (bool, Option<&'static str>, String) {
+    use crate::macos;
+
+    const VZ_VENDOR: &str = "Apple Virtualization.framework";
+    let is_vz_platform = |value: &str| {
+        let value = value.to_ascii_lowercase();
+        value.contains("virtualmac") || value.contains("apple-virt")
+    };
+
+    if let Some(compatible) = macos::platform_expert_string("compatible")
+        && is_vz_platform(&compatible)
+    {
+        return (
+            true,
+            Some(VZ_VENDOR),
+            format!("IORegistry compatible 为 '{compatible}'，运行在 Apple 虚拟机中。"),
+        );
+    }
+    if let Ok(model) = macos::sysctl_string("hw.model")
+        && is_vz_platform(&model)
+    {
+        return (
+            true,
+            Some(VZ_VENDOR),
+            format!("hw.model 为 '{model}'，运行在 Apple 虚拟机中。"),
+        );
+    }
+    match macos::sysctl_int("kern.hv_vmm_present") {
+        Ok(1) => (
+            true,
+            None,
+            "kern.hv_vmm_present 为 1，运行在未知 Hypervisor 的虚拟机中。".to_string(),
+        ),
+        Ok(_) => (false, None, "未检测到虚拟机特征。".to_string()),
+        Err(err_no) => (
+            false,
+            None,
+            format!("未检测到虚拟机特征，kern.hv_vmm_present 读取失败。错误码: {err_no}"),
+        ),
+    }
+}
+
+#[cfg(target_os = "linux")]
+/// 检测当前是否运行在虚拟机中，返回 (是否为客户机, Hypervisor 厂商, 详情)
+///
+/// ARM 客户机 (如 Apple Virtualization.framework 中的 Linux) 没有 CPUID 的 Hypervisor 位，
+/// 改为检查精简设备树中 `/hypervisor` 节点的 `compatible`；x86/x86_64 上再退回到 CPUID 0x40000000 的厂商签名
+pub fn detect_vm_linux() -> (bool, Option<&'static str>, String) {
+    if let Ok(content) = std::fs::read("/proc/device-tree/hypervisor/compatible") {
+        let (compatible, vendor) = parse_device_tree_hypervisor(&content);
+        return (
+            true,
+            vendor,
+            format!(
+                "设备树 /hypervisor 的 compatible 为 '{}'，运行在虚拟机中。",
+                compatible.join(", ")
+            ),
+        );
+    }
+    match read_hypervisor_signature() {
+        Some(signature) => (
+            true,
+            None,
+            format!("CPUID 报告运行在 Hypervisor 中，厂商签名为 '{signature}'。"),
+        ),
+        None => (false, None, "未检测到虚拟机特征。".to_string()),
+    }
+}
+
+#[cfg(any(target_os = "linux", test))]
+/// 解析设备树 `compatible` 属性 (以 NUL 分隔的字符串列表)，返回 (compatible 列表, Hypervisor 厂商)
+fn parse_device_tree_hypervisor(content: &[u8]) -> (Vec<String>, Option<&'static str>) {
+    let compatible: Vec<String> = content
+        .split(|&b| b == 0)
+        .filter(|it| !it.is_empty())
+        .map(|it| String::from_utf8_lossy(it).into_owned())
+        .collect();
+    let vendor = compatible.iter().find_map(|it| {
+        let it = it.to_ascii_lowercase();
+        if it.starts_with("apple,") {
+            Some("Apple Virtualization.framework")
+        } else if it.starts_with("xen,") {
+            Some("Xen")
+        } else if it.contains("kvm") {
+            Some("KVM")
+        } else {
+            None
         }
+    });
+    (compatible, vendor)
+}
+
+#[cfg(target_os = "macos")]
+/// 获取 Mac 的 CPU 类型，返回 `apple_silicon` 或 `intel`
+///
+/// 通过 `hw.optional.arm64` 在运行时判断，x86_64 构建在 Rosetta 下运行时依然能返回 `apple_silicon`，
+/// 而 `cfg!(target_arch)` 只能反映编译目标
+pub fn get_mac_cpu_kind() -> &'static str {
+    match crate::macos::sysctl_int("hw.optional.arm64") {
+        Ok(1) => "apple_silicon",
+        // Intel Mac 上该键不存在 (ENOENT)
+        _ => "intel",
+    }
+}
+
+#[cfg(target_os = "windows")]
+/// 将 `IMAGE_FILE_MACHINE_*` 转换为架构名称
+fn image_file_machine_name(
+    machine: windows::Win32::System::SystemInformation::IMAGE_FILE_MACHINE,
+) -> &'static str {
+    use windows::Win32::System::SystemInformation::{
+        IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM, IMAGE_FILE_MACHINE_ARM64,
+        IMAGE_FILE_MACHINE_ARMNT, IMAGE_FILE_MACHINE_I386,
+    };
+    match machine {
+        IMAGE_FILE_MACHINE_AMD64 => "x86_64",
+        IMAGE_FILE_MACHINE_I386 => "x86",
+        IMAGE_FILE_MACHINE_ARM64 => "aarch64",
+        IMAGE_FILE_MACHINE_ARM | IMAGE_FILE_MACHINE_ARMNT => "arm",
+        _ => "unknown",
+    }
+}
+
+#[cfg(target_os = "windows")]
+/// 通过 `IsWow64Process2` 获取当前进程与本机的架构，返回 (是否为 WOW64, 进程架构, 本机架构)
+///
+/// ARM64 上模拟运行的 x64 进程不属于 WOW64，此时进程架构取自编译目标，
+/// 调用方应比较两个架构来判断是否处于模拟状态
+pub fn get_process_emulation_windows() -> (bool, &'static str, &'static str) {
+    use windows::Win32::System::SystemInformation::{
+        IMAGE_FILE_MACHINE, IMAGE_FILE_MACHINE_UNKNOWN,
+    };
+    use windows::Win32::System::Threading::{GetCurrentProcess, IsWow64Process2};
+
+    let compiled_arch = if cfg!(target_arch = "x86_64") {
+        "x86_64"
+    } else if cfg!(target_arch = "aarch64") {
+        "aarch64"
+    } else if cfg!(target_arch = "x86") {
+        "x86"
     } else {
-        let err_no = unsafe { *libc::__error() };
-        (false, format!("sysctlbyname 调用失败。错误码: {}", err_no))
+        "unknown"
+    };
+
+    let mut process_machine = IMAGE_FILE_MACHINE_UNKNOWN;
+    let mut native_machine = IMAGE_FILE_MACHINE::default();
+    let result = unsafe {
+        IsWow64Process2(
+            GetCurrentProcess(),
+            &mut process_machine,
+            Some(&mut native_machine),
+        )
+    };
+    if result.is_err() {
+        // Windows 10 1511 之前没有该 API，视为未模拟
+        return (false, compiled_arch, compiled_arch);
     }
+    // 非 WOW64 进程的 process_machine 为 IMAGE_FILE_MACHINE_UNKNOWN
+    let is_wow64 = process_machine != IMAGE_FILE_MACHINE_UNKNOWN;
+    let process_arch = if is_wow64 {
+        image_file_machine_name(process_machine)
+    } else {
+        compiled_arch
+    };
+    (
+        is_wow64,
+        process_arch,
+        image_file_machine_name(native_machine),
+    )
 }
 
 #[cfg(target_os = "windows")]
+/// 获取当前进程所在的会话，返回 (是否为 RDP 会话, 会话 ID)
+///
+/// 协议类型通过 `WTSClientProtocolType` 查询，0 为本地控制台，2 为 RDP，查询失败时视为本地会话
+pub fn get_session_context_windows() -> (bool, u32) {
+    use windows::Win32::System::RemoteDesktop::{
+        ProcessIdToSessionId, WTS_CURRENT_SERVER_HANDLE, WTS_CURRENT_SESSION,
+        WTSClientProtocolType, WTSFreeMemory, WTSQuerySessionInformationW,
+    };
+    use windows::Win32::System::Threading::GetCurrentProcessId;
+    use windows::core::PWSTR;
+
+    const WTS_PROTOCOL_TYPE_RDP: u16 = 2;
+
+    let mut session_id = 0;
+    let _ = unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session_id) };
+
+    let mut buffer = PWSTR::null();
+    let mut bytes = 0;
+    let is_rdp = match unsafe {
+        WTSQuerySessionInformationW(
+            Some(WTS_CURRENT_SERVER_HANDLE),
+            WTS_CURRENT_SESSION,
+            WTSClientProtocolType,
+            &mut buffer,
+            &mut bytes,
+        )
+    } {
+        Ok(()) => {
+            let protocol = if bytes as usize >= size_of::<u16>() {
+                unsafe { (buffer.0 as *const u16).read_unaligned() }
+            } else {
+                0
+            };
+            unsafe { WTSFreeMemory(buffer.0.cast()) };
+            protocol == WTS_PROTOCOL_TYPE_RDP
+        }
+        Err(_) => false,
+    };
+    (is_rdp, session_id)
+}
+
+#[cfg(target_os = "windows")]
+/// 检查 SLAT (Intel EPT / AMD RVI)，返回 (是否支持, 说明)，WSL2 与客户端 Hyper-V 都依赖它
+///
+/// Hypervisor 运行后 `PF_SECOND_LEVEL_ADDRESS_TRANSLATION` 可能为 false，此时 Hyper-V 能够启动本身就说明支持 SLAT；
+/// ARM64 的二级地址转换 (Stage 2) 是虚拟化扩展的一部分
+pub fn check_slat_support_windows() -> (bool, String) {
+    use windows::Win32::System::Threading::{
+        IsProcessorFeaturePresent, PF_SECOND_LEVEL_ADDRESS_TRANSLATION,
+    };
+    if unsafe { IsProcessorFeaturePresent(PF_SECOND_LEVEL_ADDRESS_TRANSLATION) }.as_bool() {
+        return (true, "系统报告处理器支持 SLAT".to_string());
+    }
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if get_hyperv_partition_role() == "root" {
+            return (
+                true,
+                "Hyper-V 虚拟机监控程序正在运行，说明处理器支持 SLAT".to_string(),
+            );
+        }
+        // AMD 的 NP (Nested Paging) 位于 0x8000000A.EDX 第 0 位，Intel 的 EPT 能力位于 MSR 中，用户态无法读取
+        if cpu_identity().vendor == CpuVendor::Amd && cpuid(0x8000000A, 0).edx & 1 != 0 {
+            return (true, "CPUID 报告处理器支持 AMD RVI (NPT)".to_string());
+        }
+        (
+            false,
+            "系统报告处理器不支持 SLAT (Intel EPT / AMD RVI)".to_string(),
+        )
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        let supported = check_virtual_support().0;
+        (
+            supported,
+            if supported {
+                "ARM64 虚拟化扩展包含二级地址转换".to_string()
+            } else {
+                "未检测到 ARM64 虚拟化扩展，无法使用二级地址转换".to_string()
+            },
+        )
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_os = "windows"))]
+/// 通过 Windows Hypervisor Platform 查询 Hypervisor 是否正在运行，未启用该可选功能时返回 `None`
+///
+/// `WinHvPlatform.dll` 只在启用“Windows 虚拟机监控程序平台”后存在，静态导入会导致整个模块无法加载，
+/// 因此在运行时加载
+fn whv_hypervisor_present() -> Option<bool> {
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::FreeLibrary;
+    use windows::Win32::System::LibraryLoader::{
+        GetProcAddress, LOAD_LIBRARY_SEARCH_SYSTEM32, LoadLibraryExW,
+    };
+    use windows::core::{HRESULT, s, w};
+
+    type WHvGetCapabilityFn = unsafe extern "system" fn(i32, *mut c_void, u32, *mut u32) -> HRESULT;
+    const WHV_CAPABILITY_CODE_HYPERVISOR_PRESENT: i32 = 0;
+
+    let module =
+        unsafe { LoadLibraryExW(w!("WinHvPlatform.dll"), None, LOAD_LIBRARY_SEARCH_SYSTEM32) }
+            .ok()?;
+    let present = unsafe { GetProcAddress(module, s!("WHvGetCapability")) }.and_then(|address| {
+        let get_capability: WHvGetCapabilityFn = unsafe { std::mem::transmute(address) };
+        // WHV_CAPABILITY 是联合体，HypervisorPresent 为开头的 BOOL，缓冲区按最大成员预留
+        let mut capability = [0u32; 16];
+        let mut written = 0;
+        unsafe {
+            get_capability(
+                WHV_CAPABILITY_CODE_HYPERVISOR_PRESENT,
+                capability.as_mut_ptr().cast(),
+                size_of_val(&capability) as u32,
+                &mut written,
+            )
+        }
+        .is_ok()
+        .then_some(capability[0] != 0)
+    });
+    let _ = unsafe { FreeLibrary(module) };
+    present
+}
+
+#[cfg(all(target_arch = "aarch64", target_os = "windows"))]
+/// Hypervisor 运行后 `PF_VIRT_FIRMWARE_ENABLED` 不再可靠，优先通过 WHP 判断 Hyper-V/WHPX 是否可用
+pub fn check_virtualization_enabled_windows() -> (bool, String) {
+    use windows::Win32::System::Threading::{IsProcessorFeaturePresent, PF_VIRT_FIRMWARE_ENABLED};
+    let firmware_enabled = unsafe { IsProcessorFeaturePresent(PF_VIRT_FIRMWARE_ENABLED) }.as_bool();
+    match whv_hypervisor_present() {
+        Some(true) => (
+            true,
+            localize(
+                "Windows Hypervisor Platform 报告 Hypervisor 正在运行，Hyper-V/WHPX 可用",
+                "Windows Hypervisor Platform reports the hypervisor is running, Hyper-V/WHPX is available",
+            )
+            .to_string(),
+        ),
+        _ if firmware_enabled => (
+            true,
+            localize(
+                "虚拟化已在固件中启用",
+                "Virtualization is enabled in firmware",
+            )
+            .to_string(),
+        ),
+        Some(false) => (
+            false,
+            localize(
+                "Windows Hypervisor Platform 报告 Hypervisor 未运行，且固件未开放虚拟化扩展 (部分骁龙设备的固件不允许进入 EL2)",
+                "Windows Hypervisor Platform reports the hypervisor is not running and the firmware does not expose virtualization extensions (some Snapdragon firmware does not allow entering EL2)",
+            )
+            .to_string(),
+        ),
+        None => (
+            false,
+            localize(
+                "虚拟化未在固件中启用或此检查不受支持，未启用 Windows 虚拟机监控程序平台，无法确认 WHPX 是否可用",
+                "Virtualization is not enabled in firmware or this check is unsupported; Windows Hypervisor Platform is not enabled, so WHPX availability cannot be confirmed",
+            )
+            .to_string(),
+        ),
+    }
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_os = "windows"
+))]
 pub fn check_virtualization_enabled_windows() -> (bool, String) {
     use windows::Win32::System::Threading::{
         IsProcessorFeaturePresent,
-        PF_VIRT_FIRMWARE_ENABLED, // 值为 19（0x13）
+        PF_VIRT_FIRMWARE_ENABLED, // 值为 21（0x15）
     };
     // 适用于 Windows8 / Server 2012 及更高版本
     let result = unsafe { IsProcessorFeaturePresent(PF_VIRT_FIRMWARE_ENABLED) };
     if result.as_bool() {
-        (true, "虚拟化已在固件中启用".to_string())
+        (
+            true,
+            localize(
+                "虚拟化已在固件中启用",
+                "Virtualization is enabled in firmware",
+            )
+            .to_string(),
+        )
     } else {
         let (is_hyperv, _, sign) = check_hyperv_environment_cpuid();
         if is_hyperv {
-            (true, "虚拟化检测在 Hypervisor 下失效".to_string())
+            (
+                true,
+                localize(
+                    "虚拟化检测在 Hypervisor 下失效",
+                    "The firmware check is unreliable under a hypervisor",
+                )
+                .to_string(),
+            )
+        } else if is_supported_without_hypervisor() {
+            let hint = firmware_disabled_hint();
+            (
+                false,
+                localize(
+                    format!("PF_VIRT_FIRMWARE_ENABLED 为 false。{hint}"),
+                    format!("PF_VIRT_FIRMWARE_ENABLED is false. {hint}"),
+                ),
+            )
         } else {
             (
                 false,
-                format!("虚拟化未在固件中启用或此检查不受支持(CPU Sign: {sign})"),
+                localize(
+                    format!("虚拟化未在固件中启用或此检查不受支持(CPU Sign: {sign})"),
+                    format!(
+                        "Virtualization is not enabled in firmware or this check is unsupported (CPU Sign: {sign})"
+                    ),
+                ),
             )
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_cores() {
+        // 混合架构: 两个带 SMT 的 P 核和四个不带 SMT 的 E 核
+        let siblings = ["0-1", "0-1", "2-3", "2-3", "4", "5", "6", "7"].map(String::from);
+        assert_eq!(count_cores(&siblings), Some((6, 8)));
+        assert_eq!(count_cores(&[]), None);
+    }
+
+    #[test]
+    fn test_cpuinfo_has_flag() {
+        let cpuinfo = "processor\t: 0\nvendor_id\t: GenuineIntel\nflags\t\t: fpu vme vmx sse2\n\nprocessor\t: 1\nflags\t\t: fpu\n";
+        assert!(cpuinfo_has_flag(cpuinfo, "flags", "vmx"));
+        assert!(!cpuinfo_has_flag(cpuinfo, "flags", "svm"));
+        assert!(!cpuinfo_has_flag(cpuinfo, "flags", "vm"));
+        assert!(!cpuinfo_has_flag("", "flags", "vmx"));
+
+        let cpuinfo = "flags\t\t: fpu vmx ept\nvmx flags\t: vnmi ept vpid unrestricted_guest\n";
+        assert!(cpuinfo_has_flag(cpuinfo, "vmx flags", "unrestricted_guest"));
+        assert!(!cpuinfo_has_flag(cpuinfo, "vmx flags", "vmx"));
+    }
+
+    #[test]
+    fn test_hypervisor_vendor_from_signature() {
+        let vendor = HypervisorVendor::from_signature;
+        assert_eq!(vendor(None), HypervisorVendor::None);
+        assert_eq!(vendor(Some("Microsoft Hv")), HypervisorVendor::HyperV);
+        assert_eq!(vendor(Some("KVMKVMKVM")), HypervisorVendor::Kvm);
+        assert_eq!(vendor(Some("prl hyperv  ")), HypervisorVendor::Parallels);
+        assert_eq!(vendor(Some("TCGTCGTCGTCG")), HypervisorVendor::Qemu);
+        assert_eq!(vendor(Some("bhyve bhyve ")), HypervisorVendor::Unknown);
+        assert_eq!(vendor(Some("")), HypervisorVendor::Unknown);
+    }
+
+    #[test]
+    fn test_is_virtual_gpu() {
+        assert!(!is_virtual_gpu(
+            "NVIDIA GeForce RTX 3060",
+            r"PCI\VEN_10DE&DEV_2503&SUBSYS_00000000&REV_A1\4&1A2B3C4D&0&0008"
+        ));
+        assert!(is_virtual_gpu(
+            "Microsoft Remote Display Adapter",
+            r"SWD\REMOTEDISPLAYENUM\RDPIDD_INDIRECTDISPLAY&SESSIONID_0002"
+        ));
+        assert!(is_virtual_gpu(
+            "Microsoft Hyper-V Video",
+            r"VMBUS\{DA0A7802-E377-4AAC-8E77-0558EB1073F8}\{5620E0C7-8062-4DCE-AEB7-520C7EF76171}"
+        ));
+        assert!(is_virtual_gpu(
+            "VMware SVGA 3D",
+            r"PCI\VEN_15AD&DEV_0405&SUBSYS_040515AD&REV_00\3&61AAA01&0&78"
+        ));
+        assert!(is_virtual_gpu(
+            "Microsoft Basic Display Adapter",
+            r"PCI\VEN_1013&DEV_00B8&SUBSYS_11001AF4&REV_00\3&13C0B0C5&0&10"
+        ));
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let content = "MemTotal:       16303428 kB\nMemFree:         1204520 kB\nMemAvailable:    9876544 kB\nBuffers:          412300 kB\nCached:          7012344 kB\n";
+        assert_eq!(parse_meminfo(content), Some((15921, 9645)));
+        // 旧内核没有 MemAvailable
+        let content = "MemTotal:       2048000 kB\nMemFree:         512000 kB\nBuffers:          102400 kB\nCached:           409600 kB\n";
+        assert_eq!(parse_meminfo(content), Some((2000, 1000)));
+        assert_eq!(parse_meminfo(""), None);
+    }
+
+    #[test]
+    fn test_decode_cpu_signature() {
+        // Intel Core i7-8700 (Coffee Lake)
+        assert_eq!(decode_cpu_signature(0x000906EA), (6, 0x9E, 0xA));
+        // AMD Ryzen 5 5600X (Zen 3)
+        assert_eq!(decode_cpu_signature(0x00A20F10), (0x19, 0x21, 0));
+        // Intel Pentium 4，family 为 0xF 时才会加上扩展 family
+        assert_eq!(decode_cpu_signature(0x00000F29), (0xF, 0x2, 0x9));
+    }
+
+    #[test]
+    fn test_wsl_version_from_release() {
+        assert_eq!(wsl_version_from_release("4.4.0-19041-Microsoft\n"), Some(1));
+        assert_eq!(
+            wsl_version_from_release("5.15.90.1-microsoft-standard-WSL2"),
+            Some(2)
+        );
+        assert_eq!(
+            wsl_version_from_release("4.19.104-microsoft-standard"),
+            Some(2)
+        );
+        assert_eq!(wsl_version_from_release("6.8.0-45-generic"), None);
+    }
+
+    #[test]
+    fn test_container_kind() {
+        assert_eq!(container_kind("lxc", true), "lxd");
+        assert_eq!(container_kind("lxc", false), "lxc");
+        assert_eq!(container_kind("systemd-nspawn", false), "systemd-nspawn");
+        assert_eq!(container_kind("oci", false), "other");
+    }
+
+    #[test]
+    fn test_parse_device_tree_hypervisor() {
+        let (compatible, vendor) = parse_device_tree_hypervisor(b"xen,xen-4.17\0xen,xen\0");
+        assert_eq!(compatible, ["xen,xen-4.17", "xen,xen"]);
+        assert_eq!(vendor, Some("Xen"));
+        assert_eq!(parse_device_tree_hypervisor(b"foo,bar\0").1, None);
+        assert!(parse_device_tree_hypervisor(b"").0.is_empty());
+    }
+}
@@ -10,21 +10,126 @@ struct OptionalFeature {
     install_state: u32,
 }
 
-fn wmi_err_to_string(err: &wmi::WMIError) -> String {
-    match err {
-        wmi::WMIError::HResultError { hres } => {
-            format!(
-                "WMI 查询失败, 原因: {:?}({hres}), COM 线程状态: {:?}",
-                windows::core::HRESULT::from_nt(*hres).message(),
-                get_thread_com_state()
-            )
+#[derive(Debug)]
+pub enum WindowsFeatureError {
+    /// 在 WMI 线程中初始化 COM 或连接命名空间失败
+    ComInit { hres: i32 },
+    /// WMI 查询返回错误，`com_state` 为执行查询的线程的 COM 状态
+    WmiQuery { hres: i32, com_state: String },
+    /// 不带 HRESULT 的 WMI 错误，如查询结果无法反序列化
+    Wmi(String),
+    /// WMI 线程中的查询发生 panic
+    ThreadPanic(String),
+    /// WMI 线程不可用或查询超时
+    Executor(String),
+    /// 打开或查询服务失败，如服务不存在、权限不足
+    ServiceQuery(windows_service::Error),
+    /// 查询成功但没有返回需要的数据
+    NoData(&'static str),
+    /// 调用系统 API 失败，如通过 D3DKMT 查询显卡
+    Win32(windows::core::Error),
+    /// 加载或调用 DISM API (`dismapi.dll`) 失败
+    Dism(String),
+}
+
+impl WindowsFeatureError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            WindowsFeatureError::ComInit { .. } => "COM_INIT_FAILED",
+            WindowsFeatureError::WmiQuery { .. } => "WMI_QUERY_FAILED",
+            WindowsFeatureError::Wmi(_) => "WMI_ERROR",
+            WindowsFeatureError::ThreadPanic(_) => "THREAD_PANIC",
+            WindowsFeatureError::Executor(_) => "EXECUTOR_UNAVAILABLE",
+            WindowsFeatureError::ServiceQuery(_) => "SERVICE_QUERY_FAILED",
+            WindowsFeatureError::NoData(_) => "NO_DATA",
+            WindowsFeatureError::Win32(_) => "WIN32_API_FAILED",
+            WindowsFeatureError::Dism(_) => "DISM_FAILED",
         }
-        _ => {
-            format!("WMI 查询失败, 原因: {err:?}")
+    }
+
+    /// 连接或查询 WMI 时返回的 HRESULT
+    pub fn hresult(&self) -> Option<i32> {
+        match self {
+            WindowsFeatureError::ComInit { hres } | WindowsFeatureError::WmiQuery { hres, .. } => {
+                Some(*hres)
+            }
+            _ => None,
         }
     }
 }
 
+impl std::fmt::Display for WindowsFeatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = |hres: i32| windows::core::HRESULT::from_nt(hres).message();
+        match self {
+            WindowsFeatureError::ComInit { hres } => write!(
+                f,
+                "初始化 COM 或连接 WMI 失败, 原因: {:?}({hres})",
+                message(*hres)
+            ),
+            WindowsFeatureError::WmiQuery { hres, com_state } => write!(
+                f,
+                "WMI 查询失败, 原因: {:?}({hres}), COM 线程状态: {com_state:?}",
+                message(*hres)
+            ),
+            WindowsFeatureError::Wmi(msg) => write!(f, "WMI 查询失败, 原因: {msg}"),
+            WindowsFeatureError::ThreadPanic(msg) => {
+                write!(f, "WMI 线程中的查询发生 panic, 原因: {msg}")
+            }
+            WindowsFeatureError::Executor(msg) => {
+                write!(f, "在 WMI 线程执行查询失败, 原因: {msg}")
+            }
+            // windows_service 的错误信息不包含系统错误码，附加上底层的 IO 错误
+            WindowsFeatureError::ServiceQuery(err) => match std::error::Error::source(err) {
+                Some(source) => write!(f, "{err}: {source}"),
+                None => write!(f, "{err}"),
+            },
+            WindowsFeatureError::NoData(class) => write!(f, "未查询到 {class}"),
+            WindowsFeatureError::Win32(err) => write!(f, "调用系统 API 失败, 原因: {err}"),
+            WindowsFeatureError::Dism(msg) => write!(f, "DISM 调用失败, 原因: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for WindowsFeatureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WindowsFeatureError::ServiceQuery(err) => Some(err),
+            WindowsFeatureError::Win32(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<wmi::WMIError> for WindowsFeatureError {
+    fn from(err: wmi::WMIError) -> Self {
+        match err {
+            wmi::WMIError::HResultError { hres } => WindowsFeatureError::WmiQuery {
+                hres,
+                com_state: get_thread_com_state(),
+            },
+            err => WindowsFeatureError::Wmi(format!("{err:?}")),
+        }
+    }
+}
+
+impl From<crate::wmi_executor::ExecutorError> for WindowsFeatureError {
+    fn from(err: crate::wmi_executor::ExecutorError) -> Self {
+        match err {
+            crate::wmi_executor::ExecutorError::Panicked(msg) => {
+                WindowsFeatureError::ThreadPanic(msg)
+            }
+            err => WindowsFeatureError::Executor(err.to_string()),
+        }
+    }
+}
+
+impl From<windows_service::Error> for WindowsFeatureError {
+    fn from(err: windows_service::Error) -> Self {
+        WindowsFeatureError::ServiceQuery(err)
+    }
+}
+
 pub fn get_thread_com_state() -> String {
     use windows::Win32::System::Com::{APTTYPE, CoGetApartmentType};
     use windows::core::HRESULT;
@@ -58,29 +163,103 @@ pub fn get_thread_com_state() -> String {
     }
 }
 
+/// 在共享的 WMI 线程中执行查询，连接命名空间失败时返回 `ComInit`
+fn execute_wmi_query_in<T: DeserializeOwned + Send + 'static>(
+    namespace: &'static str,
+    query: &'static str,
+) -> Result<Vec<T>, WindowsFeatureError> {
+    // 在独立的 MTA 线程中执行，防止出现 STA、MTA 问题
+    crate::wmi_executor::run(move |context| -> Result<Vec<T>, WindowsFeatureError> {
+        let wmi_con = context.connection(namespace).map_err(|err| match err {
+            wmi::WMIError::HResultError { hres } => WindowsFeatureError::ComInit { hres },
+            err => WindowsFeatureError::Wmi(format!("{err:?}")),
+        })?;
+        Ok(wmi_con.raw_query(query)?)
+    })?
+}
+
 fn execute_wmi_query<T: DeserializeOwned + Send + 'static>(
     query: &'static str,
-) -> Result<Vec<T>, String> {
-    // 使用新线程来出现防止 STA、MTA 问题
-    let task = std::thread::spawn(move || -> Result<Vec<T>, wmi::WMIError> {
-        let com_lib = wmi::COMLibrary::new()?;
-        let wmi_con = wmi::WMIConnection::new(com_lib)?;
+) -> Result<Vec<T>, WindowsFeatureError> {
+    execute_wmi_query_in(crate::wmi_executor::ROOT_CIMV2, query)
+}
 
-        let results: Vec<T> = wmi_con.raw_query(query)?;
-        Ok(results)
-    });
-    let results = task
-        .join()
-        .map_err(|err| format!("在新线程执行 WMI 查询失败, 原因: {err:?}"))?
-        .map_err(|err| wmi_err_to_string(&err))?;
+// 对应的 Windows 功能未安装时，命名空间或类不存在
+const WBEM_E_INVALID_NAMESPACE: u32 = 0x8004100E;
+const WBEM_E_INVALID_CLASS: u32 = 0x80041010;
 
-    Ok(results)
+/// 在指定命名空间中执行 WMI 查询，命名空间或类不存在时返回 `Ok(None)`
+fn execute_wmi_query_in_namespace<T: DeserializeOwned + Send + 'static>(
+    namespace: &'static str,
+    query: &'static str,
+) -> Result<Option<Vec<T>>, WindowsFeatureError> {
+    match execute_wmi_query_in(namespace, query) {
+        Ok(results) => Ok(Some(results)),
+        Err(err)
+            if err.hresult().is_some_and(|hres| {
+                matches!(hres as u32, WBEM_E_INVALID_NAMESPACE | WBEM_E_INVALID_CLASS)
+            }) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+pub mod version {
+    /// 读取 `HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion`，返回 (major, minor, build, ubr, display_version)
+    ///
+    /// Windows 10 之前没有 `CurrentMajorVersionNumber`，退回到解析 `CurrentVersion` (如 `6.3`)；
+    /// 20H2 之前没有 `DisplayVersion`，退回到 `ReleaseId` (如 `2004`)
+    pub fn read_windows_version_via_reg() -> std::io::Result<(u32, u32, u32, u32, Option<String>)> {
+        use winreg::RegKey;
+        use winreg::enums::HKEY_LOCAL_MACHINE;
+        let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion")?;
+
+        let (major, minor) = match (
+            key.get_value::<u32, _>("CurrentMajorVersionNumber"),
+            key.get_value::<u32, _>("CurrentMinorVersionNumber"),
+        ) {
+            (Ok(major), Ok(minor)) => (major, minor),
+            _ => {
+                let version = key.get_value::<String, _>("CurrentVersion")?;
+                let mut parts = version.split('.').map(|it| it.parse::<u32>().unwrap_or(0));
+                (parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+            }
+        };
+        let build = key
+            .get_value::<String, _>("CurrentBuildNumber")?
+            .parse::<u32>()
+            .unwrap_or(0);
+        let ubr = key.get_value::<u32, _>("UBR").unwrap_or(0);
+        let display_version = key
+            .get_value::<String, _>("DisplayVersion")
+            .or_else(|_| key.get_value::<String, _>("ReleaseId"))
+            .ok();
+        Ok((major, minor, build, ubr, display_version))
+    }
+
+    /// 读取 `CurrentVersion` 下的 `EditionID`，如 `Professional`、`Core`、`Enterprise`
+    pub fn read_edition_id_via_reg() -> Option<String> {
+        use winreg::RegKey;
+        use winreg::enums::HKEY_LOCAL_MACHINE;
+        RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion")
+            .and_then(|key| key.get_value::<String, _>("EditionID"))
+            .ok()
+    }
+
+    /// 家庭版 (`Core`、`CoreN`、`CoreSingleLanguage`、`CoreCountrySpecific`) 不提供 Hyper-V
+    pub fn edition_supports_hyperv(edition_id: &str) -> bool {
+        !edition_id.starts_with("Core")
+    }
 }
 
 pub mod wsl {
     use super::*;
 
-    pub fn check_wsl_via_wmi() -> Result<(bool, bool), String> {
+    pub fn check_wsl_via_wmi() -> Result<(bool, bool), WindowsFeatureError> {
         // 构建 WMI 查询
         let query = "SELECT Name, InstallState FROM Win32_OptionalFeature WHERE Name = 'Microsoft-Windows-Subsystem-Linux' OR Name = 'VirtualMachinePlatform'";
 
@@ -102,6 +281,13 @@ pub mod wsl {
 
         Ok((wsl_enabled, vmp_enabled))
     }
+
+    /// 单独查询 `VirtualMachinePlatform` 可选功能是否已启用，系统中没有该功能时返回 `Ok(None)`
+    pub fn check_vmp_via_wmi() -> Result<Option<bool>, WindowsFeatureError> {
+        let query = "SELECT Name, InstallState FROM Win32_OptionalFeature WHERE Name = 'VirtualMachinePlatform'";
+        let results: Vec<OptionalFeature> = execute_wmi_query(query)?;
+        Ok(results.first().map(|it| it.install_state == 1))
+    }
     pub fn check_wsl_via_reg() -> bool {
         use winreg::RegKey;
         use winreg::enums::HKEY_LOCAL_MACHINE;
@@ -109,7 +295,7 @@ pub mod wsl {
             .open_subkey(r"SYSTEM\CurrentControlSet\Services\lxss")
             .is_ok()
     }
-    pub fn check_wsl_via_service() -> Result<bool, Box<dyn std::error::Error>> {
+    pub fn check_wsl_via_service() -> Result<bool, WindowsFeatureError> {
         use windows_service::service::{ServiceAccess, ServiceState};
 
         use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
@@ -118,12 +304,192 @@ pub mod wsl {
         let status = service.query_status()?;
         Ok(status.current_state == ServiceState::Running)
     }
+
+    /// 读取当前用户的默认 WSL 版本 (`wsl --set-default-version`)，未设置时返回 `None`
+    pub fn read_default_version_via_reg() -> Option<u32> {
+        use winreg::RegKey;
+        use winreg::enums::HKEY_CURRENT_USER;
+        RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey(r"Software\Microsoft\Windows\CurrentVersion\Lxss")
+            .and_then(|key| key.get_value::<u32, _>("DefaultVersion"))
+            .ok()
+    }
+
+    /// 统计当前用户已注册的发行版数量，`Lxss` 下每个带有 `DistributionName` 的子键为一个发行版
+    pub fn count_distributions_via_reg() -> u32 {
+        use winreg::RegKey;
+        use winreg::enums::HKEY_CURRENT_USER;
+        let Ok(lxss) = RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey(r"Software\Microsoft\Windows\CurrentVersion\Lxss")
+        else {
+            return 0;
+        };
+        lxss.enum_keys()
+            .filter_map(Result::ok)
+            .filter(|guid| {
+                lxss.open_subkey(guid)
+                    .and_then(|distro| distro.get_value::<String, _>("DistributionName"))
+                    .is_ok()
+            })
+            .count() as u32
+    }
+
+    /// 读取默认发行版，返回 (名称, WSL 版本, 注册表中的 State)，没有安装任何发行版时返回 `None`
+    ///
+    /// `DefaultDistribution` 为发行版子键的 GUID，State 为 1 (已安装)、2 (安装中)、3 (卸载中)、4 (转换中)
+    pub fn read_default_distro_via_reg() -> Option<(String, u32, u32)> {
+        use winreg::RegKey;
+        use winreg::enums::HKEY_CURRENT_USER;
+        let lxss = RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey(r"Software\Microsoft\Windows\CurrentVersion\Lxss")
+            .ok()?;
+        let guid = lxss.get_value::<String, _>("DefaultDistribution").ok()?;
+        let distro = lxss.open_subkey(guid).ok()?;
+        let name = distro.get_value::<String, _>("DistributionName").ok()?;
+        // 旧版本只支持 WSL1，没有 Version 值
+        let version = distro.get_value::<u32, _>("Version").unwrap_or(1);
+        let state = distro.get_value::<u32, _>("State").unwrap_or(0);
+        Some((name, version, state))
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename = "Win32_Process")]
+    #[serde(rename_all = "PascalCase")]
+    struct Process {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    /// WSL2 的轻量虚拟机是否正在运行，其内存由 `vmmem` (新版本为 `vmmemWSL`) 进程代表
+    ///
+    /// 所有 WSL2 发行版共用同一个虚拟机，无法区分具体是哪个发行版在运行
+    pub fn is_utility_vm_running() -> Result<bool, WindowsFeatureError> {
+        let query = "SELECT Name FROM Win32_Process WHERE Name = 'vmmem' OR Name = 'vmmemWSL'";
+        let results: Vec<Process> = execute_wmi_query(query)?;
+        Ok(!results.is_empty())
+    }
+
+    /// 是否安装了应用商店版本的 WSL，该版本默认使用 WSL2 并自带 WSLg
+    pub fn is_store_package_installed() -> bool {
+        program_files().join(r"WSL\wsl.exe").exists()
+    }
+
+    /// 查找 WSLg 组件，返回已存在的文件路径
+    ///
+    /// 应用商店版本位于 `Program Files\WSL`，Windows 11 内置版本的系统发行版位于 `System32\lxss`
+    pub fn find_wslg_components() -> Vec<String> {
+        let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string());
+        [
+            program_files().join(r"WSL\wslg.exe"),
+            program_files().join(r"WSL\system.vhd"),
+            std::path::Path::new(&system_root).join(r"System32\lxss\system.vhd"),
+        ]
+        .into_iter()
+        .filter(|it| it.exists())
+        .map(|it| it.display().to_string())
+        .collect()
+    }
+
+    /// 查找 WSL2 使用的内核镜像，优先使用 `%USERPROFILE%\.wslconfig` 中 `[wsl2]` 的 `kernel` 配置，
+    /// 其次是应用商店版本和内置版本自带的内核，都不存在时返回 `None`
+    pub fn find_kernel_image() -> Option<std::path::PathBuf> {
+        let configured = std::env::var("USERPROFILE")
+            .ok()
+            .and_then(|profile| {
+                std::fs::read_to_string(std::path::Path::new(&profile).join(".wslconfig")).ok()
+            })
+            .and_then(|config| parse_wslconfig_kernel(&config))
+            .map(std::path::PathBuf::from);
+        let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string());
+        configured
+            .into_iter()
+            .chain([
+                program_files().join(r"WSL\tools\kernel"),
+                std::path::Path::new(&system_root).join(r"System32\lxss\tools\kernel"),
+            ])
+            .find(|it| it.exists())
+    }
+
+    /// 读取 WSL2 内核镜像的版本，未安装 WSL2 或无法识别时返回 `None`
+    ///
+    /// bzImage 的版本字符串位于镜像开头的 setup 扇区中，只需读取前 64 KiB；
+    /// arm64 的 Image 没有 setup 扇区，需要读取整个镜像查找版本横幅
+    pub fn read_kernel_version() -> Option<String> {
+        use std::io::Read;
+        let path = find_kernel_image()?;
+        let mut image = Vec::new();
+        std::fs::File::open(&path)
+            .ok()?
+            .take(0x10000)
+            .read_to_end(&mut image)
+            .ok()?;
+        if image.get(0x38..0x3C) == Some(b"ARM\x64".as_slice()) {
+            image = std::fs::read(&path).ok()?;
+        }
+        parse_kernel_image_version(&image)
+    }
+
+    /// 解析 `.wslconfig` 中 `[wsl2]` 节的 `kernel`，路径中的 `\\` 还原为 `\`
+    pub fn parse_wslconfig_kernel(config: &str) -> Option<String> {
+        let mut in_wsl2 = false;
+        for line in config.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_wsl2 = line.eq_ignore_ascii_case("[wsl2]");
+                continue;
+            }
+            if !in_wsl2 {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if key.trim().eq_ignore_ascii_case("kernel") {
+                let value = value.trim().trim_matches('"').replace(r"\\", r"\");
+                return (!value.is_empty()).then_some(value);
+            }
+        }
+        None
+    }
+
+    /// 从内核镜像读取内核版本，如 `5.15.90.1-microsoft-standard-WSL2` 返回 `5.15.90.1`
+    ///
+    /// x86 的 bzImage 引导头位于 0x200，0x202 处为魔数 `HdrS`，0x20E 处的 u16 为版本字符串相对 0x200 的偏移；
+    /// arm64 的 Image 头中没有版本字符串，退回到在未压缩的镜像中查找 `Linux version ` 横幅
+    pub fn parse_kernel_image_version(image: &[u8]) -> Option<String> {
+        let release = if image.get(0x202..0x206) == Some(b"HdrS".as_slice()) {
+            let offset = u16::from_le_bytes(image.get(0x20E..0x210)?.try_into().ok()?) as usize;
+            let start = offset + 0x200;
+            let end = image.get(start..)?.iter().position(|&it| it == 0)? + start;
+            &image[start..end]
+        } else {
+            const BANNER: &[u8] = b"Linux version ";
+            image
+                .windows(BANNER.len())
+                .enumerate()
+                .filter(|(_, it)| *it == BANNER)
+                .find_map(|(index, _)| {
+                    let rest = &image[index + BANNER.len()..];
+                    rest.first().filter(|it| it.is_ascii_digit())?;
+                    let end = rest.iter().position(|&it| it == b' ' || it == 0)?;
+                    Some(&rest[..end])
+                })?
+        };
+        let release = std::str::from_utf8(release).ok()?.split(' ').next()?;
+        let version = release.split('-').next()?;
+        (!version.is_empty()).then(|| version.to_string())
+    }
+
+    fn program_files() -> std::path::PathBuf {
+        std::env::var("ProgramFiles")
+            .unwrap_or_else(|_| r"C:\Program Files".to_string())
+            .into()
+    }
 }
 
 pub mod hypervisor {
     use super::*;
 
-    pub fn check_hyperv_via_wmi() -> Result<bool, String> {
+    pub fn check_hyperv_via_wmi() -> Result<bool, WindowsFeatureError> {
         // 构建 WMI 查询
         let query = "SELECT Name, InstallState FROM Win32_OptionalFeature WHERE Name = 'Microsoft-Hyper-V-All'";
 
@@ -139,7 +505,7 @@ pub mod hypervisor {
         }
     }
 
-    pub fn check_hyperv_via_service() -> Result<bool, Box<dyn std::error::Error>> {
+    pub fn check_hyperv_via_service() -> Result<bool, WindowsFeatureError> {
         use windows_service::service::{ServiceAccess, ServiceState};
         use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
 
@@ -148,6 +514,601 @@ pub mod hypervisor {
         let status = service.query_status()?;
         Ok(status.current_state == ServiceState::Running)
     }
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename = "Msvm_VirtualEthernetSwitch")]
+    #[serde(rename_all = "PascalCase")]
+    struct VirtualEthernetSwitch {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    /// 检查 Default Switch 是否存在，未安装 Hyper-V 时命名空间不存在，返回 `Ok(None)`
+    ///
+    /// Default Switch 的 `Name` 固定为 `C08CB7B8-9B3C-408E-8E30-5E16A3AEB444`，`ElementName` 会随系统语言变化
+    pub fn check_default_switch_via_wmi() -> Result<Option<bool>, WindowsFeatureError> {
+        let query = "SELECT Name FROM Msvm_VirtualEthernetSwitch WHERE Name = 'C08CB7B8-9B3C-408E-8E30-5E16A3AEB444'";
+        let results: Option<Vec<VirtualEthernetSwitch>> =
+            execute_wmi_query_in_namespace(r"ROOT\virtualization\v2", query)?;
+        Ok(results.map(|switches| !switches.is_empty()))
+    }
+
+    /// 检查 Hyper-V PowerShell 模块对应的可选功能是否已启用
+    pub fn check_powershell_module_via_wmi() -> Result<bool, WindowsFeatureError> {
+        let query = "SELECT Name, InstallState FROM Win32_OptionalFeature WHERE Name = 'Microsoft-Hyper-V-Management-PowerShell'";
+        let results: Vec<OptionalFeature> = execute_wmi_query(query)?;
+        Ok(results.first().is_some_and(|it| it.install_state == 1))
+    }
+
+    /// 检查 `%WINDIR%\System32\WindowsPowerShell\v1.0\Modules\Hyper-V` 目录是否存在
+    pub fn check_powershell_module_via_dir() -> bool {
+        let windir = std::env::var("WINDIR").unwrap_or_else(|_| r"C:\Windows".to_string());
+        std::path::Path::new(&windir)
+            .join(r"System32\WindowsPowerShell\v1.0\Modules\Hyper-V")
+            .is_dir()
+    }
+}
+
+pub mod conflicts {
+    use super::*;
+
+    /// 与 Hyper-V 冲突的虚拟化产品的驱动服务 (服务名, 产品名)
+    const COMPETING_SERVICES: &[(&str, &str)] = &[
+        ("VBoxSup", "VirtualBox"),
+        ("VBoxDrv", "VirtualBox"),
+        ("vmx86", "VMware Workstation/Player"),
+        ("IntelHaxm", "Intel HAXM"),
+    ];
+
+    /// 检查 Windows 虚拟机监控程序平台 (WHPX) 可选功能是否已启用
+    pub fn check_whpx_via_wmi() -> Result<bool, WindowsFeatureError> {
+        let query = "SELECT Name, InstallState FROM Win32_OptionalFeature WHERE Name = 'HypervisorPlatform'";
+        let results: Vec<OptionalFeature> = execute_wmi_query(query)?;
+        Ok(results.first().is_some_and(|it| it.install_state == 1))
+    }
+
+    /// 列出已安装的冲突产品，返回 (服务名, 产品名, 是否正在运行)
+    pub fn list_competing_services() -> Vec<(&'static str, &'static str, bool)> {
+        use windows_service::service::{ServiceAccess, ServiceState};
+        use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+        let Ok(manager) =
+            ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        else {
+            return vec![];
+        };
+        COMPETING_SERVICES
+            .iter()
+            .filter_map(|&(service_name, product)| {
+                let service = manager
+                    .open_service(service_name, ServiceAccess::QUERY_STATUS)
+                    .ok()?;
+                let running = service
+                    .query_status()
+                    .is_ok_and(|status| status.current_state == ServiceState::Running);
+                Some((service_name, product, running))
+            })
+            .collect()
+    }
+}
+
+pub mod gpu {
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename = "Msvm_PartitionableGpu")]
+    #[serde(rename_all = "PascalCase")]
+    struct PartitionableGpu {
+        name: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename = "Win32_VideoController")]
+    #[serde(rename_all = "PascalCase")]
+    struct VideoController {
+        name: Option<String>,
+        driver_version: Option<String>,
+        #[serde(rename = "PNPDeviceID")]
+        pnp_device_id: Option<String>,
+    }
+
+    /// 检查 Host Compute Service (vmcompute) 是否存在，返回是否正在运行
+    ///
+    /// 该服务按需启动，存在即表示支持，未运行并不代表不可用
+    pub fn check_vmcompute_via_service() -> Result<bool, WindowsFeatureError> {
+        use windows_service::service::{ServiceAccess, ServiceState};
+        use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+        let service = manager.open_service("vmcompute", ServiceAccess::QUERY_STATUS)?;
+        let status = service.query_status()?;
+        Ok(status.current_state == ServiceState::Running)
+    }
+
+    /// 查询支持分区 (GPU-PV) 的显卡，未安装 Hyper-V 时命名空间不存在，返回 `Ok(None)`
+    ///
+    /// 需要管理员或 Hyper-V Administrators 组权限
+    pub fn list_partitionable_gpus() -> Result<Option<Vec<String>>, WindowsFeatureError> {
+        let query = "SELECT Name FROM Msvm_PartitionableGpu";
+        let results: Option<Vec<PartitionableGpu>> =
+            execute_wmi_query_in_namespace(r"ROOT\virtualization\v2", query)?;
+        Ok(results.map(|gpus| gpus.into_iter().map(|it| it.name).collect()))
+    }
+
+    /// 从驱动版本号推断 WDDM 版本，如 `31.0.15.3179` 为 WDDM 3.1
+    ///
+    /// Windows 驱动版本的第一段为 WDDM 版本乘以 10，早于 WDDM 2.0 的驱动不遵循该规则，返回 `None`
+    pub fn parse_wddm_version(driver_version: &str) -> Option<(u32, u32)> {
+        let major = driver_version.split('.').next()?.parse::<u32>().ok()?;
+        (major >= 20).then_some((major / 10, major % 10))
+    }
+
+    /// 将 `D3DKMT_DRIVERVERSION` 转换为 WDDM 版本，如 `KMT_DRIVERVERSION_WDDM_2_9` (2900) 为 (2, 9)
+    pub fn wddm_version_from_kmt(version: i32) -> WddmVersion {
+        let version = version.max(0) as u32;
+        (version / 1000, version % 1000 / 100)
+    }
+
+    /// WDDM 版本 (主版本, 次版本)
+    pub type WddmVersion = (u32, u32);
+
+    /// 通过 D3DKMT 查询各显卡适配器的 (名称, WDDM 版本)，包括 Microsoft Basic Render Driver
+    ///
+    /// 这里是内核图形子系统报告的驱动模型版本，不依赖驱动版本号的命名规则
+    pub fn list_wddm_versions() -> Result<Vec<(String, WddmVersion)>, WindowsFeatureError> {
+        use windows::Wdk::Graphics::Direct3D::{
+            D3DKMT_ADAPTERINFO, D3DKMT_ADAPTERREGISTRYINFO, D3DKMT_CLOSEADAPTER,
+            D3DKMT_DRIVERVERSION, D3DKMT_ENUMADAPTERS2, D3DKMT_QUERYADAPTERINFO,
+            D3DKMTCloseAdapter, D3DKMTEnumAdapters2, D3DKMTQueryAdapterInfo,
+            KMTQAITYPE_ADAPTERREGISTRYINFO, KMTQAITYPE_DRIVERVERSION, KMTQUERYADAPTERINFOTYPE,
+        };
+
+        fn query<T>(
+            adapter: u32,
+            kind: KMTQUERYADAPTERINFOTYPE,
+            data: &mut T,
+        ) -> windows::core::Result<()> {
+            let mut info = D3DKMT_QUERYADAPTERINFO {
+                hAdapter: adapter,
+                Type: kind,
+                pPrivateDriverData: (data as *mut T).cast(),
+                PrivateDriverDataSize: size_of::<T>() as u32,
+            };
+            unsafe { D3DKMTQueryAdapterInfo(&mut info) }.ok()
+        }
+
+        // 第一次调用只获取适配器数量
+        let mut enumerate = D3DKMT_ENUMADAPTERS2::default();
+        unsafe { D3DKMTEnumAdapters2(&mut enumerate) }
+            .ok()
+            .map_err(WindowsFeatureError::Win32)?;
+        let mut adapters = vec![D3DKMT_ADAPTERINFO::default(); enumerate.NumAdapters as usize];
+        enumerate.pAdapters = adapters.as_mut_ptr();
+        unsafe { D3DKMTEnumAdapters2(&mut enumerate) }
+            .ok()
+            .map_err(WindowsFeatureError::Win32)?;
+        adapters.truncate(enumerate.NumAdapters as usize);
+
+        // 先逐个查询并关闭所有句柄，再返回第一个错误
+        let results: Vec<_> = adapters
+            .iter()
+            .map(|adapter| {
+                let mut version = D3DKMT_DRIVERVERSION::default();
+                let mut registry = D3DKMT_ADAPTERREGISTRYINFO::default();
+                let result = query(adapter.hAdapter, KMTQAITYPE_DRIVERVERSION, &mut version)
+                    .and_then(|()| {
+                        query(
+                            adapter.hAdapter,
+                            KMTQAITYPE_ADAPTERREGISTRYINFO,
+                            &mut registry,
+                        )
+                    });
+                let close = D3DKMT_CLOSEADAPTER {
+                    hAdapter: adapter.hAdapter,
+                };
+                let _ = unsafe { D3DKMTCloseAdapter(&close) };
+                result.map(|()| {
+                    let name = &registry.AdapterString;
+                    let end = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+                    (
+                        String::from_utf16_lossy(&name[..end]),
+                        wddm_version_from_kmt(version.0),
+                    )
+                })
+            })
+            .collect();
+        results
+            .into_iter()
+            .collect::<windows::core::Result<_>>()
+            .map_err(WindowsFeatureError::Win32)
+    }
+
+    /// 查询显卡名称及驱动版本，返回 (名称, 驱动版本)
+    pub fn list_video_controllers() -> Result<Vec<(String, String)>, WindowsFeatureError> {
+        Ok(list_video_adapters()?
+            .into_iter()
+            .map(|(name, driver_version, _)| (name, driver_version))
+            .collect())
+    }
+
+    /// 查询显卡，返回 (名称, 驱动版本, PNPDeviceID)
+    pub fn list_video_adapters() -> Result<Vec<(String, String, String)>, WindowsFeatureError> {
+        let query = "SELECT Name, DriverVersion, PNPDeviceID FROM Win32_VideoController";
+        let results: Vec<VideoController> = execute_wmi_query(query)?;
+        Ok(results
+            .into_iter()
+            .map(|it| {
+                (
+                    it.name.unwrap_or_default(),
+                    it.driver_version.unwrap_or_default(),
+                    it.pnp_device_id.unwrap_or_default(),
+                )
+            })
+            .collect())
+    }
+}
+
+pub mod sriov {
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename = "MSFT_NetAdapterSriovSettingData")]
+    #[serde(rename_all = "PascalCase")]
+    struct NetAdapterSriovSettingData {
+        name: String,
+        // SriovSupport: 0 = Unknown, 1 = Supported, 其它值表示不支持的原因
+        sriov_support: Option<u32>,
+        enabled: Option<bool>,
+    }
+
+    /// 查询支持或已启用 SR-IOV 的网卡名称，没有支持的网卡时该类不存在，返回空列表
+    pub fn list_sriov_nics_via_wmi() -> Result<Vec<String>, WindowsFeatureError> {
+        let query = "SELECT Name, SriovSupport, Enabled FROM MSFT_NetAdapterSriovSettingData";
+        let results: Option<Vec<NetAdapterSriovSettingData>> =
+            execute_wmi_query_in_namespace(r"ROOT\StandardCimv2", query)?;
+        Ok(results
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|it| it.sriov_support == Some(1) || it.enabled == Some(true))
+            .map(|it| it.name)
+            .collect())
+    }
+}
+
+pub mod core_isolation {
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename = "Win32_DeviceGuard")]
+    #[serde(rename_all = "PascalCase")]
+    struct DeviceGuard {
+        // 1 = Credential Guard, 2 = HVCI
+        security_services_running: Option<Vec<u32>>,
+    }
+
+    /// 读取 `DeviceGuard\Scenarios` 下某个场景的配置，返回 (Enabled, WasEnabledBy)，键不存在时返回 `None`
+    pub fn read_scenario_via_reg(scenario: &str) -> Option<(bool, Option<u32>)> {
+        use winreg::RegKey;
+        use winreg::enums::HKEY_LOCAL_MACHINE;
+        let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey(format!(
+                r"SYSTEM\CurrentControlSet\Control\DeviceGuard\Scenarios\{scenario}"
+            ))
+            .ok()?;
+        let enabled = key.get_value::<u32, _>("Enabled").unwrap_or(0) == 1;
+        let was_enabled_by = key.get_value::<u32, _>("WasEnabledBy").ok();
+        Some((enabled, was_enabled_by))
+    }
+
+    /// 查询正在运行的安全服务，返回 (Credential Guard, HVCI)，系统不支持 VBS 时命名空间不存在，返回 `Ok(None)`
+    pub fn check_running_via_wmi() -> Result<Option<(bool, bool)>, WindowsFeatureError> {
+        let query = "SELECT SecurityServicesRunning FROM Win32_DeviceGuard";
+        let results: Option<Vec<DeviceGuard>> =
+            execute_wmi_query_in_namespace(r"ROOT\Microsoft\Windows\DeviceGuard", query)?;
+        Ok(results.map(|results| {
+            let running = results
+                .into_iter()
+                .next()
+                .and_then(|it| it.security_services_running)
+                .unwrap_or_default();
+            (running.contains(&1), running.contains(&2))
+        }))
+    }
+}
+
+pub mod memory {
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename = "Win32_OperatingSystem")]
+    #[serde(rename_all = "PascalCase")]
+    struct OperatingSystem {
+        // 单位均为 KB
+        total_visible_memory_size: Option<u64>,
+        free_physical_memory: Option<u64>,
+    }
+
+    /// 查询 (物理内存总量, 可用内存) MB
+    ///
+    /// `TotalVisibleMemorySize` 不包含固件和硬件保留的部分，略小于安装的内存容量
+    pub fn get_memory_via_wmi() -> Result<(u64, u64), WindowsFeatureError> {
+        let query = "SELECT TotalVisibleMemorySize, FreePhysicalMemory FROM Win32_OperatingSystem";
+        let results: Vec<OperatingSystem> = execute_wmi_query(query)?;
+        let os = results
+            .into_iter()
+            .next()
+            .ok_or(WindowsFeatureError::NoData("Win32_OperatingSystem"))?;
+        Ok((
+            os.total_visible_memory_size.unwrap_or(0) / 1024,
+            os.free_physical_memory.unwrap_or(0) / 1024,
+        ))
+    }
+
+    /// 查询主板 SMBIOS 报告的已安装内存容量 (MB)，包含被固件和硬件保留的部分
+    pub fn get_installed_memory_mb() -> Option<u64> {
+        use windows::Win32::System::SystemInformation::GetPhysicallyInstalledSystemMemory;
+        let mut kilobytes = 0u64;
+        unsafe { GetPhysicallyInstalledSystemMemory(&mut kilobytes) }.ok()?;
+        Some(kilobytes / 1024)
+    }
+}
+
+pub mod isolation {
+    /// 检查当前进程是否加载了 `SbieDll.dll`，Sandboxie 会将其注入沙盒中的每个进程
+    pub fn check_sandboxie_dll() -> bool {
+        use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+        use windows::core::w;
+        unsafe { GetModuleHandleW(w!("SbieDll.dll")) }.is_ok()
+    }
+
+    /// 查询 Sandboxie 的驱动 (`SbieDrv`) 或服务 (`SbieSvc`) 是否正在运行，未安装时返回错误
+    pub fn check_sandboxie_via_service(name: &str) -> Result<bool, super::WindowsFeatureError> {
+        use windows_service::service::{ServiceAccess, ServiceState};
+        use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+        let service = manager.open_service(name, ServiceAccess::QUERY_STATUS)?;
+        let status = service.query_status()?;
+        Ok(status.current_state == ServiceState::Running)
+    }
+}
+
+pub mod hgs {
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename = "MSFT_HgsClientConfiguration")]
+    #[serde(rename_all = "PascalCase")]
+    struct HgsClientConfiguration {
+        is_host_guarded: Option<bool>,
+        attestation_server_url: Option<String>,
+    }
+
+    /// 检查 Host Guardian Hyper-V Support 可选功能是否已启用
+    pub fn check_host_guardian_via_wmi() -> Result<bool, WindowsFeatureError> {
+        let query =
+            "SELECT Name, InstallState FROM Win32_OptionalFeature WHERE Name = 'HostGuardian'";
+        let results: Vec<OptionalFeature> = execute_wmi_query(query)?;
+        Ok(results.first().is_some_and(|it| it.install_state == 1))
+    }
+
+    /// 查询 HGS 客户端配置，返回 (是否为受保护主机, 证明服务地址)，未安装 HGS 客户端时返回 `Ok(None)`
+    pub fn check_hgs_client_via_wmi() -> Result<Option<(bool, Option<String>)>, WindowsFeatureError>
+    {
+        let query = "SELECT IsHostGuarded, AttestationServerUrl FROM MSFT_HgsClientConfiguration";
+        let results: Option<Vec<HgsClientConfiguration>> =
+            execute_wmi_query_in_namespace(r"ROOT\Microsoft\Windows\Hgs", query)?;
+        Ok(results.map(|results| {
+            results
+                .into_iter()
+                .next()
+                .map(|it| {
+                    (
+                        it.is_host_guarded.unwrap_or(false),
+                        it.attestation_server_url.filter(|url| !url.is_empty()),
+                    )
+                })
+                .unwrap_or((false, None))
+        }))
+    }
+}
+
+pub mod dism {
+    // DISM 在线映像 API，用于启用可选功能，调用前需要管理员权限
+    //
+    // `windows` crate 没有提供 DismApi 的绑定，因此在运行时从 System32 加载 `dismapi.dll`
+    use super::WindowsFeatureError;
+    use std::ffi::c_void;
+    use std::ptr;
+    use windows::Win32::Foundation::{CloseHandle, FreeLibrary, HANDLE, HMODULE};
+    use windows::Win32::System::LibraryLoader::{
+        GetProcAddress, LOAD_LIBRARY_SEARCH_SYSTEM32, LoadLibraryExW,
+    };
+    use windows::core::{HRESULT, HSTRING, PCSTR, PCWSTR, s, w};
+
+    /// `DISM_ONLINE_IMAGE`，表示当前正在运行的系统
+    const DISM_ONLINE_IMAGE: PCWSTR = w!("DISM_{53BFAE52-B167-4E2F-A258-0A37B57FF845}");
+    const DISM_LOG_ERRORS: i32 = 0;
+    const DISM_PACKAGE_NONE: i32 = 0;
+    /// 功能已启用，但需要重启计算机才能生效
+    const ERROR_SUCCESS_REBOOT_REQUIRED: u32 = 3010;
+
+    type DismSession = u32;
+    type DismInitializeFn = unsafe extern "system" fn(i32, PCWSTR, PCWSTR) -> HRESULT;
+    type DismOpenSessionFn =
+        unsafe extern "system" fn(PCWSTR, PCWSTR, PCWSTR, *mut DismSession) -> HRESULT;
+    type DismEnableFeatureFn = unsafe extern "system" fn(
+        DismSession,
+        PCWSTR,
+        PCWSTR,
+        i32,
+        i32,
+        *const PCWSTR,
+        u32,
+        i32,
+        HANDLE,
+        *const c_void,
+        *const c_void,
+    ) -> HRESULT;
+    type DismCloseSessionFn = unsafe extern "system" fn(DismSession) -> HRESULT;
+    type DismShutdownFn = unsafe extern "system" fn() -> HRESULT;
+
+    struct DismApi {
+        module: HMODULE,
+        initialize: DismInitializeFn,
+        open_session: DismOpenSessionFn,
+        enable_feature: DismEnableFeatureFn,
+        close_session: DismCloseSessionFn,
+        shutdown: DismShutdownFn,
+    }
+
+    impl DismApi {
+        fn load() -> Result<Self, WindowsFeatureError> {
+            let module =
+                unsafe { LoadLibraryExW(w!("dismapi.dll"), None, LOAD_LIBRARY_SEARCH_SYSTEM32) }
+                    .map_err(|err| {
+                        WindowsFeatureError::Dism(format!(
+                            "加载 dismapi.dll 失败: {}",
+                            err.message()
+                        ))
+                    })?;
+            let api = (|| -> Result<Self, WindowsFeatureError> {
+                Ok(DismApi {
+                    module,
+                    initialize: unsafe { proc_address(module, s!("DismInitialize"))? },
+                    open_session: unsafe { proc_address(module, s!("DismOpenSession"))? },
+                    enable_feature: unsafe { proc_address(module, s!("DismEnableFeature"))? },
+                    close_session: unsafe { proc_address(module, s!("DismCloseSession"))? },
+                    shutdown: unsafe { proc_address(module, s!("DismShutdown"))? },
+                })
+            })();
+            if api.is_err() {
+                let _ = unsafe { FreeLibrary(module) };
+            }
+            api
+        }
+    }
+
+    impl Drop for DismApi {
+        fn drop(&mut self) {
+            let _ = unsafe { FreeLibrary(self.module) };
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `T` 必须是与导出函数签名一致的函数指针类型
+    unsafe fn proc_address<T>(module: HMODULE, name: PCSTR) -> Result<T, WindowsFeatureError> {
+        let address = unsafe { GetProcAddress(module, name) }.ok_or_else(|| {
+            WindowsFeatureError::Dism(format!("dismapi.dll 缺少导出函数 {}", unsafe {
+                name.to_string().unwrap_or_default()
+            }))
+        })?;
+        Ok(unsafe { std::mem::transmute_copy(&address) })
+    }
+
+    fn hresult_to_string(hr: HRESULT) -> String {
+        format!("{} ({:#010X})", hr.message(), hr.0)
+    }
+
+    /// 当前进程是否以管理员身份 (UAC 提权后) 运行，查询失败时视为未提权
+    pub fn is_elevated() -> bool {
+        use windows::Win32::Security::{
+            GetTokenInformation, TOKEN_ELEVATION, TOKEN_QUERY, TokenElevation,
+        };
+        use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+        let mut token = HANDLE::default();
+        if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) }.is_err() {
+            return false;
+        }
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut size = 0;
+        let result = unsafe {
+            GetTokenInformation(
+                token,
+                TokenElevation,
+                Some(&mut elevation as *mut TOKEN_ELEVATION as *mut c_void),
+                size_of::<TOKEN_ELEVATION>() as u32,
+                &mut size,
+            )
+        };
+        let _ = unsafe { CloseHandle(token) };
+        result.is_ok() && elevation.TokenIsElevated != 0
+    }
+
+    /// 单个功能的启用结果，成功时为是否需要重启，失败时为错误信息
+    pub type EnableResult<'a> = (&'a str, Result<bool, String>);
+
+    /// 在当前系统上依次启用可选功能，同时启用其依赖的父功能
+    ///
+    /// 外层错误表示 DISM 本身不可用
+    pub fn enable_features<'a>(
+        names: &[&'a str],
+    ) -> Result<Vec<EnableResult<'a>>, WindowsFeatureError> {
+        let api = DismApi::load()?;
+        unsafe { (api.initialize)(DISM_LOG_ERRORS, PCWSTR::null(), PCWSTR::null()) }
+            .ok()
+            .map_err(|err| {
+                WindowsFeatureError::Dism(format!(
+                    "DismInitialize 失败: {}",
+                    hresult_to_string(err.code())
+                ))
+            })?;
+
+        let mut session = 0;
+        let result = unsafe {
+            (api.open_session)(
+                DISM_ONLINE_IMAGE,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                &mut session,
+            )
+        }
+        .ok()
+        .map_err(|err| {
+            WindowsFeatureError::Dism(format!(
+                "DismOpenSession 失败: {}",
+                hresult_to_string(err.code())
+            ))
+        })
+        .map(|()| {
+            let results = names
+                .iter()
+                .map(|&name| {
+                    let feature = HSTRING::from(name);
+                    let hr = unsafe {
+                        (api.enable_feature)(
+                            session,
+                            PCWSTR(feature.as_ptr()),
+                            PCWSTR::null(),
+                            DISM_PACKAGE_NONE,
+                            0,
+                            ptr::null(),
+                            0,
+                            1,
+                            HANDLE::default(),
+                            ptr::null(),
+                            ptr::null(),
+                        )
+                    };
+                    let result = if hr.0 as u32 == ERROR_SUCCESS_REBOOT_REQUIRED
+                        || hr == HRESULT::from_win32(ERROR_SUCCESS_REBOOT_REQUIRED)
+                    {
+                        Ok(true)
+                    } else if hr.is_ok() {
+                        Ok(false)
+                    } else {
+                        Err(hresult_to_string(hr))
+                    };
+                    (name, result)
+                })
+                .collect();
+            let _ = unsafe { (api.close_session)(session) };
+            results
+        });
+        let _ = unsafe { (api.shutdown)() };
+        result
+    }
 }
 
 #[cfg(test)]
@@ -172,4 +1133,64 @@ mod tests {
     fn test_check_wsl_via_service() {
         println!("{:?}", wsl::check_wsl_via_service().unwrap());
     }
+
+    #[test]
+    fn test_parse_wslconfig_kernel() {
+        let config = "[wsl2]\nmemory=4GB\nkernel=C:\\\\temp\\\\bzImage\n";
+        assert_eq!(
+            wsl::parse_wslconfig_kernel(config).as_deref(),
+            Some(r"C:\temp\bzImage")
+        );
+        assert_eq!(
+            wsl::parse_wslconfig_kernel("[experimental]\nkernel=x\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_kernel_image_version() {
+        let mut image = vec![0u8; 0x400];
+        image[0x202..0x206].copy_from_slice(b"HdrS");
+        image[0x20E..0x210].copy_from_slice(&0x100u16.to_le_bytes());
+        let release = b"5.15.90.1-microsoft-standard-WSL2 (root@host) #1 SMP\0";
+        image[0x300..0x300 + release.len()].copy_from_slice(release);
+        assert_eq!(
+            wsl::parse_kernel_image_version(&image).as_deref(),
+            Some("5.15.90.1")
+        );
+        assert_eq!(wsl::parse_kernel_image_version(&[0u8; 0x100]), None);
+
+        let mut image = vec![0u8; 0x400];
+        image[0x38..0x3C].copy_from_slice(b"ARM\x64");
+        let banner = b"Linux version 5.15.153.1-microsoft-standard-WSL2 (root@host) #1 SMP\n\0";
+        image[0x300..0x300 + banner.len()].copy_from_slice(banner);
+        assert_eq!(
+            wsl::parse_kernel_image_version(&image).as_deref(),
+            Some("5.15.153.1")
+        );
+    }
+
+    #[test]
+    fn test_edition_supports_hyperv() {
+        assert!(version::edition_supports_hyperv("Professional"));
+        assert!(version::edition_supports_hyperv("Enterprise"));
+        assert!(version::edition_supports_hyperv("ServerDatacenter"));
+        assert!(!version::edition_supports_hyperv("Core"));
+        assert!(!version::edition_supports_hyperv("CoreSingleLanguage"));
+    }
+
+    #[test]
+    fn test_parse_wddm_version() {
+        assert_eq!(gpu::parse_wddm_version("31.0.15.3179"), Some((3, 1)));
+        assert_eq!(gpu::parse_wddm_version("27.20.100.8681"), Some((2, 7)));
+        assert_eq!(gpu::parse_wddm_version("10.18.13.6881"), None);
+        assert_eq!(gpu::parse_wddm_version(""), None);
+    }
+
+    #[test]
+    fn test_wddm_version_from_kmt() {
+        assert_eq!(gpu::wddm_version_from_kmt(2900), (2, 9));
+        assert_eq!(gpu::wddm_version_from_kmt(3100), (3, 1));
+        assert_eq!(gpu::wddm_version_from_kmt(1105), (1, 1));
+    }
 }
@@ -0,0 +1,266 @@
+#![cfg(target_os = "windows")]
+//! 共享的 WMI 执行线程
+//!
+//! Node 的主线程可能已经以 STA 模式初始化了 COM，直接查询 WMI 会失败，因此所有查询都投递到
+//! 一组独立的 MTA 线程中执行。每个线程只初始化一次 COM，并按命名空间缓存 `WMIConnection`，
+//! 避免每次查询都创建线程和重新连接
+use std::collections::HashMap;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+pub const ROOT_CIMV2: &str = r"ROOT\CIMV2";
+
+/// [`run`] 的超时时间，WMI 存储库损坏时 COM 调用可能一直阻塞；
+/// 首次枚举 `Win32_OptionalFeature` 等类可能需要十几秒，因此比 Machine ID 单个查询的超时宽松
+#[cfg(feature = "features")]
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+type Job = Box<dyn FnOnce(&mut WmiContext) + Send>;
+
+#[derive(Debug)]
+pub enum ExecutorError {
+    /// 执行线程已退出，无法提交任务
+    Send(String),
+    /// 执行线程在返回结果前退出
+    Recv(String),
+    /// 任务执行过程中发生 panic
+    Panicked(String),
+    /// 任务在指定时间内没有返回，如 WMI 存储库损坏导致 COM 调用一直阻塞
+    Timeout(Duration),
+    /// 被丢弃但仍阻塞的线程过多，不再创建新的线程组，值为仍在运行的线程数
+    Exhausted(usize),
+}
+
+impl std::fmt::Display for ExecutorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutorError::Send(msg) => write!(f, "Failed to submit WMI job: {}", msg),
+            ExecutorError::Recv(msg) => write!(f, "Failed to receive WMI result: {}", msg),
+            ExecutorError::Panicked(msg) => write!(f, "WMI job panicked: {}", msg),
+            ExecutorError::Timeout(timeout) => {
+                write!(f, "WMI job timed out after {}ms", timeout.as_millis())
+            }
+            ExecutorError::Exhausted(live) => write!(
+                f,
+                "Too many blocked WMI executor threads ({} still running)",
+                live
+            ),
+        }
+    }
+}
+
+/// 执行线程内的状态，只能在任务闭包中访问
+pub struct WmiContext {
+    com_lib: Option<wmi::COMLibrary>,
+    connections: HashMap<String, wmi::WMIConnection>,
+}
+
+impl WmiContext {
+    /// 获取指定命名空间的连接，首次使用时建立并缓存，命名空间不区分大小写
+    ///
+    /// COM 初始化失败时不会缓存错误，下一次调用会重新尝试
+    pub fn connection(&mut self, namespace: &str) -> Result<&wmi::WMIConnection, wmi::WMIError> {
+        let com_lib = match self.com_lib {
+            Some(com_lib) => com_lib,
+            None => *self.com_lib.insert(wmi::COMLibrary::new()?),
+        };
+        let key = namespace.to_ascii_uppercase();
+        if !self.connections.contains_key(&key) {
+            let connection = wmi::WMIConnection::with_namespace_path(namespace, com_lib)?;
+            self.connections.insert(key.clone(), connection);
+        }
+        Ok(&self.connections[&key])
+    }
+}
+
+/// 执行线程的数量，相互独立的查询 (如 Machine ID 的各个因子) 可以同时执行
+const WORKER_COUNT: usize = 4;
+
+/// 同时存活的执行线程上限，包括被丢弃但仍阻塞在 COM 调用中的线程
+///
+/// 阻塞的线程无法被强制结束，WMI 持续不可用时每次超时都会留下一组线程，达到上限后直接返回
+/// [`ExecutorError::Exhausted`]，不再继续创建
+const MAX_LIVE_WORKERS: usize = WORKER_COUNT * 4;
+
+/// 一组共享任务队列的执行线程
+#[derive(Clone)]
+struct Executor {
+    /// 代数，超时后丢弃对应的线程组并在下一次提交时重新创建
+    generation: u64,
+    tx: Sender<Job>,
+    /// 该线程组已完成的任务数，用于区分排队很久的任务与阻塞的线程组
+    completed: Arc<AtomicU64>,
+}
+
+static EXECUTOR: Mutex<Option<Executor>> = Mutex::new(None);
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+/// 当前存活的执行线程数，线程退出时减少
+static LIVE_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+/// 返回当前的线程组，尚未创建或已被丢弃时创建新的线程组
+///
+/// 每个线程各自初始化 COM 并缓存连接，`WMIConnection` 不会跨线程使用
+fn executor() -> Result<Executor, ExecutorError> {
+    let mut executor = EXECUTOR.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(executor) = executor.as_ref() {
+        return Ok(executor.clone());
+    }
+    // 只在持有锁时创建线程，读取后到创建前不会有其它线程组加入
+    let live = LIVE_WORKERS.load(Ordering::Relaxed);
+    if live + WORKER_COUNT > MAX_LIVE_WORKERS {
+        return Err(ExecutorError::Exhausted(live));
+    }
+    let (tx, rx) = channel::<Job>();
+    let rx = Arc::new(Mutex::new(rx));
+    let completed = Arc::new(AtomicU64::new(0));
+    for index in 0..WORKER_COUNT {
+        let rx = rx.clone();
+        let completed = completed.clone();
+        LIVE_WORKERS.fetch_add(1, Ordering::Relaxed);
+        let spawned = std::thread::Builder::new()
+            .name(format!("virt-detect-wmi-{index}"))
+            .spawn(move || {
+                let mut context = WmiContext {
+                    com_lib: None,
+                    connections: HashMap::new(),
+                };
+                // 发送端全部释放后 (包括被 `abandon` 丢弃) 执行完排队的任务并退出
+                loop {
+                    let job = rx.lock().unwrap_or_else(PoisonError::into_inner).recv();
+                    match job {
+                        Ok(job) => {
+                            job(&mut context);
+                            completed.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(_) => break,
+                    }
+                }
+                LIVE_WORKERS.fetch_sub(1, Ordering::Relaxed);
+            });
+        if let Err(err) = spawned {
+            LIVE_WORKERS.fetch_sub(1, Ordering::Relaxed);
+            // 已创建的线程在 `tx` 释放后自行退出
+            return Err(ExecutorError::Send(err.to_string()));
+        }
+    }
+    Ok(executor
+        .insert(Executor {
+            generation: GENERATION.fetch_add(1, Ordering::Relaxed),
+            tx,
+            completed,
+        })
+        .clone())
+}
+
+/// 丢弃阻塞的线程组，之后提交的任务由新的线程组执行，不再排在阻塞的任务之后
+///
+/// 阻塞在 COM 调用中的线程无法被强制结束，也不会被 join；调用返回后它发现发送端已释放，
+/// 执行完排队的任务后自行退出。一直不返回的线程会泄漏，数量受 [`MAX_LIVE_WORKERS`] 限制
+fn abandon(generation: u64) {
+    let mut executor = EXECUTOR.lock().unwrap_or_else(PoisonError::into_inner);
+    // 其它调用方可能已经丢弃并重新创建了线程组
+    if executor
+        .as_ref()
+        .is_some_and(|it| it.generation == generation)
+    {
+        *executor = None;
+    }
+}
+
+/// 执行线程发回的消息，开始执行时先发送 `Started`，超时从这时开始计算
+enum Event<R> {
+    Started,
+    Finished(Result<R, String>),
+}
+
+/// 已提交到执行线程的任务，通过 [`Pending::wait`] 取回结果
+pub struct Pending<R> {
+    generation: u64,
+    completed: Arc<AtomicU64>,
+    rx: Receiver<Event<R>>,
+}
+
+impl<R> Pending<R> {
+    /// 等待任务完成，任务开始执行后超过 `timeout` 没有返回时丢弃当前的线程组并返回 `Timeout`
+    ///
+    /// 排队的时间不计入 `timeout`：一次提交多个任务时，排在后面的任务在较慢的机器上可能等待很久，
+    /// 只要线程组仍在完成其它任务就继续等待；连续 `timeout` 没有任何任务完成时才认为线程组已阻塞
+    pub fn wait(self, timeout: Duration) -> Result<R, ExecutorError> {
+        let timed_out = || {
+            abandon(self.generation);
+            ExecutorError::Timeout(timeout)
+        };
+        let mut completed = self.completed.load(Ordering::Relaxed);
+        loop {
+            match self.rx.recv_timeout(timeout) {
+                Ok(Event::Started) => break,
+                Ok(Event::Finished(result)) => return result.map_err(ExecutorError::Panicked),
+                Err(RecvTimeoutError::Timeout) => {
+                    let now = self.completed.load(Ordering::Relaxed);
+                    if now == completed {
+                        return Err(timed_out());
+                    }
+                    completed = now;
+                }
+                Err(err @ RecvTimeoutError::Disconnected) => {
+                    return Err(ExecutorError::Recv(err.to_string()));
+                }
+            }
+        }
+        match self.rx.recv_timeout(timeout) {
+            Ok(Event::Finished(result)) => result.map_err(ExecutorError::Panicked),
+            Ok(Event::Started) => Err(ExecutorError::Recv(
+                "WMI job reported start twice".to_string(),
+            )),
+            Err(RecvTimeoutError::Timeout) => Err(timed_out()),
+            Err(err @ RecvTimeoutError::Disconnected) => Err(ExecutorError::Recv(err.to_string())),
+        }
+    }
+}
+
+/// 提交 `job` 但不等待，多个任务可以在不同的执行线程中同时运行
+pub fn spawn<R, F>(job: F) -> Result<Pending<R>, ExecutorError>
+where
+    R: Send + 'static,
+    F: FnOnce(&mut WmiContext) -> R + Send + 'static,
+{
+    let (tx_event, rx_event) = channel();
+    let executor = executor()?;
+    executor
+        .tx
+        .send(Box::new(move |context: &mut WmiContext| {
+            // 等待方已经放弃 (如提前返回了错误) 时不再执行
+            if tx_event.send(Event::Started).is_err() {
+                return;
+            }
+            let result = catch_unwind(AssertUnwindSafe(|| job(context)));
+            let _ = tx_event.send(Event::Finished(result.map_err(|err| {
+                if let Some(s) = err.downcast_ref::<String>() {
+                    s.clone()
+                } else if let Some(s) = err.downcast_ref::<&str>() {
+                    s.to_string()
+                } else {
+                    "Unknown panic in WMI job".to_string()
+                }
+            })));
+        }))
+        .map_err(|err| ExecutorError::Send(err.to_string()))?;
+    Ok(Pending {
+        generation: executor.generation,
+        completed: executor.completed,
+        rx: rx_event,
+    })
+}
+
+/// 在 WMI 执行线程中运行 `job` 并等待结果，最多等待 [`DEFAULT_TIMEOUT`]
+#[cfg(feature = "features")]
+pub fn run<R, F>(job: F) -> Result<R, ExecutorError>
+where
+    R: Send + 'static,
+    F: FnOnce(&mut WmiContext) -> R + Send + 'static,
+{
+    spawn(job)?.wait(DEFAULT_TIMEOUT)
+}
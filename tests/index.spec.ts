@@ -1,5 +1,5 @@
 import { expect, test, describe } from "vitest";
-import { getVirtualization, isWslEnabled, isHypervEnabled, getMachineId, MachineIdFactor } from "../index";
+import { getVirtualization, getVirtualizationAsync, setLanguage, Language, VirtStatus, isWslEnabled, isHypervEnabled, getMachineId, getMachineIdAsync, clearMachineIdCache, MachineIdFactor } from "../index";
 
 describe("Virtualization", () => {
   test("getVirtualization", () => {
@@ -8,6 +8,24 @@ describe("Virtualization", () => {
     expect(result.cpuSupported).toBeTypeOf("boolean");
     expect(result.osReportedEnabled).toBeTypeOf("boolean");
     expect(result.os).toBeOneOf(["windows", "linux", "macos"]);
+    const expected = result.cpuSupported
+      ? (result.osReportedEnabled ? VirtStatus.CpuAndOsEnabled : VirtStatus.CpuSupportedOsDisabled)
+      : (result.osReportedEnabled ? VirtStatus.CpuUnsupportedOsReportsEnabled : VirtStatus.CpuUnsupported);
+    expect(result.status).toBe(expected);
+  });
+  test("getVirtualizationAsync", async () => {
+    const result = await getVirtualizationAsync();
+    expect(result).toEqual(getVirtualization());
+  });
+  test("setLanguage", () => {
+    setLanguage(Language.En);
+    try {
+      const result = getVirtualization();
+      expect(result.overallStatusMessage).not.toMatch(/[\u4e00-\u9fff]/);
+      expect(result.osCheckDetails).not.toMatch(/[\u4e00-\u9fff]/);
+    } finally {
+      setLanguage(Language.ZhCn);
+    }
   });
 });
 
@@ -16,6 +34,7 @@ describe("WSL", () => {
     const result = isWslEnabled();
     expect(result).toBeDefined();
     expect(result.enabled).toBeTypeOf("boolean");
+    expect(result.checks.length).toBeGreaterThan(0);
     expect(result.enabled).toBeTruthy();
   });
 });
@@ -94,4 +113,16 @@ describe("MachineID", () => {
     expect(result.factors.find(it => it.startsWith('gpu'))).toBeUndefined();
     console.log(result)
   })
+  test("getMachineIdAsync", async () => {
+    const factors = [MachineIdFactor.Baseboard, MachineIdFactor.Processor, MachineIdFactor.DiskDrivers];
+    const result = await getMachineIdAsync(factors);
+    expect(result.machineId).toEqual(getMachineId(factors).machineId);
+  })
+  test("clearMachineIdCache", () => {
+    const factors = [MachineIdFactor.Baseboard, MachineIdFactor.Processor];
+    const cached = getMachineId(factors);
+    expect(getMachineId(factors, { salt: "app" }).factors).toEqual(cached.factors);
+    clearMachineIdCache();
+    expect(getMachineId(factors).machineId).toEqual(cached.machineId);
+  })
 })
\ No newline at end of file